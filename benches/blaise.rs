@@ -2,7 +2,7 @@ use blaise::{
     gtfs::GtfsReader,
     prelude::Repository,
     raptor::{Allocator, Location},
-    shared::{Coordinate, Time},
+    shared::{Coordinate, Distance, Time},
 };
 use criterion::{Criterion, criterion_group, criterion_main};
 use std::{env, hint::black_box, path::Path, time::Duration};
@@ -32,6 +32,43 @@ fn long_solve(repository: &Repository, allocator: &mut Allocator) {
             .solve_with_allocator(allocator),
     );
 }
+fn area_solve(repository: &Repository, allocator: &mut Allocator, from: &str, to: &str) {
+    let from = Location::Area(from.into());
+    let to = Location::Area(to.into());
+    let time = Time::from_seconds(28800);
+    allocator.reset();
+    let _ = black_box(
+        repository
+            .router(from, to)
+            .departure_at(time)
+            .solve_with_allocator(allocator),
+    );
+}
+
+fn stop_solve(repository: &Repository, allocator: &mut Allocator, from: &str, to: &str) {
+    let from = Location::Stop(from.into());
+    let to = Location::Stop(to.into());
+    let time = Time::from_seconds(28800);
+    allocator.reset();
+    let _ = black_box(
+        repository
+            .router(from, to)
+            .departure_at(time)
+            .solve_with_allocator(allocator),
+    );
+}
+
+/// Finds the closest served stop to a coordinate, so the stop and area benches
+/// cover the same trip as the short coordinate solve regardless of feed ids.
+fn closest_served_stop(repository: &Repository, coordinate: (f32, f32)) -> u32 {
+    repository
+        .stops_by_coordinate_sorted(&coordinate.into(), Distance::from_meters(1000.0))
+        .into_iter()
+        .find(|(stop, _)| repository.stop_idx_has_trips(stop.index))
+        .map(|(stop, _)| stop.index)
+        .expect("No served stop close to the bench coordinate")
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let gtfs_data_path = match env::var("GTFS_DATA_PATH") {
         Ok(path_str) => Path::new(&path_str).to_owned(),
@@ -50,6 +87,21 @@ fn criterion_benchmark(c: &mut Criterion) {
 
     let mut allocator = Allocator::new(&repository);
 
+    let from_stop = closest_served_stop(&repository, (59.370_136, 18.001_749));
+    let to_stop = closest_served_stop(&repository, (59.335_34, 18.057_737));
+    let from_stop_id = repository.stops[from_stop as usize].id.clone();
+    let to_stop_id = repository.stops[to_stop as usize].id.clone();
+    let area_id = |stop_idx: u32| {
+        let station_idx = repository.stops[stop_idx as usize]
+            .parent_index
+            .unwrap_or(stop_idx);
+        repository
+            .area_by_stop_idx(station_idx)
+            .map(|area| area.id.clone())
+    };
+    let from_area_id = area_id(from_stop);
+    let to_area_id = area_id(to_stop);
+
     let mut group = c.benchmark_group("Routing");
 
     group.warm_up_time(Duration::from_secs(10));
@@ -64,6 +116,16 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| long_solve(&repository, &mut allocator))
     });
 
+    group.bench_function("Stop solve", |b| {
+        b.iter(|| stop_solve(&repository, &mut allocator, &from_stop_id, &to_stop_id))
+    });
+
+    if let (Some(from_area_id), Some(to_area_id)) = (from_area_id, to_area_id) {
+        group.bench_function("Area solve", |b| {
+            b.iter(|| area_solve(&repository, &mut allocator, &from_area_id, &to_area_id))
+        });
+    }
+
     group.finish();
 }
 
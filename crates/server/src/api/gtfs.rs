@@ -4,12 +4,12 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
-use blaise::prelude::*;
-use futures_util::StreamExt;
-use reqwest::header::ACCEPT_ENCODING;
+use blaise::{
+    gtfs::download::{DownloadError, download},
+    prelude::*,
+};
 use std::{collections::HashMap, fs, path::Path, sync::Arc};
-use tokio::{fs::File, io::AsyncWriteExt};
-use tracing::error;
+use tracing::{error, info};
 
 pub async fn age(
     Query(_): Query<HashMap<String, String>>,
@@ -45,54 +45,34 @@ pub async fn fetch_url(
     State(state): State<Arc<AppState>>,
 ) -> Result<Response, StatusCode> {
     if let Some(q) = params.get("q") {
-        let response = reqwest::Client::new()
-            .get(q)
-            .header(ACCEPT_ENCODING, "gzip, deflate")
-            .send()
+        download(&reqwest::Client::new(), q, &state.gtfs_data_path)
             .await
             .map_err(|err| {
                 error!("Failed to fetch: {err}");
-                StatusCode::BAD_REQUEST
+                match err {
+                    DownloadError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                    _ => StatusCode::BAD_REQUEST,
+                }
             })?;
 
-        if !response.status().is_success() {
-            let body = response.text().await.unwrap_or_default();
-            error!("Response is not success: {body}");
-            return Err(StatusCode::BAD_REQUEST);
-        }
-
-        let mut file = File::create(&state.gtfs_data_path).await.map_err(|err| {
-            error!("Failed to create file: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-        let mut stream = response.bytes_stream();
-        while let Some(chunk) = stream.next().await {
-            let data = chunk.map_err(|err| {
-                error!("Failed to fetch chunk: {err}");
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-
-            file.write_all(&data).await.map_err(|err| {
-                error!("Failed to write to file: {err}");
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-        }
-
-        file.flush().await.map_err(|err| {
-            error!("Failed to flush file: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-        let data = GtfsReader::new()
-            .from_zip(&state.gtfs_data_path)
-            .map_err(|err| {
+        // Reading and indexing the feed is blocking work, keep it off the async workers.
+        let path = state.gtfs_data_path.clone();
+        let repo = tokio::task::spawn_blocking(move || {
+            let data = GtfsReader::new().from_zip(&path).map_err(|err| {
                 error!("Failed create gtfs repository from zip: {err}");
                 StatusCode::INTERNAL_SERVER_ERROR
             })?;
-        let repo = Repository::new().load_gtfs(data).map_err(|err| {
-            error!("Failed load gtfs file: {err}");
+            Repository::new().load_gtfs(data).map_err(|err| {
+                error!("Failed load gtfs file: {err}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })
+        })
+        .await
+        .map_err(|err| {
+            error!("Failed to join gtfs loading task: {err}");
             StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+        })??;
+        info!("{}", repo.load_report());
         let pool = AllocatorPool::new(state.allocator_count, &repo);
         let _ = state.allocator_pool.write().await.replace(pool);
         let _ = state.repository.write().await.replace(repo);
@@ -1,7 +1,9 @@
 mod gtfs;
 mod routing;
 mod search;
+mod stream;
 
 pub use gtfs::*;
 pub use routing::*;
 pub use search::*;
+pub use stream::*;
@@ -1,4 +1,5 @@
 use crate::{
+    api::json_array,
     dto::{AreaDto, StopDto},
     state::AppState,
 };
@@ -25,13 +26,14 @@ pub async fn search_areas(
                 },
                 None => 5,
             };
-            let result: Vec<_> = repository
+            // Collected while holding the lock, which is released before the response streams.
+            let areas: Vec<_> = repository
                 .search_areas_by_name(query)
                 .into_iter()
                 .take(count)
-                .map(|area| AreaDto::from(area, repository))
+                .map(AreaDto::from)
                 .collect();
-            Ok(Json(result).into_response())
+            Ok(json_array(areas.into_iter()))
         } else {
             Err(StatusCode::BAD_REQUEST)
         }
@@ -54,14 +56,15 @@ pub async fn search_stops(
                 },
                 None => 5,
             };
-            let result: Vec<_> = repository
+            // Collected while holding the lock, which is released before the response streams.
+            let stops: Vec<_> = repository
                 .search_stops_by_name(query)
                 .into_iter()
                 .filter(|stop| repository.stop_idx_has_trips(stop.index))
                 .take(count)
                 .map(StopDto::from)
                 .collect();
-            Ok(Json(result).into_response())
+            Ok(json_array(stops.into_iter()))
         } else {
             Err(StatusCode::BAD_REQUEST)
         }
@@ -88,7 +91,7 @@ pub async fn near_areas(
             let mut result: Vec<_> = repository
                 .areas_by_coordinate(&coordinate, distance)
                 .into_iter()
-                .map(|area| AreaDto::from(area, repository))
+                .map(AreaDto::from)
                 .collect();
             result.sort_by(|a, b| {
                 a.coordinate
@@ -120,17 +123,11 @@ pub async fn near_stops(
                 None => AVERAGE_STOP_DISTANCE,
             };
             let coordinate = Coordinate::from_str(query).map_err(|_| StatusCode::BAD_REQUEST)?;
-            let mut result: Vec<_> = repository
-                .stops_by_coordinate(&coordinate, distance)
+            let result: Vec<_> = repository
+                .stops_by_coordinate_sorted(&coordinate, distance)
                 .into_iter()
-                .map(StopDto::from)
+                .map(|(stop, _)| StopDto::from(stop))
                 .collect();
-            result.sort_by(|a, b| {
-                a.coordinate
-                    .network_distance(&coordinate)
-                    .as_meters()
-                    .total_cmp(&b.coordinate.network_distance(&coordinate).as_meters())
-            });
             Ok(Json(result).into_response())
         } else {
             Err(StatusCode::BAD_REQUEST)
@@ -0,0 +1,62 @@
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures_util::stream;
+use serde::Serialize;
+use std::iter;
+
+/// Streams `items` as a JSON array, serializing every item only when the body is polled
+/// so large results are never buffered as a whole.
+pub fn json_array<T, I>(items: I) -> Response
+where
+    T: Serialize,
+    I: Iterator<Item = T> + Send + 'static,
+{
+    let mut first = true;
+    let items = items.map(move |item| {
+        let mut chunk = if first { Vec::new() } else { vec![b','] };
+        first = false;
+        serde_json::to_writer(&mut chunk, &item).map(|_| chunk)
+    });
+    let chunks = iter::once(Ok(vec![b'[']))
+        .chain(items)
+        .chain(iter::once(Ok(vec![b']'])));
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        Body::from_stream(stream::iter(chunks)),
+    )
+        .into_response()
+}
+
+#[tokio::test]
+async fn json_array_test() {
+    use crate::dto::StopDto;
+    use blaise::{repository::Stop, shared::Coordinate};
+
+    let stops: Vec<_> = (0..3)
+        .map(|i| {
+            StopDto::from(&Stop {
+                id: format!("stop-{i}").into(),
+                name: format!("Stop {i}").into(),
+                coordinate: Coordinate::new(59.33, 18.0 + i as f32 / 100.0),
+                ..Default::default()
+            })
+        })
+        .collect();
+    // Compare against what `Json(stops)` would have sent.
+    let buffered: serde_json::Value =
+        serde_json::from_slice(&serde_json::to_vec(&stops).unwrap()).unwrap();
+
+    let body = axum::body::to_bytes(json_array(stops.into_iter()).into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let streamed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(streamed, buffered);
+
+    let body = axum::body::to_bytes(json_array(iter::empty::<StopDto>()).into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body.as_ref(), b"[]");
+}
@@ -1,7 +1,4 @@
-use blaise::{
-    repository::{Area, Repository},
-    shared::geo::Coordinate,
-};
+use blaise::{repository::Area, shared::geo::Coordinate};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,19 +8,12 @@ pub struct AreaDto {
     pub coordinate: Coordinate,
 }
 
-impl AreaDto {
-    pub fn from(area: &Area, repository: &Repository) -> Self {
-        let id = area.id.to_string();
-        let name = area.name.to_string();
-        let coordinate: Coordinate = repository
-            .stops_by_area_idx(area.index)
-            .into_iter()
-            .map(|stop| stop.coordinate)
-            .sum();
+impl From<&Area> for AreaDto {
+    fn from(area: &Area) -> Self {
         Self {
-            id,
-            name,
-            coordinate,
+            id: area.id.to_string(),
+            name: area.name.to_string(),
+            coordinate: area.centroid,
         }
     }
 }
@@ -1,7 +1,10 @@
 use blaise::{
-    raptor::{Itinerary, Leg, LegStop, LegType, Location},
+    raptor::{Itinerary, Leg, LegStop, LegType, Location, OccupancyStatus},
     repository::{Repository, Shape},
-    shared::{geo::Coordinate, time::Time},
+    shared::{
+        geo::{Coordinate, Distance},
+        time::Time,
+    },
 };
 use serde::{Deserialize, Serialize};
 
@@ -27,18 +30,11 @@ impl From<Coordinate> for LocationDto {
 impl LocationDto {
     pub fn from(location: Location, repository: &Repository) -> Option<Self> {
         match location {
-            Location::Area(id) => repository.area_by_id(&id).map(|val| {
-                let coordinate: Coordinate = repository
-                    .stops_by_area_idx(val.index)
-                    .into_iter()
-                    .map(|stop| stop.coordinate)
-                    .sum();
-                LocationDto {
-                    kind: "area".into(),
-                    id: val.id.to_string(),
-                    name: val.name.to_string(),
-                    coordinate,
-                }
+            Location::Area(id) => repository.area_by_id(&id).map(|val| LocationDto {
+                kind: "area".into(),
+                id: val.id.to_string(),
+                name: val.name.to_string(),
+                coordinate: val.centroid,
             }),
             Location::Stop(id) => repository.stop_by_id(&id).map(|val| LocationDto {
                 kind: "stop".into(),
@@ -46,7 +42,36 @@ impl LocationDto {
                 name: val.name.to_string(),
                 coordinate: val.coordinate,
             }),
+            Location::StopCode(code) => {
+                repository.stop_by_code(&code, None).map(|val| LocationDto {
+                    kind: "stop".into(),
+                    id: val.id.to_string(),
+                    name: val.name.to_string(),
+                    coordinate: val.coordinate,
+                })
+            }
             Location::Coordinate(coordinate) => Some(coordinate.into()),
+            Location::Route(id) | Location::RouteDirection(id, _) => {
+                repository.route_by_id(&id).and_then(|val| {
+                    // Anchor the route at the first stop of its first pattern.
+                    let stop_idx = *repository
+                        .raptors_by_route_idx(val.index)
+                        .first()?
+                        .stops
+                        .first()?;
+                    Some(LocationDto {
+                        kind: "route".into(),
+                        id: val.id.to_string(),
+                        name: val
+                            .long_name
+                            .clone()
+                            .or_else(|| val.short_name.clone())
+                            .unwrap_or_else(|| val.id.clone())
+                            .to_string(),
+                        coordinate: repository.stops[stop_idx as usize].coordinate,
+                    })
+                })
+            }
         }
     }
 }
@@ -56,7 +81,7 @@ pub struct LegStopDto {
     pub location: LocationDto,
     pub departure_time: Time,
     pub arrival_time: Time,
-    pub distance_traveled: Option<f32>,
+    pub distance_traveled: Option<Distance>,
 }
 
 impl LegStopDto {
@@ -65,7 +90,7 @@ impl LegStopDto {
             location: LocationDto::from(leg_stop.location, repository)?,
             departure_time: leg_stop.departure_time,
             arrival_time: leg_stop.arrival_time,
-            distance_traveled: leg_stop.distance_traveled.map(|value| value.as_meters()),
+            distance_traveled: leg_stop.distance_traveled,
         })
     }
 }
@@ -81,21 +106,25 @@ pub struct LegDto {
     pub head_sign: Option<String>,
     pub long_name: Option<String>,
     pub short_name: Option<String>,
+    pub color: Option<String>,
+    pub text_color: Option<String>,
     pub shapes: Option<Vec<ShapeDto>>,
+    pub occupancy: Option<OccupancyStatus>,
+    pub distance: Distance,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ShapeDto {
     pub location: LocationDto,
     pub sequence: u32,
-    pub distance_traveled: Option<f32>,
+    pub distance_traveled: Option<Distance>,
 }
 impl From<&Shape> for ShapeDto {
     fn from(value: &Shape) -> Self {
         Self {
             location: value.coordinate.into(),
             sequence: value.sequence,
-            distance_traveled: value.distance_traveled.map(|value| value.as_meters()),
+            distance_traveled: value.distance_traveled,
         }
     }
 }
@@ -145,6 +174,17 @@ impl LegDto {
             .map(|stop| LegStopDto::from(stop, repository))
             .collect();
 
+        let (color, text_color) = if let LegType::Transit(trip_idx) = leg.leg_type {
+            let trip = &repository.trips[trip_idx as usize];
+            let style = repository.route_style(trip.raptor_route_idx);
+            (
+                Some(style.color.to_string()),
+                Some(style.text_color.to_string()),
+            )
+        } else {
+            (None, None)
+        };
+
         let (head_sign, long_name, short_name) = if let LegType::Transit(trip_idx) = leg.leg_type {
             let trip = &repository.trips[trip_idx as usize];
             let head_sign = trip
@@ -175,6 +215,8 @@ impl LegDto {
             head_sign,
             long_name,
             short_name,
+            color,
+            text_color,
             shapes: if let LegType::Transit(trip_idx) = leg.leg_type {
                 repository
                     .shapes_by_trip_idx(trip_idx)
@@ -182,6 +224,8 @@ impl LegDto {
             } else {
                 None
             },
+            occupancy: leg.occupancy,
+            distance: leg.distance,
         })
     }
 }
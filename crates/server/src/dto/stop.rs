@@ -6,6 +6,8 @@ pub struct StopDto {
     pub id: String,
     pub name: String,
     pub coordinate: Coordinate,
+    pub description: Option<String>,
+    pub url: Option<String>,
 }
 
 impl StopDto {
@@ -13,10 +15,14 @@ impl StopDto {
         let id = stop.id.to_string();
         let name = stop.name.to_string();
         let coordinate = stop.coordinate;
+        let description = stop.description.as_deref().map(str::to_string);
+        let url = stop.url.as_deref().map(str::to_string);
         Self {
             id,
             name,
             coordinate,
+            description,
+            url,
         }
     }
 }
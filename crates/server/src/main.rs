@@ -48,7 +48,7 @@ async fn main() {
 
     // Built app state
     let app_state = AppState {
-        repository: RwLock::new(None),
+        repository: Arc::new(RwLock::new(None)),
         allocator_pool: RwLock::new(None),
         allocator_count: alloc_count,
         gtfs_data_path,
@@ -64,6 +64,7 @@ async fn main() {
             .load_gtfs(reader)
             .expect("Failed to load gtfs data in repository");
         info!("Loading data took {:?}", now.elapsed());
+        info!("{}", repo.load_report());
         info!("Allocating {alloc_count} pools...");
         let now = Instant::now();
         let pool = AllocatorPool::new(alloc_count, &repo);
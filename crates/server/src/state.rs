@@ -8,7 +8,7 @@ use tracing::{trace, warn};
 pub struct AppState {
     pub gtfs_data_path: PathBuf,
     pub allocator_count: usize,
-    pub repository: RwLock<Option<Repository>>,
+    pub repository: Arc<RwLock<Option<Repository>>>,
     pub allocator_pool: RwLock<Option<AllocatorPool>>,
 }
 
@@ -27,10 +27,20 @@ impl AllocatorPool {
         }
     }
 
+    /// Checks out an allocator for `repository`. Pooled allocators sized for a previous
+    /// repository are replaced, and a temporary one is handed out if the pool is empty.
     pub fn get_safe(&self, repository: &Repository) -> AllocatorGuard {
         self.inner
             .pop()
             .map(|alloc| {
+                if !alloc.fits(repository) {
+                    warn!("Replaced allocator sized for a previous repository");
+                    return AllocatorGuard {
+                        allocator: Some(Allocator::new(repository)),
+                        owned: true,
+                        pool: self.inner.clone(),
+                    };
+                }
                 trace!("Reused allocator");
                 AllocatorGuard {
                     allocator: Some(alloc),
@@ -66,3 +76,78 @@ impl Drop for AllocatorGuard {
         }
     }
 }
+
+#[cfg(test)]
+fn sample_repository() -> Repository {
+    let gtfs = blaise::gtfs::GtfsReader::new().from_directory(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../tests/fixtures/sample"
+    ));
+    Repository::new().load_gtfs(gtfs).unwrap()
+}
+
+#[test]
+fn allocator_pool_concurrent_test() {
+    use blaise::prelude::*;
+
+    let repository = sample_repository();
+    let pool = AllocatorPool::new(2, &repository);
+    let queries = [
+        ("S_A1", "S_E1", "07:00:00"),
+        ("S_A1", "S_G", "07:00:00"),
+        ("S_B", "S_D", "07:30:00"),
+        ("S_C2", "S_G", "07:10:00"),
+    ];
+    let solve = |allocator: &mut Allocator, (from, to, at): (&str, &str, &str)| {
+        let itinerary = repository
+            .router(Location::Stop(from.into()), Location::Stop(to.into()))
+            .departure_at(Time::from_hms(at).unwrap())
+            .solve_with_allocator(allocator)
+            .unwrap();
+        format!("{:?}", itinerary.legs)
+    };
+    let expected: Vec<_> = queries
+        .iter()
+        .map(|query| solve(&mut Allocator::new(&repository), *query))
+        .collect();
+
+    // More threads and rounds than pooled allocators, so they are shared and reused.
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8)
+            .map(|thread| {
+                let (pool, solve, queries, repository) = (&pool, &solve, &queries, &repository);
+                scope.spawn(move || {
+                    (0..5)
+                        .map(|round| {
+                            let i = (thread + round) % queries.len();
+                            let mut guard = pool.get_safe(repository);
+                            (i, solve(guard.allocator.as_mut().unwrap(), queries[i]))
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .for_each(|(i, legs)| assert_eq!(legs, expected[i]));
+    });
+    assert_eq!(pool.inner.len(), 2);
+}
+
+#[test]
+fn allocator_pool_reload_test() {
+    let repository = sample_repository();
+    // Stands in for the repository the pool was built for before a reload.
+    let stop = repository.coordinate_by_stop_id("S_A1").unwrap();
+    let previous = repository.subgraph_for_bbox(stop, stop);
+    let pool = AllocatorPool::new(1, &previous);
+    assert!(!pool.inner.pop().unwrap().fits(&repository));
+    let _ = pool.inner.push(Allocator::new(&previous));
+
+    drop(pool.get_safe(&repository));
+    // The stale allocator was replaced in the pool, not handed out again.
+    let guard = pool.get_safe(&repository);
+    assert!(guard.owned);
+    assert!(guard.allocator.as_ref().unwrap().fits(&repository));
+}
@@ -0,0 +1,337 @@
+use reqwest::{
+    Client, StatusCode,
+    header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE},
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{info, warn};
+
+/// How many times an interrupted download is resumed before giving up.
+const MAX_ATTEMPTS: usize = 5;
+
+#[derive(Error, Debug)]
+pub enum DownloadError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Unexpected response status: {0}")]
+    Status(StatusCode),
+    #[error("Expected {expected} bytes but received {actual}")]
+    LengthMismatch { expected: u64, actual: u64 },
+    #[error("The partial download does not match the remote file")]
+    StalePart,
+}
+
+/// Downloads `url` to `path` in chunks.
+///
+/// Data is written to a `.part` file next to `path`, named after `url`, and only renamed
+/// into place once the full length announced by the server has been received. If the
+/// transfer is interrupted it is resumed with a range request, and a `.part` file left
+/// behind by an earlier call for the same `url` is picked up the same way.
+///
+/// Resuming sends the `ETag` or `Last-Modified` of the first response as `If-Range`, so a
+/// changed remote file is downloaded from the start instead of appended to the old bytes.
+/// A partial download without such a validator is discarded.
+pub async fn download(
+    client: &Client,
+    url: &str,
+    path: impl AsRef<Path>,
+) -> Result<(), DownloadError> {
+    let path = path.as_ref();
+    let part_path = part_path(path, url);
+    let mut attempt = 1;
+    loop {
+        match download_part(client, url, &part_path).await {
+            Ok(()) => break,
+            Err(DownloadError::Request(err)) if attempt < MAX_ATTEMPTS => {
+                warn!("Download of {url} interrupted, resuming: {err}");
+                attempt += 1;
+            }
+            Err(DownloadError::StalePart) if attempt < MAX_ATTEMPTS => {
+                warn!("Partial download of {url} is stale, starting over");
+                discard_part(&part_path).await?;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    fs::rename(&part_path, path).await?;
+    remove_if_exists(&validator_path(&part_path)).await?;
+    Ok(())
+}
+
+/// Continues filling `part_path` from where it left off until it holds the whole body.
+async fn download_part(client: &Client, url: &str, part_path: &Path) -> Result<(), DownloadError> {
+    let validator_path = validator_path(part_path);
+    let mut offset = fs::metadata(part_path)
+        .await
+        .map(|meta| meta.len())
+        .unwrap_or(0);
+    let validator = fs::read_to_string(&validator_path).await.ok();
+    let mut request = client.get(url);
+    match validator.as_deref() {
+        Some(validator) if offset > 0 => {
+            info!("Resuming download of {url} at byte {offset}");
+            request = request
+                .header(RANGE, format!("bytes={offset}-"))
+                .header(IF_RANGE, validator);
+        }
+        // Without a validator the bytes can't be matched to the remote file
+        _ if offset > 0 => {
+            discard_part(part_path).await?;
+            offset = 0;
+        }
+        _ => {}
+    }
+    let mut response = request.send().await?;
+
+    let (mut written, expected) = match response.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            let total = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(content_range_total);
+            (offset, total)
+        }
+        // The server ignored the range or the file changed, so start over
+        status if status.is_success() => {
+            match response_validator(&response) {
+                Some(validator) => fs::write(&validator_path, validator).await?,
+                None => remove_if_exists(&validator_path).await?,
+            }
+            (0, response.content_length())
+        }
+        // Nothing left to fetch only if the part file holds exactly the full body
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+            let total = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(content_range_total);
+            return match total {
+                Some(total) if total == offset => Ok(()),
+                _ => Err(DownloadError::StalePart),
+            };
+        }
+        status => return Err(DownloadError::Status(status)),
+    };
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(written > 0)
+        .truncate(written == 0)
+        .open(part_path)
+        .await?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+    file.flush().await?;
+
+    match expected {
+        Some(expected) if expected != written => Err(DownloadError::LengthMismatch {
+            expected,
+            actual: written,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The strong `ETag`, or else the `Last-Modified`, usable as `If-Range` when resuming.
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    // Weak entity tags are not allowed in `If-Range`
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+}
+
+async fn discard_part(part_path: &Path) -> io::Result<()> {
+    remove_if_exists(part_path).await?;
+    remove_if_exists(&validator_path(part_path)).await
+}
+
+async fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// The `.part` file for downloading `url` to `path`, keyed to the url so a partial download
+/// of one url is never resumed with the bytes of another.
+fn part_path(path: &Path, url: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{:016x}.part", fnv1a(url.as_bytes())));
+    path.with_file_name(name)
+}
+
+/// Where the validator of the response filling `part_path` is kept between attempts.
+fn validator_path(part_path: &Path) -> PathBuf {
+    let mut name = part_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".validator");
+    part_path.with_file_name(name)
+}
+
+/// A hash that stays the same across builds, unlike the std `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Parses the complete length out of a `bytes start-end/total` or `bytes */total` content range.
+fn content_range_total(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+#[cfg(test)]
+/// Reads a request head and returns its headers with lowercase names.
+fn read_headers(stream: &mut std::net::TcpStream) -> std::collections::HashMap<String, String> {
+    use std::io::{BufRead, BufReader};
+    let mut reader = BufReader::new(stream);
+    let mut headers = std::collections::HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            return headers;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn resume_interrupted_download_test() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let body: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/feed.zip", listener.local_addr().unwrap());
+
+    let served = body.clone();
+    let server = thread::spawn(move || {
+        let mut requests = vec![];
+        // First connection announces the full body but drops halfway through
+        let (mut stream, _) = listener.accept().unwrap();
+        requests.push(read_headers(&mut stream));
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+            served.len()
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(&served[..served.len() / 2]).unwrap();
+        drop(stream);
+
+        // Second connection serves the rest of the requested range
+        let (mut stream, _) = listener.accept().unwrap();
+        let headers = read_headers(&mut stream);
+        let start: usize = headers
+            .get("range")
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.strip_suffix('-'))
+            .and_then(|start| start.parse().ok())
+            .unwrap_or(0);
+        requests.push(headers);
+        let head = format!(
+            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+            served.len() - start,
+            start,
+            served.len() - 1,
+            served.len()
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(&served[start..]).unwrap();
+        requests
+    });
+
+    let directory = crate::repository::TempDir::new();
+    let path = directory.path().join("feed.zip");
+
+    download(&Client::new(), &url, &path).await.unwrap();
+    let requests = server.join().unwrap();
+
+    assert_eq!(requests[0].get("range"), None);
+    assert_eq!(
+        requests[1].get("range"),
+        Some(&format!("bytes={}-", body.len() / 2))
+    );
+    assert_eq!(
+        requests[1].get("if-range").map(String::as_str),
+        Some("\"v1\"")
+    );
+    let mut downloaded = vec![];
+    std::fs::File::open(&path)
+        .unwrap()
+        .read_to_end(&mut downloaded)
+        .unwrap();
+    assert_eq!(downloaded, body);
+    assert!(!part_path(&path, &url).exists());
+    assert!(!validator_path(&part_path(&path, &url)).exists());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn stale_part_download_test() {
+    use std::{io::Write, net::TcpListener, thread};
+
+    let body: Vec<u8> = (0..1024u32).map(|i| (i % 241) as u8).collect();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/feed.zip", listener.local_addr().unwrap());
+    let directory = crate::repository::TempDir::new();
+    let path = directory.path().join("feed.zip");
+
+    // A part file of another url is never touched
+    let other = part_path(&path, "http://example.com/other.zip");
+    std::fs::write(&other, b"other").unwrap();
+    // A part file without a validator can't be resumed
+    std::fs::write(part_path(&path, &url), vec![0u8; 2048]).unwrap();
+
+    let served = body.clone();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let headers = read_headers(&mut stream);
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            served.len()
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(&served).unwrap();
+        headers
+    });
+
+    download(&Client::new(), &url, &path).await.unwrap();
+    let headers = server.join().unwrap();
+
+    assert_eq!(headers.get("range"), None);
+    assert_eq!(std::fs::read(&path).unwrap(), body);
+    assert_eq!(std::fs::read(&other).unwrap(), b"other");
+}
+
+#[test]
+fn content_range_total_test() {
+    assert_eq!(content_range_total("bytes 0-99/100"), Some(100));
+    assert_eq!(content_range_total("bytes */2048"), Some(2048));
+    assert_eq!(content_range_total("bytes 0-99/*"), None);
+}
@@ -1,10 +1,13 @@
+#[cfg(feature = "reqwest")]
+pub mod download;
 pub mod models;
 
+use crate::shared::geo::Distance;
 use models::*;
 use serde::de::DeserializeOwned;
 use std::{
     fs::{self, File},
-    io::{self},
+    io::{self, BufRead, BufReader},
     path::{Path, PathBuf},
 };
 use thiserror::Error;
@@ -25,6 +28,17 @@ pub enum Error {
     MissingSource,
 }
 
+impl Error {
+    /// Returns `true` if the error was caused by a file missing from the source.
+    pub fn is_missing_file(&self) -> bool {
+        match self {
+            Error::FileNotFound(_) => true,
+            Error::Io(err) => err.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
+
 pub struct Config {
     pub stops_path: String,
     pub areas_path: String,
@@ -35,6 +49,42 @@ pub struct Config {
     pub stop_times_path: String,
     pub trips_path: String,
     pub shapes_path: String,
+    pub calendar_path: String,
+    pub calendar_dates_path: String,
+    /// Leaves stops without any service (and without served child stops) out of
+    /// the spatial index, so they aren't found by coordinate and no walks lead to them.
+    /// They can still be looked up by id and keep their walks to the served stops nearby.
+    pub exclude_unserved_stops: bool,
+    /// Sorts hash map backed groupings (shapes and raptor routes) by id before assigning
+    /// indices, so repeated builds from the same feed produce identical indices.
+    pub deterministic: bool,
+    /// Merges regular stops with the same parent that lie within this distance of each
+    /// other into one, e.g. a feed listing a stop once per direction at the same position.
+    pub dedupe_colocated_stops: Option<Distance>,
+    /// The unit `shape_dist_traveled` is given in. GTFS leaves it up to the feed, but shapes
+    /// and stop times have to agree for legs to be trimmed correctly.
+    pub shape_dist_unit: DistanceUnit,
+}
+
+/// Unit of the `shape_dist_traveled` values in a feed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    #[default]
+    Meters,
+    Kilometers,
+    Miles,
+    Feet,
+}
+
+impl DistanceUnit {
+    pub fn to_distance(&self, value: f32) -> Distance {
+        match self {
+            DistanceUnit::Meters => Distance::from_meters(value),
+            DistanceUnit::Kilometers => Distance::from_kilometers(value),
+            DistanceUnit::Miles => Distance::from_miles(value),
+            DistanceUnit::Feet => Distance::from_feet(value),
+        }
+    }
 }
 
 impl Default for Config {
@@ -49,6 +99,12 @@ impl Default for Config {
             stop_times_path: "stop_times.txt".into(),
             trips_path: "trips.txt".into(),
             shapes_path: "shapes.txt".into(),
+            calendar_path: "calendar.txt".into(),
+            calendar_dates_path: "calendar_dates.txt".into(),
+            exclude_unserved_stops: false,
+            deterministic: false,
+            dedupe_colocated_stops: None,
+            shape_dist_unit: DistanceUnit::Meters,
         }
     }
 }
@@ -77,6 +133,10 @@ impl GtfsReader {
         self
     }
 
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     pub fn from_zip<P: AsRef<Path>>(mut self, path: P) -> Result<Self, self::Error> {
         let zip_file = File::open(path)?;
         let archive = ZipArchive::new(zip_file)?;
@@ -202,6 +262,28 @@ impl GtfsReader {
             Source::Directory(path) => stream_from_dir(path, &self.config.shapes_path, f),
         }
     }
+
+    pub fn stream_calendar<F>(&mut self, f: F) -> Result<(), self::Error>
+    where
+        F: FnMut((usize, GtfsCalendar)),
+    {
+        match &mut self.storage {
+            Source::None => Ok(()),
+            Source::Zip(archive) => stream_from_zip(archive, &self.config.calendar_path, f),
+            Source::Directory(path) => stream_from_dir(path, &self.config.calendar_path, f),
+        }
+    }
+
+    pub fn stream_calendar_dates<F>(&mut self, f: F) -> Result<(), self::Error>
+    where
+        F: FnMut((usize, GtfsCalendarDate)),
+    {
+        match &mut self.storage {
+            Source::None => Ok(()),
+            Source::Zip(archive) => stream_from_zip(archive, &self.config.calendar_dates_path, f),
+            Source::Directory(path) => stream_from_dir(path, &self.config.calendar_dates_path, f),
+        }
+    }
 }
 
 fn stream_from_zip<T, F>(
@@ -214,7 +296,7 @@ where
     F: FnMut((usize, T)),
 {
     let file = get_file_from_zip(archive, file_name)?;
-    let mut reader = csv::Reader::from_reader(file);
+    let mut reader = csv_reader(BufReader::new(file))?;
     for (i, result) in reader.deserialize().enumerate() {
         let record: T = result?;
         f((i, record));
@@ -231,8 +313,8 @@ where
     let file = fs::File::open(file_path)?;
 
     // BufReader is critical here for speed
-    let reader = std::io::BufReader::with_capacity(128 * 1024, file);
-    let mut csv_reader = csv::Reader::from_reader(reader);
+    let reader = BufReader::with_capacity(128 * 1024, file);
+    let mut csv_reader = csv_reader(reader)?;
 
     for (i, result) in csv_reader.deserialize().enumerate() {
         let record: T = result?;
@@ -241,6 +323,22 @@ where
     Ok(())
 }
 
+/// Builds the CSV reader every file is read with.
+///
+/// A leading UTF-8 BOM is skipped so it doesn't end up in the first column name,
+/// header names are trimmed and fields may be quoted with doubled quotes inside.
+fn csv_reader<R: BufRead>(mut reader: R) -> Result<csv::Reader<R>, self::Error> {
+    const BOM: &[u8] = b"\xEF\xBB\xBF";
+    if reader.fill_buf()?.starts_with(BOM) {
+        reader.consume(BOM.len());
+    }
+    Ok(csv::ReaderBuilder::new()
+        .quoting(true)
+        .double_quote(true)
+        .trim(csv::Trim::Headers)
+        .from_reader(reader))
+}
+
 fn get_file_from_zip<'a>(
     archive: &'a mut ZipArchive<File>,
     name: &'a str,
@@ -251,3 +349,27 @@ fn get_file_from_zip<'a>(
     let file = archive.by_index(index)?;
     Ok(file)
 }
+
+#[test]
+fn bom_and_quoted_fields_test() {
+    let feed = crate::repository::SampleFeed::new();
+    let stops = feed.read("stops.txt").replace(
+        "S_B,Birch Street,",
+        "S_B,\"Birch \"\"Old\"\" Street, North\",",
+    );
+    feed.write("stops.txt", format!("\u{feff}{stops}"));
+
+    let mut ids = vec![];
+    feed.reader(Config::default())
+        .stream_stops(|(_, stop)| ids.push(stop.stop_id))
+        .unwrap();
+    assert_eq!(ids.first().map(String::as_str), Some("ST_A"));
+
+    let repository = feed.load(Config::default());
+    assert_eq!(
+        repository.stops.len(),
+        crate::repository::sample_repository().stops.len()
+    );
+    let stop = repository.stop_by_id("S_B").unwrap();
+    assert_eq!(&*stop.name, "Birch \"Old\" Street, North");
+}
@@ -1,8 +1,8 @@
 use crate::{
-    repository::{Area, Route, Stop, StopAccessType, StopTime, Timepoint},
+    repository::{Area, LocationType, Route, Stop, StopAccessType, StopTime, Timepoint},
     shared::{
         geo::{Coordinate, Distance},
-        time::Time,
+        time::{self, Time},
     },
 };
 use serde::{Deserialize, Serialize};
@@ -17,10 +17,27 @@ pub struct GtfsStop {
     pub location_type: Option<u8>,
     pub parent_station: Option<String>,
     pub platform_code: Option<String>,
+    pub zone_id: Option<String>,
+    pub stop_code: Option<String>,
+    pub stop_desc: Option<String>,
+    pub stop_url: Option<String>,
 }
 
 impl From<GtfsStop> for Stop {
     fn from(value: GtfsStop) -> Self {
+        let location_type = match value.location_type.unwrap_or_default() {
+            1 => LocationType::Station,
+            2 => LocationType::Entrance(value.parent_station.unwrap_or_default().into()),
+            3 => LocationType::Node,
+            4 => LocationType::Boarding,
+            _ => match value.parent_station {
+                Some(parent_station) => LocationType::Platform {
+                    parent_station: parent_station.into(),
+                    platform_code: value.platform_code.unwrap_or_default().into(),
+                },
+                None => LocationType::Stop,
+            },
+        };
         Self {
             index: u32::MAX,
             id: value.stop_id.into(),
@@ -31,6 +48,11 @@ impl From<GtfsStop> for Stop {
                 longitude: value.stop_lon,
             },
             parent_index: None,
+            zone_id: value.zone_id.map(|zone_id| zone_id.into()),
+            code: value.stop_code.map(|code| code.into()),
+            description: value.stop_desc.map(|description| description.into()),
+            url: value.stop_url.map(|url| url.into()),
+            location_type,
         }
     }
 }
@@ -49,6 +71,8 @@ impl From<GtfsArea> for Area {
             id: value.area_id.into(),
             name: value.area_name.clone().into(),
             normalized_name: value.area_name.to_lowercase().into(),
+            centroid: Coordinate::default(),
+            bbox: None,
         }
     }
 }
@@ -62,6 +86,10 @@ pub struct GtfsRoute {
     pub route_long_name: Option<String>,
     pub route_type: i32,
     pub route_desc: Option<String>,
+    pub route_color: Option<String>,
+    pub route_text_color: Option<String>,
+    pub continuous_pickup: Option<u8>,
+    pub continuous_drop_off: Option<u8>,
 }
 
 impl From<GtfsRoute> for Route {
@@ -74,6 +102,10 @@ impl From<GtfsRoute> for Route {
             long_name: value.route_long_name.map(|val| val.into()),
             route_type: value.route_type,
             route_desc: value.route_desc.map(|val| val.into()),
+            color: value.route_color.map(|val| val.into()),
+            text_color: value.route_text_color.map(|val| val.into()),
+            continuous_pickup: value.continuous_pickup.and_then(continuous_access),
+            continuous_drop_off: value.continuous_drop_off.and_then(continuous_access),
         }
     }
 }
@@ -102,7 +134,8 @@ pub struct GtfsTransfer {
     pub from_stop_id: String,
     pub to_stop_id: String,
     pub transfer_type: String,
-    pub min_transfer_time: Option<u32>,
+    /// Signed so that broken feeds with negative times can be loaded and clamped.
+    pub min_transfer_time: Option<i64>,
     pub from_trip_id: Option<String>,
     pub to_trip_id: Option<String>,
 }
@@ -122,10 +155,33 @@ pub struct GtfsStopTime {
     pub timepoint: Option<u8>,
     pub pickup_booking_rule_id: Option<String>,
     pub drop_off_booking_rule_id: Option<String>,
+    pub continuous_pickup: Option<u8>,
+    pub continuous_drop_off: Option<u8>,
+}
+
+impl GtfsStopTime {
+    /// Whether the arrival or departure time is filled in but isn't a valid `HH:MM:SS` time.
+    /// Only empty times are missing, see the conversion into a [`StopTime`].
+    pub fn has_malformed_time(&self) -> bool {
+        [&self.arrival_time, &self.departure_time]
+            .into_iter()
+            .any(|time| !time.trim().is_empty() && Time::from_hms(time.trim()).is_none())
+    }
 }
 
 impl From<GtfsStopTime> for StopTime {
+    /// Stops without any time are left at [`time::MAX`] and marked [`Timepoint::Approximate`],
+    /// to be interpolated once the whole trip is known. A stop with only one of the
+    /// two times uses it for both. Rows with [`GtfsStopTime::has_malformed_time`] are
+    /// expected to be skipped before converting.
     fn from(value: GtfsStopTime) -> Self {
+        let arrival_time = Time::from_hms(&value.arrival_time);
+        let departure_time = Time::from_hms(&value.departure_time);
+        let (arrival_time, departure_time, timepoint) = match (arrival_time, departure_time) {
+            (Some(arrival), Some(departure)) => (arrival, departure, Timepoint::Exact),
+            (Some(time), None) | (None, Some(time)) => (time, time, Timepoint::Exact),
+            (None, None) => (time::MAX, time::MAX, Timepoint::Approximate),
+        };
         Self {
             index: u32::MAX,
             // trip_id: Default::default(),
@@ -135,17 +191,30 @@ impl From<GtfsStopTime> for StopTime {
             slice: Default::default(),
             inner_idx: u32::MAX,
             sequence: value.stop_sequence,
-            arrival_time: Time::from_hms(&value.arrival_time).unwrap(),
-            departure_time: Time::from_hms(&value.departure_time).unwrap(),
+            arrival_time,
+            departure_time,
             headsign: value.stop_headsign.map(|val| val.into()),
             distance_traveled: value.shape_dist_traveled.map(Distance::from_meters),
             pickup_type: StopAccessType::Regularly,
             drop_off_type: StopAccessType::Regularly,
-            timepoint: Timepoint::Exact,
+            timepoint,
+            continuous_pickup: value.continuous_pickup.and_then(continuous_access),
+            continuous_drop_off: value.continuous_drop_off.and_then(continuous_access),
         }
     }
 }
 
+/// Maps a GTFS `continuous_pickup`/`continuous_drop_off` value.
+fn continuous_access(value: u8) -> Option<StopAccessType> {
+    match value {
+        0 => Some(StopAccessType::Regularly),
+        1 => Some(StopAccessType::NoneAvailable),
+        2 => Some(StopAccessType::AgencyArrange),
+        3 => Some(StopAccessType::DriverArrange),
+        _ => None,
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct GtfsTrip {
@@ -167,3 +236,27 @@ pub struct GtfsShape {
     pub shape_pt_sequence: u32,
     pub shape_dist_traveled: Option<f32>,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GtfsCalendar {
+    pub service_id: String,
+    pub monday: u8,
+    pub tuesday: u8,
+    pub wednesday: u8,
+    pub thursday: u8,
+    pub friday: u8,
+    pub saturday: u8,
+    pub sunday: u8,
+    pub start_date: u32,
+    pub end_date: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GtfsCalendarDate {
+    pub service_id: String,
+    pub date: u32,
+    /// 1 when service is added for the date, 2 when it is removed.
+    pub exception_type: u8,
+}
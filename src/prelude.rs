@@ -1,4 +1,27 @@
+//! Everything needed to load a GTFS feed and route on it with a single import.
+//!
+//! ```no_run
+//! use blaise::prelude::*;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let reader = GtfsReader::new().from_zip("gtfs_data.zip")?;
+//!     let repository = Repository::new().load_gtfs(reader)?;
+//!     let from = Location::Stop("STOP_ID_1".into());
+//!     let to = Location::Coordinate(Coordinate::new(59.3, 18.0));
+//!     let itinerary = repository
+//!         .router(from, to)
+//!         .departure_at(Time::from_seconds(28800))
+//!         .solve()?;
+//!     println!("Found a path with {} legs!", itinerary.legs.len());
+//!     Ok(())
+//! }
+//! ```
 pub use crate::gtfs::*;
+pub use crate::gtfs::{Error as GtfsError, GtfsReader as Gtfs};
+pub use crate::raptor::{
+    Allocator, AreaAnchor, Error as RaptorError, Itinerary, Leg, LegStop, LegType, Location,
+    Raptor, TimeConstraint,
+};
 pub use crate::repository::*;
 pub use crate::shared::*;
 pub use std::str::FromStr;
@@ -1,5 +1,5 @@
 use crate::{
-    raptor::{MAX_ROUNDS, Parent, ServingRoute, Target, Update},
+    raptor::{MAX_ROUNDS, Parent, Penalty, ServingRoute, Target, Update},
     repository::Repository,
     shared::{Time, time},
 };
@@ -14,6 +14,8 @@ use std::mem;
 pub struct Allocator {
     /// The best known arrival time at each stop across all rounds.
     pub(crate) tau_star: Vec<Option<Time>>,
+    /// The [`Penalty`] of every label in `tau_star`.
+    pub(crate) tau_penalties: Vec<Penalty>,
     /// Tracks which stops were updated in the current round and need to be explored in the next.
     pub(crate) marked_stops: BitVec<usize, Lsb0>,
     /// Tracks the earliest relevant stop index for each route in the current round.
@@ -24,6 +26,10 @@ pub struct Allocator {
     /// Labels for the current round (k).
     /// We use two arrays to "double-buffer" labels since RAPTOR only ever references the previous round.
     pub(crate) curr_labels: Vec<Option<Time>>,
+    /// The [`Penalty`] of every label in `prev_labels`.
+    pub(crate) prev_penalties: Vec<Penalty>,
+    /// The [`Penalty`] of every label in `curr_labels`.
+    pub(crate) curr_penalties: Vec<Penalty>,
     /// A flattened 2D matrix [round][stop_index] storing path reconstruction pointers.
     pub(crate) parents: Vec<Option<Parent>>,
     /// Buffer used to batch updates before applying them to the state.
@@ -46,9 +52,12 @@ impl Allocator {
     pub fn new(repository: &Repository) -> Self {
         Self {
             tau_star: vec![None; repository.stops.len()],
+            tau_penalties: vec![Penalty::default(); repository.stops.len()],
             marked_stops: bitvec!(usize, Lsb0; 0; repository.stops.len()),
             prev_labels: vec![None; repository.stops.len()],
             curr_labels: vec![None; repository.stops.len()],
+            prev_penalties: vec![Penalty::default(); repository.stops.len()],
+            curr_penalties: vec![Penalty::default(); repository.stops.len()],
             parents: vec![None; repository.stops.len() * MAX_ROUNDS],
             updates: Vec::with_capacity(1024),
             active: vec![u32::MAX; repository.raptor_routes.len()],
@@ -60,13 +69,23 @@ impl Allocator {
         }
     }
 
+    /// Returns `true` if the allocator is sized for `repository`. An allocator built before
+    /// the repository was reloaded has to be replaced rather than reused.
+    pub fn fits(&self, repository: &Repository) -> bool {
+        self.stop_count == repository.stops.len()
+            && self.active.len() == repository.raptor_routes.len()
+    }
+
     /// Resets the internal buffers to their initial state, allowing the allocator
     /// to be reused for a new search without re-allocating memory.
     pub fn reset(&mut self) {
         self.tau_star.fill(None);
+        self.tau_penalties.fill(Penalty::default());
         self.marked_stops.fill(false);
         self.prev_labels.fill(None);
         self.curr_labels.fill(None);
+        self.prev_penalties.fill(Penalty::default());
+        self.curr_penalties.fill(Penalty::default());
         self.parents.fill(None);
         self.active.fill(u32::MAX);
         self.active_mask.fill(false);
@@ -78,13 +97,25 @@ impl Allocator {
 
     pub(crate) fn run_updates(&mut self) {
         self.updates.iter().for_each(|update| {
-            let best_time = self.tau_star[update.stop_idx as usize].unwrap_or(time::MAX);
-            if update.arrival_time < best_time {
-                self.curr_labels[update.stop_idx as usize] = Some(update.arrival_time);
-                self.parents[flat_matrix(self.round, update.stop_idx as usize, self.stop_count)] =
+            let stop_idx = update.stop_idx as usize;
+            let best_key = best_key(&self.tau_star, &self.tau_penalties, stop_idx);
+            if update.penalty.key(update.arrival_time) < best_key {
+                // Checks the label the update would set, like `run_updates_reverse`.
+                if self
+                    .target
+                    .time_limit
+                    .is_some_and(|limit| update.arrival_time > limit)
+                {
+                    self.target.pruned = true;
+                    return;
+                }
+                self.curr_labels[stop_idx] = Some(update.arrival_time);
+                self.curr_penalties[stop_idx] = update.penalty;
+                self.parents[flat_matrix(self.round, stop_idx, self.stop_count)] =
                     Some(update.parent);
-                self.tau_star[update.stop_idx as usize] = Some(update.arrival_time);
-                self.marked_stops.set(update.stop_idx as usize, true);
+                self.tau_star[stop_idx] = Some(update.arrival_time);
+                self.tau_penalties[stop_idx] = update.penalty;
+                self.marked_stops.set(stop_idx, true);
             }
         });
         self.updates.clear();
@@ -94,6 +125,15 @@ impl Allocator {
         self.updates.iter().for_each(|update| {
             let best_time = self.tau_star[update.stop_idx as usize].unwrap_or(time::MIN);
             if update.arrival_time > best_time {
+                // Checks the label the update would set, the departure from the stop.
+                if self
+                    .target
+                    .time_limit
+                    .is_some_and(|limit| update.arrival_time < limit)
+                {
+                    self.target.pruned = true;
+                    return;
+                }
                 self.curr_labels[update.stop_idx as usize] = Some(update.arrival_time);
                 self.parents[flat_matrix(self.round, update.stop_idx as usize, self.stop_count)] =
                     Some(update.parent);
@@ -110,7 +150,9 @@ impl Allocator {
 
     pub(crate) fn swap_labels(&mut self) {
         mem::swap(&mut self.curr_labels, &mut self.prev_labels);
+        mem::swap(&mut self.curr_penalties, &mut self.prev_penalties);
         self.curr_labels.fill(None);
+        self.curr_penalties.fill(Penalty::default());
     }
 
     pub(crate) fn next_round(&mut self) {
@@ -150,6 +192,15 @@ impl<T> LazyBuffer<T> {
     }
 }
 
+/// The best comparison key at `stop_idx`, the arrival time in `tau_star` plus its [`Penalty`].
+/// Takes the fields rather than the [`Allocator`] so it can be used while updates are buffered.
+#[inline(always)]
+pub(crate) fn best_key(tau_star: &[Option<Time>], penalties: &[Penalty], stop_idx: usize) -> Time {
+    tau_star[stop_idx]
+        .map(|time| penalties[stop_idx].key(time))
+        .unwrap_or(time::MAX)
+}
+
 /// Converts a (round, stop_index) coordinate into a flat index
 /// for the 1D parents/labels arrays.
 #[inline(always)] // Hint to compiler to inline for performance
@@ -1,15 +1,81 @@
 use crate::{
-    raptor::{self, Allocator, Location},
+    raptor::{self, Allocator, AreaAnchor, Location},
     repository::{RaptorRoute, Repository, Stop, Transfer, Trip},
-    shared::{AVERAGE_STOP_DISTANCE, Distance, Duration, Time},
+    shared::{AVERAGE_STOP_DISTANCE, Coordinate, Distance, Duration, Time},
 };
+use bitvec::slice::BitSlice;
 use tracing::{trace, warn};
 
-pub fn stops_by_location<'a>(
+/// The stops a [`Location`] resolved to, see [`stops_by_location`].
+pub struct LocationStops<'a> {
+    pub stops: Vec<&'a Stop>,
+    /// Set when an area had no stops with service and the stops around it were used instead.
+    pub fallback: bool,
+}
+
+impl<'a> From<Vec<&'a Stop>> for LocationStops<'a> {
+    fn from(stops: Vec<&'a Stop>) -> Self {
+        Self {
+            stops,
+            fallback: false,
+        }
+    }
+}
+
+/// The stops boardable from `stop`, its siblings within the station or its children.
+fn stops_by_stop<'a>(repository: &'a Repository, stop: &'a Stop) -> Vec<&'a Stop> {
+    let stop = repository.platform_by_stop_idx(stop.index);
+    if let Some(station_idx) = stop.parent_index {
+        repository.stops_by_station(station_idx)
+    } else {
+        let stops = repository.stops_by_station(stop.index);
+        if stops.is_empty() { vec![stop] } else { stops }
+    }
+}
+
+/// Every stop served by the route `id`, only counting its trips in `direction_id` when given.
+fn stops_by_route<'a>(
     repository: &'a Repository,
-    location: &'a Location,
+    id: &str,
+    direction_id: Option<u8>,
 ) -> Result<Vec<&'a Stop>, raptor::Error> {
-    match location {
+    let route = repository
+        .route_by_id(id)
+        .ok_or(raptor::Error::InvalidRouteID)?;
+    // The location has no position of its own, so every stop the route serves
+    // is a candidate rather than the ones within walking distance.
+    let mut stops: Vec<_> = repository
+        .raptors_by_route_idx(route.index)
+        .into_iter()
+        .filter(|raptor| {
+            direction_id.is_none_or(|direction_id| {
+                raptor.trips.iter().any(|trip_idx| {
+                    repository.trips[*trip_idx as usize].direction_id == Some(direction_id)
+                })
+            })
+        })
+        .flat_map(|raptor| raptor.stops.iter().copied())
+        .collect();
+    stops.sort_unstable();
+    stops.dedup();
+    if stops.is_empty() {
+        Err(raptor::Error::NoNearbyStops)
+    } else {
+        Ok(stops
+            .into_iter()
+            .map(|stop_idx| &repository.stops[stop_idx as usize])
+            .collect())
+    }
+}
+
+pub fn stops_by_location<'a>(
+    repository: &'a Repository,
+    location: &Location,
+    walk_distance: Distance,
+    area_anchor: AreaAnchor,
+    code_hint: Option<&Coordinate>,
+) -> Result<LocationStops<'a>, raptor::Error> {
+    let stops = match location {
         Location::Area(id) => {
             trace!("Possible area: {id}");
             let area = repository
@@ -25,12 +91,37 @@ pub fn stops_by_location<'a>(
                 Ok(stops)
             } else {
                 warn!("Had to use coordinates to satisfy stops for area {id}");
-                let coordiante = repository.coordinate_by_area_idx(area.index);
-                Ok(repository
-                    .stops_by_coordinate(&coordiante, AVERAGE_STOP_DISTANCE)
+                let coordiante = match area_anchor {
+                    AreaAnchor::Centroid => repository.coordinate_by_area_idx(area.index),
+                    AreaAnchor::Medoid => repository
+                        .medoid_by_area_idx(area.index)
+                        .map(|stop| stop.coordinate)
+                        .unwrap_or_else(|| repository.coordinate_by_area_idx(area.index)),
+                };
+                // Half the diagonal of the area keeps the search within its footprint, but
+                // never below a stop distance so single stop or colocated areas find anything.
+                let radius = area
+                    .bbox
+                    .map(|bbox| {
+                        Distance::from_meters(
+                            (bbox.min.euclidean_distance(&bbox.max).as_meters() / 2.0)
+                                .max(AVERAGE_STOP_DISTANCE.as_meters()),
+                        )
+                    })
+                    .unwrap_or(AVERAGE_STOP_DISTANCE);
+                let stops: Vec<_> = repository
+                    .stops_by_coordinate(&coordiante, radius)
                     .into_iter()
                     .filter(|stop| repository.stop_idx_has_trips(stop.index))
-                    .collect())
+                    .collect();
+                if stops.is_empty() {
+                    Err(raptor::Error::NoNearbyStops)
+                } else {
+                    return Ok(LocationStops {
+                        stops,
+                        fallback: true,
+                    });
+                }
             }
         }
         Location::Stop(id) => {
@@ -38,23 +129,37 @@ pub fn stops_by_location<'a>(
             let stop = repository
                 .stop_by_id(id)
                 .ok_or(raptor::Error::InvalidStopID)?;
-            if let Some(station_idx) = stop.parent_index {
-                Ok(repository.stops_by_station(station_idx))
+            Ok(stops_by_stop(repository, stop))
+        }
+        Location::StopCode(code) => {
+            trace!("Possible stop code: {code}");
+            let stop = repository
+                .stop_by_code(code, code_hint)
+                .ok_or(raptor::Error::InvalidStopCode)?;
+            Ok(stops_by_stop(repository, stop))
+        }
+        Location::Route(id) => {
+            trace!("Possible route: {id}");
+            stops_by_route(repository, id, None)
+        }
+        Location::RouteDirection(id, direction_id) => {
+            trace!("Possible route: {id} in direction {direction_id}");
+            stops_by_route(repository, id, Some(*direction_id))
+        }
+        Location::Coordinate(coordinate) => {
+            let stops: Vec<_> = repository
+                .stops_by_coordinate(coordinate, walk_distance)
+                .into_iter()
+                .filter(|stop| repository.stop_idx_has_trips(stop.index))
+                .collect();
+            if stops.is_empty() {
+                Err(raptor::Error::NoNearbyStops)
             } else {
-                let stops = repository.stops_by_station(stop.index);
-                if stops.is_empty() {
-                    Ok(vec![stop])
-                } else {
-                    Ok(stops)
-                }
+                Ok(stops)
             }
         }
-        Location::Coordinate(coordinate) => Ok(repository
-            .stops_by_coordinate(coordinate, AVERAGE_STOP_DISTANCE)
-            .into_iter()
-            .filter(|stop| repository.stop_idx_has_trips(stop.index))
-            .collect()),
-    }
+    };
+    stops.map(LocationStops::from)
 }
 
 pub(crate) struct ServingRoute {
@@ -66,9 +171,10 @@ pub fn routes_serving_stop(repository: &Repository, stop_idx: u32, allocator: &m
     allocator.routes_serving_stops.extend(
         repository.stop_to_raptors[stop_idx as usize]
             .iter()
-            .filter_map(|route_idx| {
+            .flat_map(|route_idx| {
+                // Loop routes visit a stop more than once, every position is a place to board.
                 let route = &repository.raptor_routes[*route_idx as usize];
-                index_in_route(route, stop_idx).map(|idx_in_route| ServingRoute {
+                indices_in_route(route, stop_idx).map(|idx_in_route| ServingRoute {
                     route_idx: route.index,
                     idx_in_route,
                 })
@@ -76,23 +182,32 @@ pub fn routes_serving_stop(repository: &Repository, stop_idx: u32, allocator: &m
     )
 }
 
+/// Returns the first position of `stop_idx` in the route.
 pub fn index_in_route(route: &RaptorRoute, stop_idx: u32) -> Option<u32> {
-    for (index, route_stop_idx) in route.stops.iter().enumerate() {
-        if *route_stop_idx == stop_idx {
-            return Some(index as u32);
-        }
-    }
-    None
+    indices_in_route(route, stop_idx).next()
 }
 
-pub fn get_arrival_time(repository: &Repository, trip_idx: u32, p_idx: usize) -> Time {
-    let stop_times = repository.stop_times_by_trip_idx(trip_idx);
-    stop_times[p_idx].arrival_time
+/// Returns every position of `stop_idx` in the route, in order.
+/// Only loop routes have more than one.
+pub fn indices_in_route(route: &RaptorRoute, stop_idx: u32) -> impl Iterator<Item = u32> + '_ {
+    route
+        .stops
+        .iter()
+        .enumerate()
+        .filter(move |(_, route_stop_idx)| **route_stop_idx == stop_idx)
+        .map(|(index, _)| index as u32)
+}
+
+pub fn get_arrival_time(repository: &Repository, trip_idx: u32, p_idx: usize) -> Option<Time> {
+    repository
+        .stop_time_at_idx(trip_idx, p_idx)
+        .map(|stop_time| stop_time.arrival_time)
 }
 
-pub fn get_departure_time(repository: &Repository, trip_idx: u32, p_idx: usize) -> Time {
-    let stop_times = repository.stop_times_by_trip_idx(trip_idx);
-    stop_times[p_idx].departure_time
+pub fn get_departure_time(repository: &Repository, trip_idx: u32, p_idx: usize) -> Option<Time> {
+    repository
+        .stop_time_at_idx(trip_idx, p_idx)
+        .map(|stop_time| stop_time.departure_time)
 }
 
 /// Finds the latest trip that we can take from current stop based on the time
@@ -101,19 +216,19 @@ pub fn find_latest_trip<'a>(
     route: &'a RaptorRoute,
     p_idx: usize,
     max_arrival: Time,
+    running: Option<&BitSlice>,
 ) -> Option<&'a Trip> {
-    let idx = route
-        .trips
-        .partition_point(|&trip_idx| get_arrival_time(repository, trip_idx, p_idx) <= max_arrival);
+    let idx = route.trips.partition_point(|&trip_idx| {
+        get_arrival_time(repository, trip_idx, p_idx).is_some_and(|time| time <= max_arrival)
+    });
 
-    if idx == 0 {
-        None
-    } else {
-        route
-            .trips
-            .get(idx - 1)
-            .map(|&t_idx| &repository.trips[t_idx as usize])
-    }
+    route.trips[..idx]
+        .iter()
+        .rev()
+        .find(|&&t_idx| {
+            is_running(running, t_idx) && repository.stop_time_at_idx(t_idx, p_idx).is_some()
+        })
+        .map(|&t_idx| &repository.trips[t_idx as usize])
 }
 
 /// Finds the earliest trip that we can take from current stop based on the time
@@ -122,28 +237,43 @@ pub fn find_earliest_trip<'a>(
     route: &'a RaptorRoute,
     p_idx: usize,
     min_departure: Time,
+    running: Option<&BitSlice>,
 ) -> Option<&'a Trip> {
-    let idx = route
-        .trips
-        .partition_point(|&trip_idx| get_arrival_time(repository, trip_idx, p_idx) < min_departure);
-    route
-        .trips
-        .get(idx)
+    let idx = route.trips.partition_point(|&trip_idx| {
+        get_arrival_time(repository, trip_idx, p_idx).is_some_and(|time| time < min_departure)
+    });
+    route.trips[idx..]
+        .iter()
+        .find(|&&t_idx| {
+            is_running(running, t_idx) && repository.stop_time_at_idx(t_idx, p_idx).is_some()
+        })
         .map(|&t_idx| &repository.trips[t_idx as usize])
 }
 
+/// Whether a trip can be taken, all trips can without a service date.
+fn is_running(running: Option<&BitSlice>, trip_idx: u32) -> bool {
+    running.is_none_or(|running| running[trip_idx as usize])
+}
+
 pub fn transfer_duration<'a>(repository: &'a Repository, transfer: &'a Transfer) -> Duration {
     if let Some(duration) = transfer.min_transfer_time {
         duration
     } else {
-        let from = &repository.stops[transfer.from_stop_idx as usize];
-        let to = &repository.stops[transfer.to_stop_idx as usize];
-        time_to_walk(from.coordinate.network_distance(&to.coordinate))
+        repository.walk_time_between_stops(transfer.from_stop_idx, transfer.to_stop_idx)
     }
 }
 
+/// The average walking speed in meters per second used for every walk.
+pub const WALK_SPEED: f32 = 1.5;
+
 #[inline(always)]
 pub const fn time_to_walk(distance: Distance) -> Duration {
-    let duration = (distance.as_meters() / 1.5).ceil() as u32;
+    let duration = (distance.as_meters() / WALK_SPEED).ceil() as u32;
     Duration::from_seconds(duration)
 }
+
+/// The distance covered walking for `duration`, the inverse of [`time_to_walk`].
+#[inline(always)]
+pub const fn walk_distance_in(duration: Duration) -> Distance {
+    Distance::from_meters(duration.as_seconds() as f32 * WALK_SPEED)
+}
@@ -1,17 +1,46 @@
 use crate::{
     raptor::{
-        Allocator, LazyBuffer, Parent, Update, find_earliest_trip, find_latest_trip,
-        get_arrival_time, get_departure_time, time_to_walk, transfer_duration,
+        Allocator, LazyBuffer, Parent, ParentType, Penalty, Point, Update, best_key,
+        find_earliest_trip, find_latest_trip, get_arrival_time, get_departure_time,
     },
-    repository::{Repository, Trip},
-    shared::time,
+    repository::{Repository, RouteType, StopAccessType, Trip},
+    shared::{Coordinate, Distance, time},
 };
 use rayon::prelude::*;
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
 
 /// Explores all active routes and add any updates to the update buffer in the allocator.
 /// This is the core of the k-th round: it propagates travel times by one additional "hop"
 /// using only transit routes.
-pub fn explore_routes(repository: &Repository, allocator: &mut Allocator) {
+///
+/// In the first round every second waited before boarding adds `initial_wait_penalty`
+/// seconds to the [`Penalty`] the labels are compared by,
+/// see [`crate::raptor::Raptor::initial_wait_penalty`].
+/// Journeys riding a trip not run by the `preferred_agency` add its bonus to their [`Penalty`]
+/// once, see [`crate::raptor::Raptor::prefer_agency`].
+/// Only trips departing at least `boarding_time` after a stop is reached are boarded,
+/// see [`crate::raptor::Raptor::boarding_time`]. Changing vehicles at the stop the previous
+/// trip arrived at uses `same_stop_transfer_time` instead,
+/// see [`crate::raptor::Raptor::same_stop_transfer_time`].
+pub fn explore_routes(
+    repository: &Repository,
+    allocator: &mut Allocator,
+    initial_wait_penalty: f32,
+    preferred_agency: Option<&(Arc<str>, time::Duration)>,
+    boarding_time: time::Duration,
+    same_stop_transfer_time: time::Duration,
+) {
+    let first_round = allocator.round == 0;
+    // How each stop was reached in the previous round, none before the first round.
+    let stop_count = allocator.stop_count;
+    let prev_parents = allocator.round.checked_sub(1).map(|round| {
+        let offset = round * stop_count;
+        &allocator.parents[offset..offset + stop_count]
+    });
+    let running = match &allocator.target.first_boarding {
+        Some(first_boarding) if first_round => Some(first_boarding.as_bitslice()),
+        _ => allocator.target.running.as_deref(),
+    };
     let updates = allocator
         .active_mask
         .iter_ones()
@@ -22,9 +51,16 @@ pub fn explore_routes(repository: &Repository, allocator: &mut Allocator) {
                 let p_idx = allocator.active[route_idx];
 
                 let route = &repository.raptor_routes[route_idx];
+                let agency_penalty = preferred_agency
+                    .filter(|(agency_id, _)| {
+                        repository.display_route(route).agency_id != *agency_id
+                    })
+                    .map(|(_, bonus)| *bonus)
+                    .unwrap_or_default();
                 let mut active_trip: Option<&Trip> = None;
                 let mut boarding_stop: u32 = u32::MAX;
                 let mut boarding_p: usize = usize::MAX;
+                let mut boarding_penalty = Penalty::default();
 
                 // Optimization: We only start scanning from the earliest stop that was
                 // updated in the previous round (p_idx) to avoid redundant checks.
@@ -33,22 +69,33 @@ pub fn explore_routes(repository: &Repository, allocator: &mut Allocator) {
                     // PART A: Update arrival times
                     // If we are currently "on" a trip, check if it reaches this stop
                     // earlier than any path discovered in previous rounds.
+                    // A trip with fewer stop times than its route is skipped rather than panicking.
                     if let Some(trip) = active_trip
-                        && let arrival_time = get_arrival_time(repository, trip.index, i)
-                        && arrival_time < allocator.tau_star[stop_idx as usize].unwrap_or(time::MAX)
-                        && arrival_time < allocator.target.tau_star
+                        && let Some(arrival_time) = get_arrival_time(repository, trip.index, i)
+                        && boarding_penalty.key(arrival_time)
+                            < best_key(
+                                &allocator.tau_star,
+                                &allocator.tau_penalties,
+                                stop_idx as usize,
+                            )
+                        && boarding_penalty.key(arrival_time) < allocator.target.tau_star
+                        && let Some(departure_time) =
+                            get_departure_time(repository, trip.index, boarding_p)
                     {
-                        buffer.push(Update::new(
-                            stop_idx,
-                            arrival_time,
-                            Parent::new_transit(
-                                boarding_stop.into(),
-                                stop_idx.into(),
-                                trip.index,
-                                get_departure_time(repository, trip.index, boarding_p),
+                        buffer.push(
+                            Update::new(
+                                stop_idx,
                                 arrival_time,
-                            ),
-                        ));
+                                Parent::new_transit(
+                                    boarding_stop.into(),
+                                    stop_idx.into(),
+                                    trip.index,
+                                    departure_time,
+                                    arrival_time,
+                                ),
+                            )
+                            .with_penalty(boarding_penalty),
+                        );
                     }
 
                     // PART B: Trip Hopping
@@ -56,18 +103,42 @@ pub fn explore_routes(repository: &Repository, allocator: &mut Allocator) {
                     // arrival time at this stop from the PREVIOUS round is earlier
                     // than the departure of a trip on the current route.
                     let prev_label = allocator.prev_labels[stop_idx as usize].unwrap_or(time::MAX);
+                    // Staying at the stop the previous trip arrived at needs no walk to the platform.
+                    let same_stop = prev_parents
+                        .and_then(|parents| parents[stop_idx as usize])
+                        .is_some_and(|parent| matches!(parent.parent_type, ParentType::Transit(_)));
+                    let connection_time = if same_stop {
+                        same_stop_transfer_time
+                    } else {
+                        boarding_time
+                    };
+                    let ready = prev_label.checked_add(connection_time).unwrap_or(time::MAX);
                     let current_trip_dep = active_trip
-                        .map(|t| get_departure_time(repository, t.index, i))
+                        .and_then(|t| get_departure_time(repository, t.index, i))
                         .unwrap_or(time::MAX);
 
-                    if prev_label <= current_trip_dep
+                    if ready <= current_trip_dep
                         && let Some(earlier_trip) =
-                            find_earliest_trip(repository, route, i, prev_label)
+                            find_earliest_trip(repository, route, i, ready, running)
                     {
                         // We found a better trip to board (or a fresh start for this route).
                         active_trip = Some(earlier_trip);
                         boarding_stop = stop_idx;
                         boarding_p = i;
+                        boarding_penalty = allocator.prev_penalties[stop_idx as usize];
+                        boarding_penalty.agency = boarding_penalty.agency.max(agency_penalty);
+                        if first_round && initial_wait_penalty > 0.0 {
+                            let wait = get_departure_time(repository, earlier_trip.index, i)
+                                .map(|departure| {
+                                    departure
+                                        .as_seconds()
+                                        .saturating_sub(prev_label.as_seconds())
+                                })
+                                .unwrap_or_default();
+                            boarding_penalty.wait = time::Duration::from_seconds(
+                                (wait as f32 * initial_wait_penalty).round() as u32,
+                            );
+                        }
                     }
                 }
                 buffer.swap()
@@ -78,7 +149,18 @@ pub fn explore_routes(repository: &Repository, allocator: &mut Allocator) {
 }
 
 /// Reverse exploration for Latest Departure Time (LDT) queries.
-pub fn explore_routes_reverse(repository: &Repository, allocator: &mut Allocator) {
+///
+/// Stops are labeled `boarding_time` before the trip departs, see
+/// [`crate::raptor::Raptor::boarding_time`].
+pub fn explore_routes_reverse(
+    repository: &Repository,
+    allocator: &mut Allocator,
+    boarding_time: time::Duration,
+) {
+    let running = match &allocator.target.first_boarding {
+        Some(first_boarding) if allocator.round == 0 => Some(first_boarding.as_bitslice()),
+        _ => allocator.target.running.as_deref(),
+    };
     let updates = allocator
         .active_mask
         .iter_ones()
@@ -100,36 +182,39 @@ pub fn explore_routes_reverse(repository: &Repository, allocator: &mut Allocator
 
                     // PART A: If we have an active trip, can we leave this stop LATER
                     // than previously known and still catch it?
-                    if let Some(trip) = active_trip {
-                        let dep_time = get_departure_time(repository, trip.index, i as usize);
-
-                        if dep_time > allocator.tau_star[stop_idx as usize].unwrap_or(time::MIN) {
-                            buffer.push(Update::new(
-                                stop_idx,
+                    if let Some(trip) = active_trip
+                        && let Some(dep_time) =
+                            get_departure_time(repository, trip.index, i as usize)
+                        && let Some(label) = dep_time.checked_sub(boarding_time)
+                        && label > allocator.tau_star[stop_idx as usize].unwrap_or(time::MIN)
+                        && let Some(arrival_time) =
+                            get_arrival_time(repository, trip.index, alighting_p)
+                    {
+                        buffer.push(Update::new(
+                            stop_idx,
+                            label,
+                            Parent::new_transit(
+                                (stop_idx).into(),
+                                alighting_stop.into(),
+                                trip.index,
                                 dep_time,
-                                Parent::new_transit(
-                                    (stop_idx).into(),
-                                    alighting_stop.into(),
-                                    trip.index,
-                                    dep_time,
-                                    get_arrival_time(repository, trip.index, alighting_p),
-                                ),
-                            ));
-                        }
+                                arrival_time,
+                            ),
+                        ));
                     }
 
                     // PART B: Look for a trip that arrives at this stop LATER than
                     // our previous round's departure label, allowing us to shift our whole schedule later.
                     let prev_label = allocator.prev_labels[stop_idx as usize].unwrap_or(time::MIN);
                     let trip_arrival = active_trip
-                        .map(|t| get_arrival_time(repository, t.index, i as usize))
+                        .and_then(|t| get_arrival_time(repository, t.index, i as usize))
                         .unwrap_or(time::MIN);
 
                     // If this stop has a departure label LATER than our current trip's arrival,
                     // find a trip that arrives even later (but still before the label)
                     if prev_label >= trip_arrival
                         && let Some(later_trip) =
-                            find_latest_trip(repository, route, i as usize, prev_label)
+                            find_latest_trip(repository, route, i as usize, prev_label, running)
                     {
                         active_trip = Some(later_trip);
                         alighting_stop = stop_idx;
@@ -146,7 +231,22 @@ pub fn explore_routes_reverse(repository: &Repository, allocator: &mut Allocator
 /// Handles footpaths and transfers between stops.
 /// In RAPTOR, transfers are processed after route exploration to ensure that
 /// round k transit results can be used as the starting point for round k+1.
-pub fn explore_transfers(allow_walk: bool, repository: &Repository, allocator: &mut Allocator) {
+/// Walk and transfer durations are rounded up to `round_to` when given,
+/// see [`crate::raptor::Raptor::round_transfers_to`].
+pub fn explore_transfers(
+    allow_walk: bool,
+    round_to: Option<time::Duration>,
+    by_mode: &HashMap<(RouteType, RouteType), time::Duration>,
+    repository: &Repository,
+    allocator: &mut Allocator,
+) {
+    let round = |duration: time::Duration| match round_to {
+        Some(unit) => duration.round_up_to(unit),
+        None => duration,
+    };
+    // The parents of the current round, borrowed apart from the update buffer.
+    let offset = allocator.round * allocator.stop_count;
+    let parents = &allocator.parents[offset..offset + allocator.stop_count];
     let updates = allocator
         .marked_stops
         .iter_ones()
@@ -154,56 +254,82 @@ pub fn explore_transfers(allow_walk: bool, repository: &Repository, allocator: &
         .map_init(
             || LazyBuffer::<Update>::new(32),
             |buffer, stop_idx| {
+                let mode = transit_mode(repository, parents, stop_idx, by_mode);
+                let duration = |computed: time::Duration, to_stop_idx: u32| {
+                    round(
+                        mode.and_then(|mode| {
+                            mode_transfer_time(repository, by_mode, mode, to_stop_idx, false)
+                        })
+                        .unwrap_or(computed),
+                    )
+                };
+                // Walking on keeps the penalty of the label walked from.
+                let penalty = allocator.curr_penalties[stop_idx];
                 // All the possible transfers
                 repository.stop_to_transfers[stop_idx]
                     .iter()
                     .for_each(|transfer_idx| {
                         let transfer = &repository.transfers[*transfer_idx as usize];
                         let departure_time = allocator.curr_labels[stop_idx].unwrap_or(time::MAX);
-                        let arrival_time = departure_time + transfer_duration(repository, transfer);
-                        if arrival_time
-                            < allocator.tau_star[transfer.to_stop_idx as usize].unwrap_or(time::MAX)
-                            && arrival_time < allocator.target.tau_star
+                        // An overflowing arrival is skipped rather than wrapping to an early time.
+                        if let Some(arrival_time) = departure_time.checked_add(duration(
+                            repository.transfer_durations[*transfer_idx as usize],
+                            transfer.to_stop_idx,
+                        )) && penalty.key(arrival_time)
+                            < best_key(
+                                &allocator.tau_star,
+                                &allocator.tau_penalties,
+                                transfer.to_stop_idx as usize,
+                            )
+                            && penalty.key(arrival_time) < allocator.target.tau_star
                         {
-                            buffer.push(Update::new(
-                                transfer.to_stop_idx,
-                                arrival_time,
-                                Parent::new_transfer(
-                                    (stop_idx as u32).into(),
-                                    transfer.to_stop_idx.into(),
-                                    departure_time,
+                            buffer.push(
+                                Update::new(
+                                    transfer.to_stop_idx,
                                     arrival_time,
-                                ),
-                            ));
+                                    Parent::new_transfer(
+                                        (stop_idx as u32).into(),
+                                        transfer.to_stop_idx.into(),
+                                        departure_time,
+                                        arrival_time,
+                                    ),
+                                )
+                                .with_penalty(penalty),
+                            );
                         }
                     });
 
                 if allow_walk {
-                    let current_stop = &repository.stops[stop_idx];
                     repository.stop_to_walk_stop[stop_idx]
                         .iter()
-                        .for_each(|next_stop_idx| {
+                        .zip(repository.stop_to_walk_durations[stop_idx].iter())
+                        .for_each(|(next_stop_idx, walk_duration)| {
                             let next_stop = &repository.stops[*next_stop_idx as usize];
-                            let walking_distance = current_stop
-                                .coordinate
-                                .network_distance(&next_stop.coordinate);
                             let departure_time =
                                 allocator.curr_labels[stop_idx].unwrap_or(time::MAX);
-                            let arrival_time = departure_time + time_to_walk(walking_distance);
-                            if arrival_time
-                                < allocator.tau_star[next_stop.index as usize].unwrap_or(time::MAX)
-                                && arrival_time < allocator.target.tau_star
+                            if let Some(arrival_time) =
+                                departure_time.checked_add(duration(*walk_duration, *next_stop_idx))
+                                && penalty.key(arrival_time)
+                                    < best_key(
+                                        &allocator.tau_star,
+                                        &allocator.tau_penalties,
+                                        next_stop.index as usize,
+                                    )
+                                && penalty.key(arrival_time) < allocator.target.tau_star
                             {
-                                buffer.push(Update::new(
-                                    next_stop.index,
-                                    arrival_time,
-                                    Parent::new_walk(
-                                        (stop_idx as u32).into(),
-                                        next_stop.index.into(),
-                                        departure_time,
+                                buffer.push(
+                                    Update::new(
+                                        next_stop.index,
                                         arrival_time,
-                                    ),
-                                ));
+                                        Parent::new_walk(
+                                            (stop_idx as u32).into(),
+                                            next_stop.index.into(),
+                                            departure_time,
+                                            arrival_time,
+                                        ),
+                                    )
+                                    .with_penalty(penalty),
+                                );
                             }
                         });
                 }
@@ -216,9 +342,18 @@ pub fn explore_transfers(allow_walk: bool, repository: &Repository, allocator: &
 
 pub fn explore_transfers_reverse(
     allow_walk: bool,
+    round_to: Option<time::Duration>,
+    by_mode: &HashMap<(RouteType, RouteType), time::Duration>,
     repository: &Repository,
     allocator: &mut Allocator,
 ) {
+    let round = |duration: time::Duration| match round_to {
+        Some(unit) => duration.round_up_to(unit),
+        None => duration,
+    };
+    // The parents of the current round, borrowed apart from the update buffer.
+    let offset = allocator.round * allocator.stop_count;
+    let parents = &allocator.parents[offset..offset + allocator.stop_count];
     let updates = allocator
         .marked_stops
         .iter_ones()
@@ -226,14 +361,25 @@ pub fn explore_transfers_reverse(
         .map_init(
             || LazyBuffer::<Update>::new(32),
             |buffer, stop_idx| {
+                let mode = transit_mode(repository, parents, stop_idx, by_mode);
+                let duration = |computed: time::Duration, to_stop_idx: u32| {
+                    round(
+                        mode.and_then(|mode| {
+                            mode_transfer_time(repository, by_mode, mode, to_stop_idx, true)
+                        })
+                        .unwrap_or(computed),
+                    )
+                };
                 // All the possible transfers
                 repository.stop_to_transfers[stop_idx]
                     .iter()
                     .for_each(|transfer_idx| {
                         let transfer = &repository.transfers[*transfer_idx as usize];
                         let arrival_time = allocator.curr_labels[stop_idx].unwrap_or(time::MIN);
-                        let departure_time = arrival_time - transfer_duration(repository, transfer);
-                        if departure_time
+                        if let Some(departure_time) = arrival_time.checked_sub(duration(
+                            repository.transfer_durations[*transfer_idx as usize],
+                            transfer.to_stop_idx,
+                        )) && departure_time
                             > allocator.tau_star[transfer.to_stop_idx as usize].unwrap_or(time::MIN)
                         {
                             buffer.push(Update::new(
@@ -250,18 +396,17 @@ pub fn explore_transfers_reverse(
                     });
 
                 if allow_walk {
-                    let current_stop = &repository.stops[stop_idx];
                     repository.stop_to_walk_stop[stop_idx]
                         .iter()
-                        .for_each(|next_stop_idx| {
+                        .zip(repository.stop_to_walk_durations[stop_idx].iter())
+                        .for_each(|(next_stop_idx, walk_duration)| {
                             let next_stop = &repository.stops[*next_stop_idx as usize];
-                            let walking_distance = current_stop
-                                .coordinate
-                                .network_distance(&next_stop.coordinate);
                             let arrival_time = allocator.curr_labels[stop_idx].unwrap_or(time::MIN);
-                            let departure_time = arrival_time - time_to_walk(walking_distance);
-                            if departure_time
-                                > allocator.tau_star[next_stop.index as usize].unwrap_or(time::MIN)
+                            if let Some(departure_time) =
+                                arrival_time.checked_sub(duration(*walk_duration, *next_stop_idx))
+                                && departure_time
+                                    > allocator.tau_star[next_stop.index as usize]
+                                        .unwrap_or(time::MIN)
                             {
                                 buffer.push(Update::new(
                                     next_stop.index,
@@ -282,3 +427,144 @@ pub fn explore_transfers_reverse(
         .flatten();
     allocator.updates.par_extend(updates);
 }
+
+/// The mode of the trip that reached `stop_idx` in the current round, boarded there for
+/// reverse searches. `None` when no transfer times by mode are configured.
+fn transit_mode(
+    repository: &Repository,
+    parents: &[Option<Parent>],
+    stop_idx: usize,
+    by_mode: &HashMap<(RouteType, RouteType), time::Duration>,
+) -> Option<RouteType> {
+    if by_mode.is_empty() {
+        return None;
+    }
+    match parents[stop_idx]?.parent_type {
+        ParentType::Transit(trip_idx) => Some(repository.route_by_trip_idx(trip_idx).mode()),
+        ParentType::Transfer | ParentType::Walk => None,
+    }
+}
+
+/// The configured transfer time between `mode` and the modes serving `stop_idx`, the longest
+/// if several match. Reverse searches transfer from the modes at `stop_idx` to `mode`,
+/// see [`crate::raptor::Raptor::transfer_time_by_mode`].
+fn mode_transfer_time(
+    repository: &Repository,
+    by_mode: &HashMap<(RouteType, RouteType), time::Duration>,
+    mode: RouteType,
+    stop_idx: u32,
+    reverse: bool,
+) -> Option<time::Duration> {
+    repository.stop_to_raptors[stop_idx as usize]
+        .iter()
+        .filter_map(|raptor_idx| {
+            let other = repository
+                .display_route(&repository.raptor_routes[*raptor_idx as usize])
+                .mode();
+            let key = if reverse {
+                (other, mode)
+            } else {
+                (mode, other)
+            };
+            by_mode.get(&key).copied()
+        })
+        .max()
+}
+
+/// Boards trips with continuous pickup at the point of their shape nearest to `coordinate`,
+/// see [`crate::raptor::Raptor::continuous_pickup`].
+///
+/// The boarding time is interpolated along `distance_traveled` between the surrounding stops
+/// and every later stop of the trip is reached in the first round. `ready` is the earliest
+/// time the rider can be at the boarding point.
+pub fn explore_continuous_pickups(
+    repository: &Repository,
+    allocator: &mut Allocator,
+    coordinate: &Coordinate,
+    walk_distance: Distance,
+    ready: impl Fn(&Coordinate) -> time::Time + Sync,
+) {
+    let running = allocator.target.running.as_deref();
+    let updates = repository
+        .trips
+        .par_iter()
+        .filter(|trip| running.is_none_or(|running| running[trip.index as usize]))
+        .flat_map_iter(|trip| {
+            continuous_boarding(repository, trip, coordinate, walk_distance, &ready)
+                .unwrap_or_default()
+        });
+    allocator.updates.par_extend(updates);
+}
+
+fn continuous_boarding(
+    repository: &Repository,
+    trip: &Trip,
+    coordinate: &Coordinate,
+    walk_distance: Distance,
+    ready: impl Fn(&Coordinate) -> time::Time,
+) -> Option<Vec<Update>> {
+    let route = &repository.routes[trip.route_idx as usize];
+    let stop_times = repository.stop_times_by_trip_idx(trip.index);
+    if route.continuous_pickup.is_none()
+        && stop_times
+            .iter()
+            .all(|stop_time| stop_time.continuous_pickup.is_none())
+    {
+        return None;
+    }
+
+    let (point, traveled) = repository
+        .shapes_by_trip_idx(trip.index)?
+        .iter()
+        .filter_map(|shape| Some((shape.coordinate, shape.distance_traveled?)))
+        .map(|(point, traveled)| (point, traveled, point.network_distance(coordinate)))
+        .filter(|(_, _, walk)| *walk <= walk_distance)
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal))
+        .map(|(point, traveled, _)| (point, traveled))?;
+
+    // The segment the boarding point lies on, the stop-time value overrides the route's.
+    let next = stop_times
+        .iter()
+        .position(|stop_time| stop_time.distance_traveled.is_some_and(|d| d > traveled))?;
+    let previous = &stop_times[next.checked_sub(1)?];
+    let allowed = previous
+        .continuous_pickup
+        .as_ref()
+        .or(route.continuous_pickup.as_ref())
+        .is_some_and(|access| !matches!(access, StopAccessType::NoneAvailable));
+    if !allowed {
+        return None;
+    }
+
+    let start = previous.distance_traveled?.as_meters();
+    let end = stop_times[next].distance_traveled?.as_meters();
+    let fraction = (traveled.as_meters() - start) / (end - start);
+    let running = stop_times[next]
+        .arrival_time
+        .as_seconds()
+        .saturating_sub(previous.departure_time.as_seconds());
+    let departure_time = previous.departure_time
+        + time::Duration::from_seconds((running as f32 * fraction).round() as u32);
+    if departure_time < ready(&point) {
+        return None;
+    }
+
+    Some(
+        stop_times[next..]
+            .iter()
+            .map(|stop_time| {
+                Update::new(
+                    stop_time.stop_idx,
+                    stop_time.arrival_time,
+                    Parent::new_transit(
+                        Point::Coordinate(point),
+                        stop_time.stop_idx.into(),
+                        trip.index,
+                        departure_time,
+                        stop_time.arrival_time,
+                    ),
+                )
+            })
+            .collect(),
+    )
+}
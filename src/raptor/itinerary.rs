@@ -4,9 +4,14 @@ use crate::{
         location::{Location, Point},
     },
     repository::Repository,
-    shared::{Distance, time::Time},
+    shared::{
+        Coordinate, Distance, cardinal,
+        time::{Duration, Time},
+    },
 };
 use serde::Serialize;
+use std::cmp::Ordering;
+use thiserror::Error;
 
 #[derive(Debug, Clone)]
 pub struct Leg {
@@ -16,6 +21,43 @@ pub struct Leg {
     pub arrival_time: Time,
     pub stops: Vec<LegStop>,
     pub leg_type: LegType,
+    /// How crowded the vehicle is, only known for transit legs with real-time data.
+    pub occupancy: Option<OccupancyStatus>,
+    /// Compass bearing in degrees from the start to the end of walk and transfer legs.
+    pub bearing: Option<f64>,
+    /// Distance covered by the leg, along `shape_dist_traveled` where the feed has it
+    /// and in straight lines between the coordinates of the stops otherwise.
+    pub distance: Distance,
+}
+
+impl Leg {
+    /// Time between departure and arrival.
+    pub fn duration(&self) -> Duration {
+        Duration::from_seconds(
+            self.arrival_time
+                .as_seconds()
+                .saturating_sub(self.departue_time.as_seconds()),
+        )
+    }
+
+    /// The cardinal direction (e.g. `"NE"`) of walk and transfer legs, see [`Leg::bearing`].
+    pub fn cardinal(&self) -> Option<&'static str> {
+        self.bearing.map(cardinal)
+    }
+}
+
+/// The crowding level of a vehicle, mirroring the GTFS-RT `OccupancyStatus` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OccupancyStatus {
+    Empty,
+    ManySeatsAvailable,
+    FewSeatsAvailable,
+    StandingRoomOnly,
+    CrushedStandingRoomOnly,
+    Full,
+    NotAcceptingPassengers,
+    NoDataAvailable,
+    NotBoardable,
 }
 
 #[derive(Debug, Clone, Copy, Serialize)]
@@ -44,18 +86,37 @@ pub struct LegStop {
 }
 
 impl LegStop {
+    /// Builds the stops visited by a leg.
+    ///
+    /// Transit legs yield every stop from boarding to alighting, while walk and
+    /// transfer legs yield exactly two stops, the origin and the destination.
     pub(crate) fn generate_stops(parent: &Parent, repository: &Repository) -> Vec<Self> {
         match parent.parent_type {
             ParentType::Transit(trip_idx) => {
                 let trip = &repository.trips[trip_idx as usize];
                 let stop_times = repository.stop_times_by_trip_idx(trip.index);
                 let mut stops = Vec::with_capacity(stop_times.len());
-                if let Point::Stop(from_idx) = parent.from
-                    && let Point::Stop(to_idx) = parent.to
-                {
+                if let Point::Coordinate(coordinate) = parent.from {
+                    // Boarded between stops on a continuous pickup trip.
+                    stops.push(LegStop {
+                        location: coordinate.into(),
+                        departure_time: parent.departure_time,
+                        arrival_time: parent.departure_time,
+                        distance_traveled: None,
+                    });
+                }
+                if let Point::Stop(to_idx) = parent.to {
+                    // Loop routes visit a stop more than once, so the times pick the right visit.
                     let mut in_trip = false;
                     for stop_time in stop_times {
-                        if stop_time.stop_idx == from_idx {
+                        let boards_here = match parent.from {
+                            Point::Stop(from_idx) => {
+                                stop_time.stop_idx == from_idx
+                                    && stop_time.departure_time == parent.departure_time
+                            }
+                            Point::Coordinate(_) => stop_time.arrival_time >= parent.departure_time,
+                        };
+                        if boards_here {
                             in_trip = true;
                         }
                         if in_trip {
@@ -66,7 +127,9 @@ impl LegStop {
                                 arrival_time: stop_time.arrival_time,
                                 distance_traveled: stop_time.distance_traveled,
                             });
-                            if stop_time.stop_idx == to_idx && in_trip {
+                            if stop_time.stop_idx == to_idx
+                                && stop_time.arrival_time == parent.arrival_time
+                            {
                                 break;
                             }
                         }
@@ -75,8 +138,20 @@ impl LegStop {
 
                 stops
             }
-            ParentType::Transfer => vec![],
-            ParentType::Walk => vec![],
+            ParentType::Transfer | ParentType::Walk => vec![
+                LegStop {
+                    location: point_to_location(&parent.from, repository),
+                    departure_time: parent.departure_time,
+                    arrival_time: parent.departure_time,
+                    distance_traveled: None,
+                },
+                LegStop {
+                    location: point_to_location(&parent.to, repository),
+                    departure_time: parent.arrival_time,
+                    arrival_time: parent.arrival_time,
+                    distance_traveled: None,
+                },
+            ],
         }
     }
 }
@@ -86,6 +161,21 @@ pub struct Itinerary {
     pub from: Location,
     pub to: Location,
     pub legs: Vec<Leg>,
+    /// Index of the stop the journey starts from (`Stop.index`), where a coordinate origin snapped to.
+    pub access_stop: Option<u32>,
+    /// Distance walked from a coordinate origin to `access_stop`, zero for other origins.
+    /// Estimated from the straight line with a circuity factor for the street network, or
+    /// along route shapes with [`Raptor::shape_walks`](crate::raptor::Raptor::shape_walks).
+    pub access_walk_distance: Distance,
+    /// Index of the stop the journey ends at (`Stop.index`), where a coordinate destination snapped to.
+    pub egress_stop: Option<u32>,
+    /// Distance walked from `egress_stop` to a coordinate destination, zero for other destinations.
+    /// Estimated from the straight line with a circuity factor for the street network, or
+    /// along route shapes with [`Raptor::shape_walks`](crate::raptor::Raptor::shape_walks).
+    pub egress_walk_distance: Distance,
+    /// Set when an area origin or destination had no stops with service
+    /// and the stops around it were used instead.
+    pub area_fallback: bool,
 }
 
 impl Itinerary {
@@ -95,22 +185,238 @@ impl Itinerary {
         path: Vec<Parent>,
         repository: &Repository,
     ) -> Self {
-        let legs = path
+        let access_stop = path.first().and_then(|parent| parent.from.stop_idx());
+        let egress_stop = path.last().and_then(|parent| parent.to.stop_idx());
+        let access_walk_distance = walk_distance(&from, access_stop, repository);
+        let egress_walk_distance = walk_distance(&to, egress_stop, repository);
+        let legs: Vec<Leg> = path
             .into_iter()
             .map(|parent| {
                 let leg_from = point_to_location(&parent.from, repository);
                 let leg_to = point_to_location(&parent.to, repository);
+                let stops = LegStop::generate_stops(&parent, repository);
                 Leg {
+                    distance: leg_distance(&stops, repository),
                     from: leg_from,
                     to: leg_to,
                     departue_time: parent.departure_time,
                     arrival_time: parent.arrival_time,
-                    stops: LegStop::generate_stops(&parent, repository),
+                    stops,
                     leg_type: parent.parent_type.into(),
+                    occupancy: None,
+                    bearing: match parent.parent_type {
+                        ParentType::Transit(_) => None,
+                        ParentType::Transfer | ParentType::Walk => Some(
+                            point_to_coordinate(&parent.from, repository)
+                                .bearing(&point_to_coordinate(&parent.to, repository)),
+                        ),
+                    },
                 }
             })
             .collect();
-        Self { from, to, legs }
+        Self {
+            from,
+            to,
+            legs: merge_same_trip_legs(legs),
+            access_stop,
+            access_walk_distance,
+            egress_stop,
+            egress_walk_distance,
+            area_fallback: false,
+        }
+    }
+
+    /// Departure time of the first leg, `None` without legs.
+    pub fn departure_time(&self) -> Option<Time> {
+        self.legs.first().map(|leg| leg.departue_time)
+    }
+
+    /// Arrival time of the last leg, `None` without legs.
+    pub fn arrival_time(&self) -> Option<Time> {
+        self.legs.last().map(|leg| leg.arrival_time)
+    }
+
+    /// Number of changes between vehicles, one less than the transit legs.
+    pub fn transfers(&self) -> usize {
+        self.legs
+            .iter()
+            .filter(|leg| matches!(leg.leg_type, LegType::Transit(_)))
+            .count()
+            .saturating_sub(1)
+    }
+
+    /// Whether this itinerary is at least as good as `other` in every criterion: it arrives
+    /// no later, departs no earlier and has no more transfers. Itineraries without legs
+    /// neither dominate nor are dominated.
+    ///
+    /// Two itineraries with the same times and transfers dominate each other.
+    pub fn dominates(&self, other: &Itinerary) -> bool {
+        match (self.criteria(), other.criteria()) {
+            (
+                Some((arrival, departure, transfers)),
+                Some((o_arrival, o_departure, o_transfers)),
+            ) => arrival <= o_arrival && departure >= o_departure && transfers <= o_transfers,
+            _ => false,
+        }
+    }
+
+    /// Orders by dominance, `Less` when this itinerary is strictly better, `Equal` when
+    /// both are as good in every criterion and `None` when each is better at something.
+    pub fn dominance_cmp(&self, other: &Itinerary) -> Option<Ordering> {
+        match (self.dominates(other), other.dominates(self)) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (false, false) => None,
+        }
+    }
+
+    fn criteria(&self) -> Option<(Time, Time, usize)> {
+        Some((
+            self.arrival_time()?,
+            self.departure_time()?,
+            self.transfers(),
+        ))
+    }
+
+    /// Resolves the occupancy of every transit leg from its trip index (`Trip.index`).
+    ///
+    /// `occupancy` is typically backed by the latest real-time vehicle positions,
+    /// legs whose trip has no known status keep `None`.
+    pub fn apply_occupancy<F>(&mut self, occupancy: F)
+    where
+        F: Fn(u32) -> Option<OccupancyStatus>,
+    {
+        self.legs.iter_mut().for_each(|leg| {
+            leg.occupancy = match leg.leg_type {
+                LegType::Transit(trip_idx) => occupancy(trip_idx),
+                LegType::Transfer | LegType::Walk => None,
+            };
+        });
+    }
+
+    /// Checks that the legs are in chronological order, that every leg starts where the
+    /// previous one ends and that transit legs match the stop times of their trip.
+    ///
+    /// Meant for tests and debugging backtracking, returns the first broken invariant.
+    pub fn validate(&self, repository: &Repository) -> Result<(), ItineraryError> {
+        let mut previous: Option<&Leg> = None;
+        for (i, leg) in self.legs.iter().enumerate() {
+            if leg.departue_time > leg.arrival_time
+                || previous.is_some_and(|previous| previous.arrival_time > leg.departue_time)
+            {
+                return Err(ItineraryError::NotChronological(i));
+            }
+            if previous.is_some_and(|previous| previous.to != leg.from) {
+                return Err(ItineraryError::Disconnected(i));
+            }
+            if let LegType::Transit(trip_idx) = leg.leg_type
+                && !leg_matches_trip(leg, trip_idx, repository)
+            {
+                return Err(ItineraryError::ScheduleMismatch(i));
+            }
+            previous = Some(leg);
+        }
+        Ok(())
+    }
+}
+
+/// Whether the trip departs the leg's first stop and later arrives at its last stop at the
+/// leg's times. Legs boarded between stops only have their arrival checked.
+fn leg_matches_trip(leg: &Leg, trip_idx: u32, repository: &Repository) -> bool {
+    let Some(trip) = repository.trips.get(trip_idx as usize) else {
+        return false;
+    };
+    let stop_id = |location: &Location| match location {
+        Location::Stop(id) => repository.stop_by_id(id).map(|stop| stop.index),
+        _ => None,
+    };
+    let stop_times = repository.stop_times_by_trip_idx(trip.index);
+    let boarding = match &leg.from {
+        Location::Coordinate(_) => stop_times
+            .iter()
+            .position(|stop_time| stop_time.arrival_time >= leg.departue_time),
+        from => stop_times.iter().position(|stop_time| {
+            Some(stop_time.stop_idx) == stop_id(from)
+                && stop_time.departure_time == leg.departue_time
+        }),
+    };
+    boarding.is_some_and(|boarding| {
+        stop_times[boarding..].iter().any(|stop_time| {
+            Some(stop_time.stop_idx) == stop_id(&leg.to)
+                && stop_time.arrival_time == leg.arrival_time
+        })
+    })
+}
+
+/// An invariant broken by an [`Itinerary`], see [`Itinerary::validate`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ItineraryError {
+    #[error("Leg {0} ends before it starts or before the previous leg ends")]
+    NotChronological(usize),
+    #[error("Leg {0} does not start where the previous leg ends")]
+    Disconnected(usize),
+    #[error("Leg {0} does not match the stop times of its trip")]
+    ScheduleMismatch(usize),
+}
+
+/// Distance between a coordinate location and the stop it snapped to.
+fn walk_distance(location: &Location, stop_idx: Option<u32>, repository: &Repository) -> Distance {
+    match (location, stop_idx) {
+        (Location::Coordinate(coordinate), Some(stop_idx)) => repository.stops[stop_idx as usize]
+            .coordinate
+            .network_distance(coordinate),
+        _ => Distance::from_meters(0.0),
+    }
+}
+
+/// Coalesces adjacent transit legs on the same trip into one, so a continuous ride that
+/// was split during backtracking doesn't show up as a phantom transfer.
+fn merge_same_trip_legs(legs: Vec<Leg>) -> Vec<Leg> {
+    let mut merged: Vec<Leg> = Vec::with_capacity(legs.len());
+    for leg in legs {
+        match (merged.last_mut(), leg.leg_type) {
+            (Some(last), LegType::Transit(trip_idx)) if matches!(last.leg_type, LegType::Transit(last_trip_idx) if last_trip_idx == trip_idx) =>
+            {
+                // The stop where the ride was split ends the first leg and starts the second.
+                last.stops.extend(leg.stops.into_iter().skip(1));
+                last.to = leg.to;
+                last.arrival_time = leg.arrival_time;
+                last.distance = last.distance + leg.distance;
+            }
+            _ => merged.push(leg),
+        }
+    }
+    merged
+}
+
+/// Distance between the first and last stop of a leg, see [`Leg::distance`].
+fn leg_distance(stops: &[LegStop], repository: &Repository) -> Distance {
+    if let (Some(first), Some(last)) = (stops.first(), stops.last())
+        && let (Some(start), Some(end)) = (first.distance_traveled, last.distance_traveled)
+        && end >= start
+    {
+        return end - start;
+    }
+    let coordinate = |location: &Location| match location {
+        Location::Coordinate(coordinate) => Some(*coordinate),
+        Location::Stop(id) => repository.coordinate_by_stop_id(id),
+        _ => None,
+    };
+    stops
+        .windows(2)
+        .filter_map(|pair| {
+            Some(coordinate(&pair[0].location)?.euclidean_distance(&coordinate(&pair[1].location)?))
+        })
+        .fold(Distance::from_meters(0.0), |total, distance| {
+            total + distance
+        })
+}
+
+fn point_to_coordinate(point: &Point, repository: &Repository) -> Coordinate {
+    match point {
+        Point::Coordinate(coordinate) => *coordinate,
+        Point::Stop(idx) => repository.stops[*idx as usize].coordinate,
     }
 }
 
@@ -123,3 +429,289 @@ fn point_to_location(point: &Point, repository: &Repository) -> Location {
         }
     }
 }
+
+#[test]
+fn walk_leg_stops_test() {
+    use crate::repository::Stop;
+    use crate::shared::Coordinate;
+
+    let mut repository = Repository::new();
+    repository.stops = vec![Stop {
+        index: 0,
+        id: "stop".into(),
+        coordinate: Coordinate::new(59.33, 18.06),
+        ..Default::default()
+    }]
+    .into();
+    let parent = Parent::new_walk(
+        Coordinate::new(59.331, 18.061).into(),
+        0.into(),
+        Time::from_seconds(100),
+        Time::from_seconds(250),
+    );
+    let stops = LegStop::generate_stops(&parent, &repository);
+    assert_eq!(stops.len(), 2);
+    assert!(matches!(stops[0].location, Location::Coordinate(_)));
+    assert_eq!(stops[0].departure_time, Time::from_seconds(100));
+    assert_eq!(stops[0].arrival_time, Time::from_seconds(100));
+    assert!(matches!(&stops[1].location, Location::Stop(id) if id.as_ref() == "stop"));
+    assert_eq!(stops[1].departure_time, Time::from_seconds(250));
+    assert_eq!(stops[1].arrival_time, Time::from_seconds(250));
+}
+
+#[test]
+fn apply_occupancy_test() {
+    use std::collections::HashMap;
+
+    let repository = crate::repository::sample_repository();
+    let mut itinerary = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    assert!(itinerary.legs.iter().all(|leg| leg.occupancy.is_none()));
+
+    let trip_idx = itinerary
+        .legs
+        .iter()
+        .find_map(|leg| match leg.leg_type {
+            LegType::Transit(trip_idx) => Some(trip_idx),
+            _ => None,
+        })
+        .unwrap();
+    let occupancy = HashMap::from([(trip_idx, OccupancyStatus::StandingRoomOnly)]);
+    itinerary.apply_occupancy(|trip_idx| occupancy.get(&trip_idx).copied());
+
+    itinerary.legs.iter().for_each(|leg| match leg.leg_type {
+        LegType::Transit(idx) if idx == trip_idx => {
+            assert_eq!(leg.occupancy, Some(OccupancyStatus::StandingRoomOnly))
+        }
+        _ => assert_eq!(leg.occupancy, None),
+    });
+}
+
+#[test]
+fn coordinate_snapping_test() {
+    use crate::shared::Coordinate;
+
+    let repository = crate::repository::sample_repository();
+    let origin = Coordinate::new(59.3305, 18.0);
+    let itinerary = repository
+        .router(origin.into(), Location::Stop("S_E1".into()))
+        .departure_at(Time::from_hms("06:55:00").unwrap())
+        .solve()
+        .unwrap();
+
+    let a1 = repository.stop_by_id("S_A1").unwrap();
+    let e1 = repository.stop_by_id("S_E1").unwrap();
+    assert_eq!(itinerary.access_stop, Some(a1.index));
+    assert_eq!(
+        itinerary.access_walk_distance,
+        a1.coordinate.network_distance(&origin)
+    );
+    assert!(itinerary.access_walk_distance > Distance::from_meters(0.0));
+    assert_eq!(itinerary.egress_stop, Some(e1.index));
+    assert_eq!(itinerary.egress_walk_distance, Distance::from_meters(0.0));
+}
+
+#[test]
+fn walk_leg_bearing_test() {
+    use crate::repository::Stop;
+
+    let mut repository = Repository::new();
+    repository.stops = vec![
+        Stop {
+            index: 0,
+            id: "west".into(),
+            coordinate: Coordinate::new(59.33, 18.06),
+            ..Default::default()
+        },
+        Stop {
+            index: 1,
+            id: "east".into(),
+            coordinate: Coordinate::new(59.33, 18.065),
+            ..Default::default()
+        },
+    ]
+    .into();
+    let path = vec![Parent::new_walk(
+        0.into(),
+        1.into(),
+        Time::from_seconds(0),
+        Time::from_seconds(300),
+    )];
+    let itinerary = Itinerary::new(
+        Location::Stop("west".into()),
+        Location::Stop("east".into()),
+        path,
+        &repository,
+    );
+    let leg = &itinerary.legs[0];
+    assert!((leg.bearing.unwrap() - 90.0).abs() < 0.1);
+    assert_eq!(leg.cardinal(), Some("E"));
+}
+
+#[test]
+fn merge_same_trip_legs_test() {
+    let repository = crate::repository::sample_repository();
+    let stop_idx = |id: &str| repository.stop_by_id(id).unwrap().index;
+    let trip_idx = repository.trip_by_id("R1_L_0700").unwrap().index;
+    let path = vec![
+        Parent::new_transit(
+            stop_idx("S_A1").into(),
+            stop_idx("S_C1").into(),
+            trip_idx,
+            Time::from_hms("07:00:00").unwrap(),
+            Time::from_hms("07:06:00").unwrap(),
+        ),
+        Parent::new_transit(
+            stop_idx("S_C1").into(),
+            stop_idx("S_E1").into(),
+            trip_idx,
+            Time::from_hms("07:06:00").unwrap(),
+            Time::from_hms("07:12:00").unwrap(),
+        ),
+    ];
+    let itinerary = Itinerary::new(
+        Location::Stop("S_A1".into()),
+        Location::Stop("S_E1".into()),
+        path,
+        &repository,
+    );
+    assert_eq!(itinerary.legs.len(), 1);
+    let leg = &itinerary.legs[0];
+    assert!(matches!(&leg.to, Location::Stop(id) if id.as_ref() == "S_E1"));
+    assert_eq!(leg.departue_time, Time::from_hms("07:00:00").unwrap());
+    assert_eq!(leg.arrival_time, Time::from_hms("07:12:00").unwrap());
+    assert_eq!(leg.stops.len(), 5);
+}
+
+#[test]
+fn validate_test() {
+    use crate::shared::time::Duration;
+
+    let repository = crate::repository::sample_repository();
+    let router = || repository.router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()));
+    let itinerary = router()
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    assert!(itinerary.legs.len() > 1);
+    assert_eq!(itinerary.validate(&repository), Ok(()));
+    let itinerary = router()
+        .arrival_at(Time::from_hms("08:00:00").unwrap())
+        .solve()
+        .unwrap();
+    assert_eq!(itinerary.validate(&repository), Ok(()));
+
+    let itinerary = router()
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    let mut corrupted = itinerary.clone();
+    corrupted.legs[0].departue_time += Duration::from_minutes(1);
+    assert_eq!(
+        corrupted.validate(&repository),
+        Err(ItineraryError::ScheduleMismatch(0))
+    );
+
+    let mut corrupted = itinerary.clone();
+    corrupted.legs[1].from = Location::Stop("S_X".into());
+    assert_eq!(
+        corrupted.validate(&repository),
+        Err(ItineraryError::Disconnected(1))
+    );
+
+    let mut corrupted = itinerary.clone();
+    corrupted.legs[1].departue_time = corrupted.legs[0].arrival_time - Duration::from_minutes(1);
+    assert_eq!(
+        corrupted.validate(&repository),
+        Err(ItineraryError::NotChronological(1))
+    );
+}
+
+#[test]
+fn leg_distance_test() {
+    let repository = crate::repository::sample_repository();
+    let itinerary = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_E1".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    let leg = &itinerary.legs[0];
+    assert!(matches!(leg.leg_type, LegType::Transit(_)));
+    assert_eq!(leg.stops.len(), 5);
+    assert_eq!(leg.duration(), Duration::from_minutes(12));
+    // Taken from shape_dist_traveled of the first and last stop.
+    assert_eq!(leg.distance, Distance::from_meters(4537.0));
+
+    // Without shape_dist_traveled the straight lines between the stops are used instead.
+    let mut stops = leg.stops.clone();
+    stops
+        .iter_mut()
+        .for_each(|stop| stop.distance_traveled = None);
+    let coordinate = |id: &str| repository.coordinate_by_stop_id(id).unwrap();
+    let expected = ["S_A1", "S_B", "S_C1", "S_D", "S_E1"]
+        .windows(2)
+        .map(|pair| coordinate(pair[0]).euclidean_distance(&coordinate(pair[1])))
+        .fold(Distance::from_meters(0.0), |total, distance| {
+            total + distance
+        });
+    assert!(leg_distance(&stops, &repository).approx_eq(&expected, Distance::from_meters(1.0)));
+}
+
+#[test]
+fn dominance_test() {
+    let time = |hms: &str| Time::from_hms(hms).unwrap();
+    let leg = |leg_type: LegType, departure: &str, arrival: &str| Leg {
+        from: Location::Stop("A".into()),
+        to: Location::Stop("B".into()),
+        departue_time: time(departure),
+        arrival_time: time(arrival),
+        stops: vec![],
+        leg_type,
+        occupancy: None,
+        bearing: None,
+        distance: Distance::from_meters(0.0),
+    };
+    let itinerary = |legs: Vec<Leg>| Itinerary {
+        from: Location::Stop("A".into()),
+        to: Location::Stop("B".into()),
+        legs,
+        access_stop: None,
+        access_walk_distance: Distance::from_meters(0.0),
+        egress_stop: None,
+        egress_walk_distance: Distance::from_meters(0.0),
+        area_fallback: false,
+    };
+
+    let direct = itinerary(vec![leg(LegType::Transit(0), "07:00:00", "07:30:00")]);
+    let change = itinerary(vec![
+        leg(LegType::Transit(1), "07:00:00", "07:10:00"),
+        leg(LegType::Walk, "07:10:00", "07:12:00"),
+        leg(LegType::Transit(2), "07:15:00", "07:30:00"),
+    ]);
+    assert_eq!(direct.transfers(), 0);
+    assert_eq!(change.transfers(), 1);
+    assert!(direct.dominates(&change));
+    assert!(!change.dominates(&direct));
+    assert_eq!(direct.dominance_cmp(&change), Some(Ordering::Less));
+    assert_eq!(change.dominance_cmp(&direct), Some(Ordering::Greater));
+    assert_eq!(direct.dominance_cmp(&direct.clone()), Some(Ordering::Equal));
+
+    // Arriving earlier with a transfer against a later one-seat ride.
+    let faster = itinerary(vec![
+        leg(LegType::Transit(1), "07:00:00", "07:10:00"),
+        leg(LegType::Transit(2), "07:12:00", "07:20:00"),
+    ]);
+    assert!(!faster.dominates(&direct));
+    assert!(!direct.dominates(&faster));
+    assert_eq!(faster.dominance_cmp(&direct), None);
+
+    // Leaving later for the same arrival is better.
+    let later = itinerary(vec![leg(LegType::Transit(3), "07:05:00", "07:30:00")]);
+    assert_eq!(later.dominance_cmp(&direct), Some(Ordering::Less));
+
+    let empty = itinerary(vec![]);
+    assert_eq!(empty.dominance_cmp(&direct), None);
+}
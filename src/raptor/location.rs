@@ -4,11 +4,26 @@ use crate::{
 };
 use std::sync::Arc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Location {
     Area(Arc<str>),
     Stop(Arc<str>),
     Coordinate(Coordinate),
+    /// Any stop served by the route with this `Route.id`, e.g. "put me on line 4".
+    ///
+    /// As the origin of a departure search the first trip boarded is always on the route, as
+    /// the destination of an arrival search the last one is. The other end of a search only
+    /// picks the route's stops, any trip can be taken to or from them.
+    Route(Arc<str>),
+    /// Like [`Location::Route`] but only the trips with this `direction_id`, e.g. "line 4 inbound".
+    ///
+    /// Restricts the same trip as [`Location::Route`] to these, the other end of a search
+    /// ignores the direction.
+    RouteDirection(Arc<str>, u8),
+    /// The stop with this rider-facing `Stop.code`, see [`Repository::stop_by_code`].
+    ///
+    /// [`Repository::stop_by_code`]: crate::repository::Repository::stop_by_code
+    StopCode(Arc<str>),
 }
 
 impl From<&Area> for Location {
@@ -41,12 +56,32 @@ impl From<Coordinate> for Location {
     }
 }
 
+/// How a [`Location::Area`] is reduced to a single coordinate when one is needed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AreaAnchor {
+    /// The average coordinate of all stops in the area.
+    #[default]
+    Centroid,
+    /// The stop in the area closest to the centroid, so the anchor is always a real stop.
+    Medoid,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Point {
     Coordinate(Coordinate),
     Stop(u32),
 }
 
+impl Point {
+    /// Returns the stop index if the point is a stop.
+    pub fn stop_idx(&self) -> Option<u32> {
+        match self {
+            Point::Stop(idx) => Some(*idx),
+            Point::Coordinate(_) => None,
+        }
+    }
+}
+
 impl From<u32> for Point {
     fn from(value: u32) -> Self {
         Self::Stop(value)
@@ -58,3 +93,21 @@ impl From<Coordinate> for Point {
         Self::Coordinate(value)
     }
 }
+
+#[test]
+fn location_from_entities_test() {
+    let repository = crate::repository::sample_repository();
+    let stop = repository.stop_by_id("S_A1").unwrap();
+    assert!(matches!(Location::from(stop), Location::Stop(id) if id == stop.id));
+    assert!(matches!(Location::from(stop.clone()), Location::Stop(id) if id == stop.id));
+
+    let area = repository.areas.first().unwrap();
+    assert!(matches!(Location::from(area), Location::Area(id) if id == area.id));
+    assert!(matches!(Location::from(area.clone()), Location::Area(id) if id == area.id));
+
+    let coordinate = Coordinate::new(59.33, 18.04);
+    assert!(matches!(
+        Location::from(coordinate),
+        Location::Coordinate(value) if value == coordinate
+    ));
+}
@@ -3,8 +3,11 @@ mod discovery;
 mod explorer;
 mod itinerary;
 mod location;
+mod options;
 mod path;
+mod result;
 mod state;
+mod walk;
 
 use std::mem;
 
@@ -12,16 +15,26 @@ pub use allocator::*;
 pub(crate) use discovery::*;
 pub use itinerary::*;
 pub use location::*;
+pub use options::*;
 pub(crate) use path::*;
+pub use result::*;
 pub(crate) use state::*;
+pub(crate) use walk::*;
 
 use crate::{
     raptor::explorer::{
-        explore_routes, explore_routes_reverse, explore_transfers, explore_transfers_reverse,
+        explore_continuous_pickups, explore_routes, explore_routes_reverse, explore_transfers,
+        explore_transfers_reverse,
+    },
+    repository::{Repository, RouteType},
+    shared::{
+        geo::{AVERAGE_STOP_DISTANCE, Coordinate, Distance},
+        time::{self, Duration, Time},
     },
-    repository::Repository,
-    shared::time::{self, Time},
 };
+use bitvec::prelude::*;
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use std::{collections::HashMap, sync::Arc};
 use thiserror::Error;
 use tracing::{trace, warn};
 
@@ -33,10 +46,18 @@ pub enum Error {
     InvalidAreaID,
     #[error("Stop id does not match any entry")]
     InvalidStopID,
+    #[error("Route id does not match any entry")]
+    InvalidRouteID,
+    #[error("Stop code does not match any entry")]
+    InvalidStopCode,
     #[error("A route was found but failed to build it")]
     FailedToBuildRoute,
     #[error("Could not find a route")]
     NoRouteFound,
+    #[error("No stops with service within walking distance")]
+    NoNearbyStops,
+    #[error("Could not find a route within the maximum journey duration")]
+    NoRouteWithinTimeBudget,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -54,6 +75,18 @@ impl TimeConstraint {
     }
 }
 
+/// A snapshot of the search progress, reported once per round to the
+/// callback registered with [`Raptor::on_round`].
+#[derive(Debug, Clone, Copy)]
+pub struct RoundStats {
+    /// The round that just finished, starting from 0.
+    pub round: usize,
+    /// The number of stops that were explored in this round.
+    pub marked_stops: usize,
+    /// The best time found at the target so far, if any.
+    pub best_time: Option<Time>,
+}
+
 /// The execution engine for the Round-Based Public Transit Routing (RAPTOR) algorithm.
 ///
 /// This struct holds the search parameters and a reference to the underlying transit
@@ -69,8 +102,29 @@ pub struct Raptor<'a> {
     from: Location,
     to: Location,
     time_constraint: TimeConstraint,
+    /// Only trips running on this date are taken, see [`Raptor::on_date`].
+    service_date: Option<NaiveDate>,
+    /// Whether the departure time is when the rider is ready at the origin, see [`Raptor::ready_at`].
+    ready: bool,
+    service_day_aware: bool,
+    /// Explores the whole network instead of stopping at the target, see [`Raptor::one_to_all`].
+    one_to_all: bool,
     allow_walks: bool,
-    // walk_distance: Distance,
+    continuous_pickup: bool,
+    shape_walks: bool,
+    round_transfers_to: Option<Duration>,
+    max_journey_duration: Option<Duration>,
+    transfer_time_by_mode: HashMap<(RouteType, RouteType), Duration>,
+    walk_distance: Distance,
+    access_walk: Option<Distance>,
+    egress_walk: Option<Distance>,
+    on_round: Option<Box<dyn FnMut(RoundStats) + 'a>>,
+    area_anchor: AreaAnchor,
+    stop_code_hint: Option<Coordinate>,
+    initial_wait_penalty: f32,
+    boarding_time: Duration,
+    same_stop_transfer_time: Option<Duration>,
+    preferred_agency: Option<(Arc<str>, Duration)>,
 }
 
 impl<'a> Raptor<'a> {
@@ -90,7 +144,26 @@ impl<'a> Raptor<'a> {
             from,
             to,
             time_constraint: TimeConstraint::Departure(Time::now()),
+            service_date: None,
+            ready: false,
+            service_day_aware: false,
+            one_to_all: false,
             allow_walks: true,
+            continuous_pickup: false,
+            shape_walks: false,
+            round_transfers_to: None,
+            max_journey_duration: None,
+            transfer_time_by_mode: HashMap::new(),
+            walk_distance: AVERAGE_STOP_DISTANCE,
+            access_walk: None,
+            egress_walk: None,
+            on_round: None,
+            area_anchor: AreaAnchor::default(),
+            stop_code_hint: None,
+            initial_wait_penalty: 0.0,
+            boarding_time: Duration::from_seconds(0),
+            same_stop_transfer_time: None,
+            preferred_agency: None,
         }
     }
 
@@ -101,6 +174,46 @@ impl<'a> Raptor<'a> {
     /// even for the same origin/destination.
     pub fn departure_at(mut self, departure: Time) -> Self {
         self.time_constraint = TimeConstraint::Departure(departure);
+        self.ready = false;
+        self
+    }
+
+    /// Sets both the service date, see [`Raptor::on_date`], and the departure time
+    /// within that day, see [`Raptor::departure_at`].
+    pub fn departure_at_datetime(self, departure: NaiveDateTime) -> Self {
+        self.on_date(departure.date())
+            .departure_at(Time::from_seconds(
+                departure.time().num_seconds_from_midnight(),
+            ))
+    }
+
+    /// Only takes trips whose service runs on `date`, following the calendar and its
+    /// exceptions. By default every trip is taken regardless of its service.
+    pub fn on_date(mut self, date: NaiveDate) -> Self {
+        self.service_date = Some(date);
+        self
+    }
+
+    /// Sets the time the rider is ready to leave the origin.
+    ///
+    /// Unlike [`Raptor::departure_at`], which treats the time as when the rider can board at
+    /// any stop near a coordinate origin, the walk to each of those stops is added first.
+    /// So the first boarding is never earlier than the rider can actually get to the stop.
+    pub fn ready_at(mut self, ready: Time) -> Self {
+        self.time_constraint = TimeConstraint::Departure(ready);
+        self.ready = true;
+        self
+    }
+
+    /// Also considers the previous service day's trips running past midnight.
+    ///
+    /// GTFS numbers trips running past midnight from the day they started, e.g. `24:45:00`,
+    /// so at `00:30:00` they are missed by a plain search. With this enabled the departure is
+    /// also tried as `24:30:00` and the earlier arriving itinerary is returned, times of an
+    /// overnight itinerary stay on the previous service day's clock. Only applies to
+    /// [`Raptor::solve`] and [`Raptor::solve_with_allocator`] departure searches.
+    pub fn service_day_aware(mut self, value: bool) -> Self {
+        self.service_day_aware = value;
         self
     }
 
@@ -119,12 +232,182 @@ impl<'a> Raptor<'a> {
         self
     }
 
+    /// Applies every setting in `options`, settings left as `None` keep their current value.
+    pub fn with_options(mut self, options: RouteOptions) -> Self {
+        if let Some(constraint) = options.time_constraint {
+            self = self.with_time_constraint(constraint);
+        }
+        if let Some(date) = options.service_date {
+            self = self.on_date(date);
+        }
+        if let Some(duration) = options.max_journey_duration {
+            self = self.max_journey_duration(duration);
+        }
+        if let Some(value) = options.allow_walks {
+            self = self.allow_walks(value);
+        }
+        if let Some(distance) = options.walk_distance {
+            self = self.walk_distance(distance);
+        }
+        self
+    }
+
     /// If the raptor algorithm is allowed to walk to stops, this can/will improve travel time in most cases.
     pub fn allow_walks(mut self, value: bool) -> Self {
         self.allow_walks = value;
         self
     }
 
+    /// Allows boarding trips with continuous pickup (GTFS `continuous_pickup`) anywhere along
+    /// their shape between scheduled stops, off by default so such trips are treated as regular.
+    ///
+    /// Only applies to departure searches from a [`Location::Coordinate`], the boarding time is
+    /// interpolated along the shape's `shape_dist_traveled` within the access walk.
+    pub fn continuous_pickup(mut self, value: bool) -> Self {
+        self.continuous_pickup = value;
+        self
+    }
+
+    /// Rounds every walk and transfer duration up to a multiple of `unit`, e.g. whole minutes.
+    ///
+    /// Schedules are minute-granular, so this gives cleaner and more conservative times
+    /// than arriving at `08:13:47`. Off by default.
+    pub fn round_transfers_to(mut self, unit: Duration) -> Self {
+        self.round_transfers_to = Some(unit);
+        self
+    }
+
+    /// Overrides the transfer and walk time between stops when changing from the first to
+    /// the second [`RouteType`], e.g. a longer time from buses to a rail platform. Empty by default.
+    ///
+    /// The arriving mode is the trip that reached the stop, the departing mode is taken from the
+    /// routes serving the stop transferred to, the longest configured time wins if several match.
+    pub fn transfer_time_by_mode(
+        mut self,
+        times: HashMap<(RouteType, RouteType), Duration>,
+    ) -> Self {
+        self.transfer_time_by_mode = times;
+        self
+    }
+
+    /// Discards every journey taking longer than `duration` from the departure time,
+    /// or to the arrival time for arrival searches. Unlimited by default.
+    ///
+    /// Prunes the search, if the target can't be reached in time it fails with
+    /// [`Error::NoRouteWithinTimeBudget`].
+    pub fn max_journey_duration(mut self, duration: Duration) -> Self {
+        self.max_journey_duration = Some(duration);
+        self
+    }
+
+    /// Sets the maximum distance walked between a coordinate and the stops around it.
+    ///
+    /// Applies to both ends of the journey unless overridden by
+    /// [`Raptor::access_walk`] or [`Raptor::egress_walk`].
+    pub fn walk_distance(mut self, distance: Distance) -> Self {
+        self.walk_distance = distance;
+        self
+    }
+
+    /// Sets the maximum distance walked from the origin to the first stop.
+    ///
+    /// This is a hard cap, if no stop with service is within it the search fails
+    /// with [`Error::NoNearbyStops`].
+    pub fn access_walk(mut self, distance: Distance) -> Self {
+        self.access_walk = Some(distance);
+        self
+    }
+
+    /// Sets the maximum distance walked from the last stop to the destination.
+    ///
+    /// This is a hard cap, if no stop with service is within it the search fails
+    /// with [`Error::NoNearbyStops`].
+    pub fn egress_walk(mut self, distance: Distance) -> Self {
+        self.egress_walk = Some(distance);
+        self
+    }
+
+    /// Sets how an area is anchored when its stops have no service and nearby stops
+    /// have to be found by coordinate instead. Defaults to [`AreaAnchor::Centroid`].
+    pub fn area_anchor(mut self, anchor: AreaAnchor) -> Self {
+        self.area_anchor = anchor;
+        self
+    }
+
+    /// Estimates access and egress walks between a coordinate and its stop along nearby
+    /// route shapes instead of from the straight line, see [`Repository::shape_walk_distance`].
+    /// Defaults to `false`.
+    pub fn shape_walks(mut self, value: bool) -> Self {
+        self.shape_walks = value;
+        self
+    }
+
+    /// Ignores the destination so every stop reachable from the origin gets a label,
+    /// the origin stops included. Only meaningful with [`Raptor::solve_raw`].
+    pub(crate) fn one_to_all(mut self) -> Self {
+        self.one_to_all = true;
+        self
+    }
+
+    /// Sets the position an ambiguous [`Location::StopCode`] is resolved against, the stop
+    /// with the code closest to it is used. Without a hint the first match is used.
+    pub fn stop_code_hint(mut self, hint: Coordinate) -> Self {
+        self.stop_code_hint = Some(hint);
+        self
+    }
+
+    /// Sets how many seconds of penalty every second waited at the origin before the
+    /// first boarding adds to the arrival time the search compares, defaults to `0.0`.
+    ///
+    /// Pure earliest arrival is indifferent to how long you wait before the first trip,
+    /// with a penalty the router prefers departing sooner when arrivals are close or tie.
+    /// The times in the returned [`Itinerary`] are never penalized.
+    /// Only applies to departure searches.
+    pub fn initial_wait_penalty(mut self, penalty: f32) -> Self {
+        self.initial_wait_penalty = penalty;
+        self
+    }
+
+    /// Sets the time it takes to find the platform and board, added before every boarding
+    /// including the first one. Defaults to zero.
+    ///
+    /// Unlike transfer times this also applies at the origin, so a trip departing sooner
+    /// than `boarding_time` after reaching its stop is never taken.
+    pub fn boarding_time(mut self, duration: Duration) -> Self {
+        self.boarding_time = duration;
+        self
+    }
+
+    /// Sets the time it takes to change vehicles without leaving the stop the previous trip
+    /// arrived at, e.g. across the same platform. Used instead of the [`Raptor::boarding_time`]
+    /// for such changes, which defaults to it. Transfers and walks to other stops are unaffected.
+    /// Only applies to departure searches.
+    pub fn same_stop_transfer_time(mut self, duration: Duration) -> Self {
+        self.same_stop_transfer_time = Some(duration);
+        self
+    }
+
+    /// Prefers trips run by the agency `agency_id` (`Route.agency_id`) when arrival times
+    /// are within `bonus` of each other.
+    ///
+    /// The bonus is applied once by delaying the arrival the search compares for journeys
+    /// riding any other agency's trips, so a preferred journey is never assumed to arrive
+    /// earlier than it can and connections are still made from the real arrival.
+    /// The times in the returned [`Itinerary`] are never affected.
+    pub fn prefer_agency(mut self, agency_id: Arc<str>, bonus: Duration) -> Self {
+        self.preferred_agency = Some((agency_id, bonus));
+        self
+    }
+
+    /// Registers a callback invoked at the end of every round with the current [`RoundStats`].
+    ///
+    /// Useful for progress reporting and debugging, when no callback is registered
+    /// no stats are collected.
+    pub fn on_round(mut self, callback: impl FnMut(RoundStats) + 'a) -> Self {
+        self.on_round = Some(Box::new(callback));
+        self
+    }
+
     /// Wrapper around slove_with_allocator but creates the allocator internally.
     ///
     /// Executes the multi-criteria search and returns the optimal itinerary.
@@ -160,9 +443,205 @@ impl<'a> Raptor<'a> {
     /// This method leverages the parallel optimizations in the underlying [`Repository`].
     /// Execution time typically scales with the number of possible routes between
     /// the origin and destination.
-    pub fn solve_with_allocator(self, allocator: &mut Allocator) -> Result<Itinerary, self::Error> {
-        let from_stops = stops_by_location(self.repository, &self.from)?;
-        let to_stops = stops_by_location(self.repository, &self.to)?;
+    pub fn solve_with_allocator(
+        mut self,
+        allocator: &mut Allocator,
+    ) -> Result<Itinerary, self::Error> {
+        let itinerary = self.solve_once(allocator);
+        let TimeConstraint::Departure(time) = self.time_constraint else {
+            return itinerary;
+        };
+        if !self.service_day_aware || time.as_seconds() >= Duration::from_days(1).as_seconds() {
+            return itinerary;
+        }
+
+        // Trips of the previous service day run past midnight as 24:00:00 and later.
+        self.time_constraint = TimeConstraint::Departure(time + Duration::from_days(1));
+        self.service_date = self.service_date.and_then(|date| date.pred_opt());
+        allocator.reset();
+        let overnight = self.solve_once(allocator);
+        let arrival = |itinerary: &Itinerary| itinerary.legs.last().map(|leg| leg.arrival_time);
+        match (itinerary, overnight) {
+            (Ok(itinerary), Ok(overnight))
+                if arrival(&overnight)
+                    < arrival(&itinerary).map(|time| time + Duration::from_days(1)) =>
+            {
+                Ok(overnight)
+            }
+            (Err(_), Ok(overnight)) => Ok(overnight),
+            (itinerary, _) => itinerary,
+        }
+    }
+
+    fn solve_once(&mut self, allocator: &mut Allocator) -> Result<Itinerary, self::Error> {
+        self.search(allocator)?;
+        if let Some(target_stop) = allocator.target.best_stop
+            && let Some(target_round) = allocator.target.best_round
+        {
+            let path = backtrack(
+                self.repository,
+                allocator,
+                target_stop,
+                target_round,
+                self.time_constraint,
+            )?;
+            Ok(self.itinerary(path, allocator))
+        } else {
+            Err(Self::no_route(allocator))
+        }
+    }
+
+    /// Executes the search and returns the raw RAPTOR state instead of an itinerary,
+    /// see [`RaptorResult`]. Fails only if the locations can't be resolved to stops.
+    pub fn solve_raw(mut self) -> Result<RaptorResult, self::Error> {
+        let mut allocator = Allocator::new(self.repository);
+        self.search(&mut allocator)?;
+        Ok(RaptorResult::new(allocator))
+    }
+
+    /// Wrapper around solve_pareto_with_allocator but creates the allocator internally.
+    pub fn solve_pareto(self) -> Result<Vec<Itinerary>, self::Error> {
+        let mut allocator = Allocator::new(self.repository);
+        self.solve_pareto_with_allocator(&mut allocator)
+    }
+
+    /// Executes the search and returns every Pareto-optimal itinerary with respect to
+    /// the time at the target and the number of trips taken.
+    ///
+    /// Since round `K` only finds journeys using exactly `K` trips, every round that
+    /// improves the target yields a journey that is faster than all journeys with fewer
+    /// trips. The itineraries are ordered by the number of trips, so the first one has
+    /// the fewest transfers and the last one is the same as returned by [`Raptor::solve`].
+    pub fn solve_pareto_with_allocator(
+        mut self,
+        allocator: &mut Allocator,
+    ) -> Result<Vec<Itinerary>, self::Error> {
+        self.search(allocator)?;
+        if allocator.target.journeys.is_empty() {
+            return Err(Self::no_route(allocator));
+        }
+        allocator
+            .target
+            .journeys
+            .iter()
+            .map(|(target_stop, target_round)| {
+                let path = backtrack(
+                    self.repository,
+                    allocator,
+                    *target_stop,
+                    *target_round,
+                    self.time_constraint,
+                )?;
+                Ok(self.itinerary(path, allocator))
+            })
+            .collect()
+    }
+
+    fn itinerary(&self, path: Vec<Parent>, allocator: &Allocator) -> Itinerary {
+        let mut itinerary =
+            Itinerary::new(self.from.clone(), self.to.clone(), path, self.repository);
+        itinerary.area_fallback = allocator.target.area_fallback;
+        if self.shape_walks {
+            if let (Location::Coordinate(from), Some(stop_idx)) =
+                (&self.from, itinerary.access_stop)
+            {
+                itinerary.access_walk_distance =
+                    self.repository.shape_walk_distance(from, stop_idx);
+            }
+            if let (Location::Coordinate(to), Some(stop_idx)) = (&self.to, itinerary.egress_stop) {
+                itinerary.egress_walk_distance = self.repository.shape_walk_distance(to, stop_idx);
+            }
+        }
+        itinerary
+    }
+
+    /// Distance walked between a coordinate origin or destination and a stop.
+    fn access_walk_distance(&self, coordinate: &Coordinate, stop_idx: u32) -> Distance {
+        if self.shape_walks {
+            self.repository.shape_walk_distance(coordinate, stop_idx)
+        } else {
+            coordinate.network_distance(&self.repository.stops[stop_idx as usize].coordinate)
+        }
+    }
+
+    /// Only blames the time budget if it cut a journey short, a target that can't be
+    /// reached at all is [`Error::NoRouteFound`] regardless of the budget.
+    fn no_route(allocator: &Allocator) -> self::Error {
+        match allocator.target.pruned {
+            true => self::Error::NoRouteWithinTimeBudget,
+            false => self::Error::NoRouteFound,
+        }
+    }
+
+    /// The trips a route location can be boarded on, limited to the running trips.
+    /// `None` for any other location.
+    fn route_trips(
+        &self,
+        location: &Location,
+        allocator: &Allocator,
+    ) -> Option<BitVec<usize, Lsb0>> {
+        let (id, direction_id) = match location {
+            Location::Route(id) => (id, None),
+            Location::RouteDirection(id, direction_id) => (id, Some(*direction_id)),
+            _ => return None,
+        };
+        let route_idx = self.repository.route_by_id(id).map(|route| route.index);
+        let running = allocator.target.running.as_deref();
+        Some(
+            self.repository
+                .trips
+                .iter()
+                .map(|trip| {
+                    Some(trip.route_idx) == route_idx
+                        && direction_id
+                            .is_none_or(|direction_id| trip.direction_id == Some(direction_id))
+                        && running.is_none_or(|running| running[trip.index as usize])
+                })
+                .collect(),
+        )
+    }
+
+    /// Runs the RAPTOR rounds, leaving the labels, parents and target in the allocator.
+    fn search(&mut self, allocator: &mut Allocator) -> Result<(), self::Error> {
+        let access_walk = self.access_walk.unwrap_or(self.walk_distance);
+        let egress_walk = self.egress_walk.unwrap_or(self.walk_distance);
+        let continuous_from = match (&self.from, self.time_constraint) {
+            (Location::Coordinate(coordinate), TimeConstraint::Departure(_))
+                if self.continuous_pickup =>
+            {
+                Some(*coordinate)
+            }
+            _ => None,
+        };
+        // A rider waiting along a continuous pickup route might have no stop within reach.
+        let from_stops = match stops_by_location(
+            self.repository,
+            &self.from,
+            access_walk,
+            self.area_anchor,
+            self.stop_code_hint.as_ref(),
+        ) {
+            Err(Error::NoNearbyStops) if continuous_from.is_some() => Vec::new().into(),
+            from_stops => from_stops?,
+        };
+        let to_stops = stops_by_location(
+            self.repository,
+            &self.to,
+            egress_walk,
+            self.area_anchor,
+            self.stop_code_hint.as_ref(),
+        )?;
+        allocator.target.area_fallback = from_stops.fallback || to_stops.fallback;
+        allocator.target.running = self.service_date.map(|date| {
+            (0..self.repository.trips.len() as u32)
+                .map(|trip_idx| self.repository.trip_runs_on(trip_idx, date))
+                .collect()
+        });
+        allocator.target.first_boarding = match self.time_constraint {
+            TimeConstraint::Departure(_) => self.route_trips(&self.from, allocator),
+            TimeConstraint::Arrival(_) => self.route_trips(&self.to, allocator),
+        };
+        let (from_stops, to_stops) = (from_stops.stops, to_stops.stops);
 
         match self.time_constraint {
             TimeConstraint::Arrival(time) => {
@@ -172,16 +651,52 @@ impl<'a> Raptor<'a> {
                 });
                 allocator.target.stops = from_stops.into_iter().map(|stop| stop.index).collect();
                 allocator.target.tau_star = time::MIN;
+                allocator.target.time_limit = self
+                    .max_journey_duration
+                    .map(|duration| time.checked_sub(duration).unwrap_or(time::MIN));
                 allocator.active.fill(u32::MIN);
             }
             TimeConstraint::Departure(time) => {
                 from_stops.into_iter().for_each(|stop| {
+                    let label = match &self.from {
+                        Location::Coordinate(coordinate) if self.ready => {
+                            time + time_to_walk(self.access_walk_distance(coordinate, stop.index))
+                        }
+                        _ => time,
+                    };
                     allocator.marked_stops.set(stop.index as usize, true);
-                    allocator.curr_labels[stop.index as usize] = Some(time);
+                    allocator.curr_labels[stop.index as usize] = Some(label);
+                    if self.one_to_all {
+                        allocator.tau_star[stop.index as usize] = Some(label);
+                    }
                 });
-                allocator.target.stops = to_stops.into_iter().map(|stop| stop.index).collect();
+                if !self.one_to_all {
+                    allocator.target.stops = to_stops.into_iter().map(|stop| stop.index).collect();
+                }
                 allocator.target.tau_star = time::MAX;
+                allocator.target.time_limit = self
+                    .max_journey_duration
+                    .map(|duration| time.checked_add(duration).unwrap_or(time::MAX));
                 allocator.active.fill(u32::MAX);
+
+                if let Some(coordinate) = continuous_from {
+                    explore_continuous_pickups(
+                        self.repository,
+                        allocator,
+                        &coordinate,
+                        access_walk,
+                        |point| {
+                            if self.ready {
+                                time + self.repository.walk_time_between(&coordinate, point)
+                            } else {
+                                time
+                            }
+                        },
+                    );
+                    if allocator.marked_stops.not_any() && allocator.updates.is_empty() {
+                        return Err(Error::NoNearbyStops);
+                    }
+                }
             }
         }
 
@@ -195,11 +710,17 @@ impl<'a> Raptor<'a> {
 
             // Pre process
 
-            if allocator.marked_stops.not_any() {
+            // Continuous pickups are applied alongside the first round's updates.
+            if allocator.marked_stops.not_any() && allocator.updates.is_empty() {
                 break;
             }
 
             let mut marked_stops = mem::take(&mut allocator.marked_stops);
+            let marked_count = self
+                .on_round
+                .as_ref()
+                .map(|_| marked_stops.count_ones())
+                .unwrap_or_default();
             trace!(
                 "Found {} in round {}",
                 marked_stops.iter_ones().count(),
@@ -225,11 +746,10 @@ impl<'a> Raptor<'a> {
                     match self.time_constraint {
                         TimeConstraint::Departure(_) => {
                             // Forward: Default active to u32::MAX, Keep MIN
-                            let p_idx_to_beat = allocator
-                                .active_mask
-                                .get(r_idx)
-                                .map(|_| allocator.active[r_idx])
-                                .unwrap_or(u32::MAX);
+                            let p_idx_to_beat = match allocator.active_mask[r_idx] {
+                                true => allocator.active[r_idx],
+                                false => u32::MAX,
+                            };
 
                             if p_idx < p_idx_to_beat {
                                 allocator.active[r_idx] = p_idx;
@@ -238,11 +758,10 @@ impl<'a> Raptor<'a> {
                         }
                         TimeConstraint::Arrival(_) => {
                             // Reverse: Default active to 0, Keep MAX
-                            let p_idx_to_beat = allocator
-                                .active_mask
-                                .get(r_idx)
-                                .map(|_| allocator.active[r_idx])
-                                .unwrap_or(0);
+                            let p_idx_to_beat = match allocator.active_mask[r_idx] {
+                                true => allocator.active[r_idx],
+                                false => 0,
+                            };
 
                             if p_idx > p_idx_to_beat {
                                 allocator.active[r_idx] = p_idx;
@@ -258,17 +777,36 @@ impl<'a> Raptor<'a> {
 
             match self.time_constraint {
                 TimeConstraint::Arrival(_) => {
-                    explore_routes_reverse(self.repository, allocator);
+                    explore_routes_reverse(self.repository, allocator, self.boarding_time);
                     allocator.run_updates_reverse();
 
-                    explore_transfers_reverse(self.allow_walks, self.repository, allocator);
+                    explore_transfers_reverse(
+                        self.allow_walks,
+                        self.round_transfers_to,
+                        &self.transfer_time_by_mode,
+                        self.repository,
+                        allocator,
+                    );
                     allocator.run_updates_reverse();
                 }
                 TimeConstraint::Departure(_) => {
-                    explore_routes(self.repository, allocator);
+                    explore_routes(
+                        self.repository,
+                        allocator,
+                        self.initial_wait_penalty,
+                        self.preferred_agency.as_ref(),
+                        self.boarding_time,
+                        self.same_stop_transfer_time.unwrap_or(self.boarding_time),
+                    );
                     allocator.run_updates();
 
-                    explore_transfers(self.allow_walks, self.repository, allocator);
+                    explore_transfers(
+                        self.allow_walks,
+                        self.round_transfers_to,
+                        &self.transfer_time_by_mode,
+                        self.repository,
+                        allocator,
+                    );
                     allocator.run_updates();
                 }
             }
@@ -278,7 +816,19 @@ impl<'a> Raptor<'a> {
                 .stops
                 .iter()
                 .filter_map(|stop_idx| {
-                    let tau_star = allocator.tau_star[*stop_idx as usize];
+                    // Departure searches compare the targets by their penalized keys.
+                    let tau_star = match self.time_constraint {
+                        TimeConstraint::Arrival(_) => allocator.tau_star[*stop_idx as usize],
+                        TimeConstraint::Departure(_) => {
+                            allocator.tau_star[*stop_idx as usize].map(|_| {
+                                best_key(
+                                    &allocator.tau_star,
+                                    &allocator.tau_penalties,
+                                    *stop_idx as usize,
+                                )
+                            })
+                        }
+                    };
                     tau_star.map(|tau_star| (stop_idx, tau_star))
                 })
                 .for_each(|(stop_idx, tau_star)| {
@@ -292,22 +842,810 @@ impl<'a> Raptor<'a> {
                         allocator.target.best_round = Some(allocator.round);
                     }
                 });
+            if allocator.target.best_round == Some(allocator.round)
+                && let Some(best_stop) = allocator.target.best_stop
+            {
+                allocator.target.journeys.push((best_stop, allocator.round));
+            }
+
+            if let Some(on_round) = self.on_round.as_mut() {
+                on_round(RoundStats {
+                    round: allocator.round,
+                    marked_stops: marked_count,
+                    best_time: allocator
+                        .target
+                        .best_stop
+                        .and_then(|stop_idx| allocator.tau_star[stop_idx as usize]),
+                });
+            }
             allocator.next_round();
         }
+        Ok(())
+    }
+}
 
-        if let Some(target_stop) = allocator.target.best_stop
-            && let Some(target_round) = allocator.target.best_round
-        {
-            let path = backtrack(
-                self.repository,
-                allocator,
-                target_stop,
-                target_round,
-                self.time_constraint,
-            )?;
-            Ok(Itinerary::new(self.from, self.to, path, self.repository))
-        } else {
-            Err(self::Error::NoRouteFound)
+#[test]
+fn egress_walk_test() {
+    use crate::shared::Coordinate;
+
+    let repository = crate::repository::sample_repository();
+    let from = Location::Stop("S_A1".into());
+    let to = Location::Coordinate(Coordinate::new(59.331, 18.041));
+    let departure = Time::from_hms("07:00:00").unwrap();
+
+    let last_stop = |itinerary: Itinerary| match itinerary.legs.last().map(|leg| leg.to.clone()) {
+        Some(Location::Stop(id)) => id,
+        _ => panic!("Expected the itinerary to end at a stop"),
+    };
+
+    let itinerary = repository
+        .router(from.clone(), to.clone())
+        .departure_at(departure)
+        .solve()
+        .unwrap();
+    assert_eq!(last_stop(itinerary).as_ref(), "S_C1");
+
+    let itinerary = repository
+        .router(from, to)
+        .departure_at(departure)
+        .egress_walk(Distance::from_meters(100.0))
+        .solve()
+        .unwrap();
+    assert_eq!(last_stop(itinerary).as_ref(), "S_C2");
+}
+
+#[test]
+fn on_round_test() {
+    let repository = crate::repository::sample_repository();
+    let mut rounds: Vec<RoundStats> = Vec::new();
+    let itinerary = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .on_round(|stats| rounds.push(stats))
+        .solve()
+        .unwrap();
+
+    assert!(!rounds.is_empty());
+    rounds
+        .iter()
+        .enumerate()
+        .for_each(|(i, stats)| assert_eq!(stats.round, i));
+    assert_eq!(rounds[0].marked_stops, 1);
+    assert_eq!(rounds[0].best_time, None);
+    let arrival = itinerary.legs.last().unwrap().arrival_time;
+    assert_eq!(rounds.last().unwrap().best_time, Some(arrival));
+}
+
+#[test]
+fn no_nearby_stops_test() {
+    use crate::shared::Coordinate;
+
+    let repository = crate::repository::sample_repository();
+    let far_away = Location::Coordinate(Coordinate::new(60.0, 15.0));
+    let central = Location::Stop("S_C1".into());
+    let departure = Time::from_hms("07:00:00").unwrap();
+
+    let result = repository
+        .router(far_away.clone(), central.clone())
+        .departure_at(departure)
+        .solve();
+    assert!(matches!(result, Err(Error::NoNearbyStops)));
+
+    let result = repository
+        .router(central, far_away)
+        .departure_at(departure)
+        .solve();
+    assert!(matches!(result, Err(Error::NoNearbyStops)));
+}
+
+#[test]
+fn solve_pareto_test() {
+    let repository = crate::repository::sample_repository();
+    let itineraries = repository
+        .router(Location::Stop("S_B".into()), Location::Stop("S_G".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve_pareto()
+        .unwrap();
+    assert_eq!(itineraries.len(), 2);
+
+    let trips = |itinerary: &Itinerary| {
+        itinerary
+            .legs
+            .iter()
+            .filter(|leg| matches!(leg.leg_type, LegType::Transit(_)))
+            .count()
+    };
+    let arrival = |itinerary: &Itinerary| itinerary.legs.last().unwrap().arrival_time;
+
+    let fewest_transfers = &itineraries[0];
+    let fastest = &itineraries[1];
+    assert_eq!(trips(fewest_transfers), 1);
+    assert_eq!(trips(fastest), 2);
+    assert!(arrival(fastest) < arrival(fewest_transfers));
+    assert_eq!(
+        arrival(fewest_transfers),
+        Time::from_hms("07:45:00").unwrap()
+    );
+    assert_eq!(arrival(fastest), Time::from_hms("07:25:00").unwrap());
+}
+
+#[test]
+fn short_trip_slice_test() {
+    let mut repository = crate::repository::sample_repository();
+    let trip_idx = repository.trip_by_id("R1_L_0700").unwrap().index;
+    // Corrupt the trip so it has fewer stop times than its raptor route has stops.
+    Arc::make_mut(&mut repository.trip_to_stop_times_slice)[trip_idx as usize].count = 2;
+    assert!(repository.stop_time_at_idx(trip_idx, 1).is_some());
+    assert!(repository.stop_time_at_idx(trip_idx, 2).is_none());
+
+    let from = Location::Stop("S_A1".into());
+    let to = Location::Stop("S_E1".into());
+    let departure = Time::from_hms("06:55:00").unwrap();
+    let itinerary = repository
+        .router(from.clone(), to.clone())
+        .departure_at(departure)
+        .solve()
+        .unwrap();
+    // The truncated 07:00 local can no longer reach S_E1, so the 07:05 express is used.
+    assert_eq!(
+        itinerary.legs.last().unwrap().arrival_time,
+        Time::from_hms("07:13:00").unwrap()
+    );
+
+    let _ = repository
+        .router(from, to)
+        .arrival_at(Time::from_hms("09:00:00").unwrap())
+        .solve();
+
+    // Boarding past the truncated stop times must not block the later locals.
+    let itinerary = repository
+        .router(Location::Stop("S_C1".into()), Location::Stop("S_D".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    let next_local = repository.trip_by_id("R1_L_0710").unwrap().index;
+    assert_eq!(itinerary.legs.len(), 1);
+    assert!(matches!(itinerary.legs[0].leg_type, LegType::Transit(idx) if idx == next_local));
+    assert_eq!(
+        itinerary.legs[0].arrival_time,
+        Time::from_hms("07:19:00").unwrap()
+    );
+}
+
+#[test]
+fn initial_wait_penalty_test() {
+    let repository = crate::repository::sample_repository();
+    let solve = |penalty: f32| {
+        repository
+            .router(Location::Stop("S_L1".into()), Location::Stop("S_L5".into()))
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+            .initial_wait_penalty(penalty)
+            .solve()
+            .unwrap()
+    };
+
+    // Without a penalty waiting 10 minutes for the shuttle wins by a minute.
+    let itinerary = solve(0.0);
+    let leg = itinerary.legs.first().unwrap();
+    assert_eq!(leg.departue_time, Time::from_hms("07:10:00").unwrap());
+    assert_eq!(leg.arrival_time, Time::from_hms("07:14:00").unwrap());
+
+    // With a penalty the loop bus leaving right away is preferred.
+    let itinerary = solve(0.5);
+    let leg = itinerary.legs.first().unwrap();
+    assert_eq!(leg.departue_time, Time::from_hms("07:00:00").unwrap());
+    assert_eq!(
+        itinerary.legs.last().unwrap().arrival_time,
+        Time::from_hms("07:15:00").unwrap()
+    );
+}
+
+#[test]
+fn initial_wait_penalty_onward_connection_test() {
+    let repository = crate::repository::sample_repository();
+    // Waiting five minutes for the 07:00 local is penalized with 15 minutes, which must not
+    // delay the real arrival at S_C1 (07:06) and miss the 07:17 train after the transfer.
+    let itinerary = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_F".into()))
+        .departure_at(Time::from_hms("06:55:00").unwrap())
+        .initial_wait_penalty(3.0)
+        .solve()
+        .unwrap();
+    assert_eq!(
+        itinerary.legs.first().unwrap().arrival_time,
+        Time::from_hms("07:06:00").unwrap()
+    );
+    assert_eq!(
+        itinerary.arrival_time().unwrap(),
+        Time::from_hms("07:21:00").unwrap()
+    );
+}
+
+#[test]
+fn boarding_time_test() {
+    let repository = crate::repository::sample_repository();
+    let solve = |boarding_time: Duration| {
+        repository
+            .router(Location::Stop("S_A1".into()), Location::Stop("S_E1".into()))
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+            .boarding_time(boarding_time)
+            .solve()
+            .unwrap()
+    };
+
+    // Standing at the stop as the 07:00 local leaves is enough without a boarding time.
+    let itinerary = solve(Duration::from_seconds(0));
+    let leg = itinerary.legs.first().unwrap();
+    assert_eq!(leg.departue_time, Time::from_hms("07:00:00").unwrap());
+
+    // Needing two minutes to board misses it, the 07:05 express is the next one.
+    let itinerary = solve(Duration::from_minutes(2));
+    let leg = itinerary.legs.first().unwrap();
+    assert_eq!(leg.departue_time, Time::from_hms("07:05:00").unwrap());
+}
+
+#[test]
+fn same_stop_transfer_time_test() {
+    // The 07:00 local reaches S_C1 at 07:06, a coach leaves the same stop at 07:07.
+    let repository = crate::repository::sample_repository_with(|feed| {
+        let template = feed
+            .read("trips.txt")
+            .lines()
+            .find(|line| line.contains("R5_0703"))
+            .unwrap()
+            .replace("R5_0703", "R5_0707");
+        feed.append("trips.txt", &[&template]);
+        feed.append(
+            "stop_times.txt",
+            &[
+                "R5_0707,07:07:00,07:07:00,S_C1,1,,0,0,,1,,",
+                "R5_0707,07:20:00,07:20:00,S_G,2,,0,0,,1,,",
+            ],
+        );
+    });
+
+    let solve = |same_stop: Option<Duration>| {
+        let mut router = repository
+            .router(Location::Stop("S_B".into()), Location::Stop("S_G".into()))
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+            .boarding_time(Duration::from_minutes(2));
+        if let Some(duration) = same_stop {
+            router = router.same_stop_transfer_time(duration);
+        }
+        router.solve().unwrap()
+    };
+
+    // Two minutes to board misses the 07:07 coach at S_C1.
+    let itinerary = solve(None);
+    assert!(itinerary.arrival_time().unwrap() > Time::from_hms("07:20:00").unwrap());
+
+    // Staying on the platform makes the one minute change.
+    let itinerary = solve(Some(Duration::from_seconds(0)));
+    assert_eq!(
+        itinerary.arrival_time().unwrap(),
+        Time::from_hms("07:20:00").unwrap()
+    );
+    assert_eq!(itinerary.transfers(), 1);
+}
+
+#[test]
+fn prefer_agency_test() {
+    let repository = crate::repository::sample_repository();
+    // The shuttle (AG1) and the ferry (AG2) both leave S_L1 at 07:10 and arrive at 07:14.
+    let solve = |agency_id: &str| {
+        let itinerary = repository
+            .router(Location::Stop("S_L1".into()), Location::Stop("S_L5".into()))
+            .departure_at(Time::from_hms("07:05:00").unwrap())
+            .prefer_agency(agency_id.into(), Duration::from_minutes(2))
+            .solve()
+            .unwrap();
+        let leg = itinerary.legs.first().unwrap();
+        assert_eq!(leg.arrival_time, Time::from_hms("07:14:00").unwrap());
+        match leg.leg_type {
+            LegType::Transit(trip_idx) => repository.trips[trip_idx as usize].id.clone(),
+            _ => panic!("Expected a transit leg"),
         }
+    };
+    assert_eq!(solve("AG2").as_ref(), "R7_0710");
+    assert_eq!(solve("AG1").as_ref(), "R6_0710");
+}
+
+#[test]
+fn prefer_agency_onward_connection_test() {
+    let repository = crate::repository::sample_repository();
+    // Both legs are run by AG1, the bonus must neither delay the real arrival at S_C1 and miss
+    // the 07:17 train after the transfer, nor be counted once per leg.
+    let solve = |bonus: Duration| {
+        repository
+            .router(Location::Stop("S_A1".into()), Location::Stop("S_F".into()))
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+            .prefer_agency("AG2".into(), bonus)
+            .solve_raw()
+            .unwrap()
+    };
+    let result = solve(Duration::from_minutes(15));
+    let c1 = repository.stop_by_id("S_C1").unwrap().index;
+    assert_eq!(
+        result.arrival_at(c1),
+        Some(Time::from_hms("07:06:00").unwrap())
+    );
+    assert_eq!(
+        result.target_time(),
+        Some(Time::from_hms("07:21:00").unwrap())
+    );
+    assert_eq!(result.penalty_at(c1), Some(Duration::from_minutes(15)));
+}
+
+#[test]
+fn loop_route_second_visit_test() {
+    let repository = crate::repository::sample_repository();
+    // R4 visits S_L2 at 07:03 and again at 07:12 after looping through S_L3 and S_L4.
+    let itinerary = repository
+        .router(Location::Stop("S_L2".into()), Location::Stop("S_L5".into()))
+        .departure_at(Time::from_hms("07:10:00").unwrap())
+        .solve()
+        .unwrap();
+    let leg = itinerary.legs.first().unwrap();
+    assert_eq!(leg.departue_time, Time::from_hms("07:12:00").unwrap());
+    assert_eq!(leg.arrival_time, Time::from_hms("07:15:00").unwrap());
+    assert_eq!(leg.stops.len(), 2);
+    assert_eq!(
+        leg.stops.first().unwrap().departure_time,
+        Time::from_hms("07:12:00").unwrap()
+    );
+
+    // S_L4 is only served before S_L2 through the second visit.
+    let from = repository.stop_by_id("S_L4").unwrap().index;
+    let to = repository.stop_by_id("S_L2").unwrap().index;
+    assert!(!repository.raptor_routes_between(from, to).is_empty());
+}
+
+#[test]
+fn route_location_test() {
+    let repository = crate::repository::sample_repository();
+    let itinerary = repository
+        .router(Location::Route("R2".into()), Location::Stop("S_G".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    let trip_idx = itinerary
+        .legs
+        .iter()
+        .find_map(|leg| match leg.leg_type {
+            LegType::Transit(trip_idx) => Some(trip_idx),
+            _ => None,
+        })
+        .unwrap();
+    let route = &repository.routes[repository.trips[trip_idx as usize].route_idx as usize];
+    assert_eq!(&*route.id, "R2");
+
+    // The coach R5 calls at S_B where the locals to S_E1 call too, but its only trip has left
+    // by 07:50. Starting on the coach means riding it, not taking a local from its stops.
+    let solve = |from: Location| {
+        repository
+            .router(from, Location::Stop("S_E1".into()))
+            .departure_at(Time::from_hms("07:50:00").unwrap())
+            .solve()
+    };
+    assert!(solve(Location::Stop("S_B".into())).is_ok());
+    assert!(matches!(
+        solve(Location::Route("R5".into())),
+        Err(Error::NoRouteFound)
+    ));
+
+    let missing = repository
+        .router(
+            Location::Route("MISSING".into()),
+            Location::Stop("S_G".into()),
+        )
+        .solve();
+    assert!(matches!(missing, Err(Error::InvalidRouteID)));
+}
+
+#[test]
+fn route_direction_location_test() {
+    let repository = crate::repository::sample_repository();
+    // Rail runs S_C2 -> S_F -> S_G northbound (direction 0) and back southbound (direction 1).
+    let solve = |direction_id: u8| {
+        repository
+            .router(
+                Location::RouteDirection("R2".into(), direction_id),
+                Location::Stop("S_F".into()),
+            )
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+            .solve()
+            .unwrap()
+    };
+    for direction_id in [0, 1] {
+        let itinerary = solve(direction_id);
+        let trips: Vec<_> = itinerary
+            .legs
+            .iter()
+            .filter_map(|leg| match leg.leg_type {
+                LegType::Transit(trip_idx) => Some(&repository.trips[trip_idx as usize]),
+                _ => None,
+            })
+            .collect();
+        assert!(!trips.is_empty());
+        assert!(
+            trips
+                .iter()
+                .filter(|trip| &*repository.routes[trip.route_idx as usize].id == "R2")
+                .all(|trip| trip.direction_id == Some(direction_id))
+        );
+    }
+
+    // Heading back to S_C2 takes a southbound train, only boarded after riding north first.
+    let itinerary = repository
+        .router(
+            Location::RouteDirection("R2".into(), 0),
+            Location::Stop("S_C2".into()),
+        )
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    let directions: Vec<_> = itinerary
+        .legs
+        .iter()
+        .filter_map(|leg| match leg.leg_type {
+            LegType::Transit(trip_idx) => repository.trips[trip_idx as usize].direction_id,
+            _ => None,
+        })
+        .collect();
+    assert_eq!(directions, vec![0, 1]);
+
+    let missing = repository
+        .router(
+            Location::RouteDirection("R2".into(), 7),
+            Location::Stop("S_F".into()),
+        )
+        .solve();
+    assert!(matches!(missing, Err(Error::NoNearbyStops)));
+
+    // Arriving by the route, the last trip taken runs in the direction asked for.
+    for direction_id in [0, 1] {
+        let itinerary = repository
+            .router(
+                Location::Stop("S_B".into()),
+                Location::RouteDirection("R2".into(), direction_id),
+            )
+            .arrival_at(Time::from_hms("08:00:00").unwrap())
+            .solve()
+            .unwrap();
+        let trip = itinerary
+            .legs
+            .iter()
+            .rev()
+            .find_map(|leg| match leg.leg_type {
+                LegType::Transit(trip_idx) => Some(&repository.trips[trip_idx as usize]),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(&*repository.routes[trip.route_idx as usize].id, "R2");
+        assert_eq!(trip.direction_id, Some(direction_id));
     }
 }
+
+#[test]
+fn ready_at_test() {
+    use crate::shared::Coordinate;
+
+    let repository = crate::repository::sample_repository();
+    // A couple of minutes walk north of S_A1.
+    let origin = Coordinate::new(59.3315, 18.0);
+    let a1 = repository.stop_by_id("S_A1").unwrap();
+    let walk = repository.walk_time_between(&origin, &a1.coordinate);
+    let ready = Time::from_hms("07:00:00").unwrap();
+    let solve = |router: Raptor| {
+        let itinerary = router.solve().unwrap();
+        itinerary.legs.first().unwrap().departue_time
+    };
+
+    // Departing at 07:00 assumes the rider is already at the stop.
+    let router = repository
+        .router(Location::Coordinate(origin), Location::Stop("S_E1".into()))
+        .departure_at(ready);
+    assert_eq!(solve(router), ready);
+
+    // Being ready at 07:00 misses the 07:00 local and catches the 07:05 express.
+    let router = repository
+        .router(Location::Coordinate(origin), Location::Stop("S_E1".into()))
+        .ready_at(ready);
+    let departure = solve(router);
+    assert!(departure >= ready + walk);
+    assert_eq!(departure, Time::from_hms("07:05:00").unwrap());
+}
+
+#[test]
+fn round_transfers_to_test() {
+    let repository = crate::repository::sample_repository();
+    let minute = Duration::from_minutes(1);
+    let itinerary = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .round_transfers_to(minute)
+        .solve()
+        .unwrap();
+    let mut transfers = itinerary
+        .legs
+        .iter()
+        .filter(|leg| !matches!(leg.leg_type, LegType::Transit(_)))
+        .peekable();
+    assert!(transfers.peek().is_some());
+    assert!(transfers.all(|leg| (leg.arrival_time - leg.departue_time).as_seconds() % 60 == 0));
+}
+
+#[test]
+fn solve_raw_test() {
+    let repository = crate::repository::sample_repository();
+    let e1 = repository.stop_by_id("S_E1").unwrap().index;
+    let result = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_E1".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve_raw()
+        .unwrap();
+
+    // The 07:00 local reaches East Terminal at 07:12.
+    let arrival = Time::from_hms("07:12:00").unwrap();
+    assert_eq!(result.arrival_at(e1), Some(arrival));
+    assert_eq!(result.tau_star()[e1 as usize], Some(arrival));
+    assert_eq!(result.target_stop(), Some(e1));
+    assert_eq!(result.target_time(), Some(arrival));
+    let round = result.target_round().unwrap();
+    assert!(round < result.rounds());
+    let step = result.step(round, e1).unwrap();
+    assert!(matches!(step.leg_type, LegType::Transit(_)));
+    assert_eq!(step.arrival_time, arrival);
+}
+
+#[test]
+fn continuous_pickup_test() {
+    use crate::shared::Coordinate;
+
+    let repository = crate::repository::sample_repository();
+    // On the Blue Line between S_A1 and S_B, out of reach of both stops.
+    let origin = Coordinate::new(59.3302, 18.01);
+    let departure = Time::from_hms("07:04:00").unwrap();
+    let router = || {
+        repository
+            .router(Location::Coordinate(origin), Location::Stop("S_E1".into()))
+            .departure_at(departure)
+            .access_walk(Distance::from_meters(100.0))
+    };
+
+    // By default continuous pickup is ignored and only scheduled stops can be boarded.
+    assert!(matches!(router().solve(), Err(Error::NoNearbyStops)));
+
+    // The 07:05 express passes a quarter of the way to S_C1 at 07:06, before its next stop at 07:09.
+    let itinerary = router().continuous_pickup(true).solve().unwrap();
+    let leg = itinerary.legs.first().unwrap();
+    assert!(matches!(leg.leg_type, LegType::Transit(_)));
+    assert!(matches!(leg.from, Location::Coordinate(_)));
+    assert_eq!(leg.departue_time, Time::from_hms("07:06:00").unwrap());
+    assert!(matches!(
+        leg.stops.get(1).map(|stop| &stop.location),
+        Some(Location::Stop(id)) if &**id == "S_C1"
+    ));
+    assert_eq!(
+        itinerary.legs.last().unwrap().arrival_time,
+        Time::from_hms("07:13:00").unwrap()
+    );
+}
+
+#[test]
+fn stop_code_location_test() {
+    let repository = crate::repository::sample_repository();
+    let itinerary = repository
+        .router(
+            Location::StopCode("1001".into()),
+            Location::Coordinate(Coordinate::new(59.3301, 18.0801)),
+        )
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    assert!(matches!(&itinerary.legs[0].from, Location::Stop(id) if id.as_ref() == "S_A1"));
+    let east = repository.stop_by_id("S_E1").unwrap();
+    assert_eq!(itinerary.egress_stop, Some(east.index));
+
+    // "2002" is shared by Birch Street and Garden Square, the hint picks between them.
+    let last_stop = |raptor: Raptor| match raptor.solve().unwrap().legs.last().unwrap().to.clone() {
+        Location::Stop(id) => id,
+        other => panic!("Expected a stop, got {other:?}"),
+    };
+    let router = || {
+        repository
+            .router(
+                Location::Stop("S_C2".into()),
+                Location::StopCode("2002".into()),
+            )
+            .departure_at(Time::from_hms("07:10:00").unwrap())
+    };
+    let garden = repository.coordinate_by_stop_id("S_G").unwrap();
+    assert_eq!(last_stop(router().stop_code_hint(garden)).as_ref(), "S_G");
+
+    let missing = repository
+        .router(
+            Location::StopCode("9999".into()),
+            Location::Stop("S_G".into()),
+        )
+        .solve();
+    assert!(matches!(missing, Err(Error::InvalidStopCode)));
+}
+
+#[test]
+fn departure_at_datetime_test() {
+    let repository = crate::repository::sample_repository();
+    let date = |d: u32| NaiveDate::from_ymd_opt(2025, 12, d).unwrap();
+    let router = || repository.router(Location::Stop("S_A1".into()), Location::Stop("S_E1".into()));
+
+    let raptor = router().departure_at_datetime(date(22).and_hms_opt(7, 0, 0).unwrap());
+    assert_eq!(raptor.service_date, Some(date(22)));
+    assert!(matches!(
+        raptor.time_constraint,
+        TimeConstraint::Departure(time) if time == Time::from_hms("07:00:00").unwrap()
+    ));
+    let itinerary = raptor.solve().unwrap();
+    assert_eq!(
+        itinerary.legs[0].departue_time,
+        Time::from_hms("07:00:00").unwrap()
+    );
+
+    // The weekday service doesn't run on Saturdays or on the removed Christmas Day,
+    // but does on the Saturday added by calendar_dates.txt.
+    let solve = |d: u32| {
+        router()
+            .departure_at_datetime(date(d).and_hms_opt(7, 0, 0).unwrap())
+            .solve()
+    };
+    assert!(matches!(solve(20), Err(Error::NoRouteFound)));
+    assert!(matches!(solve(25), Err(Error::NoRouteFound)));
+    assert!(solve(27).is_ok());
+}
+
+#[test]
+fn max_journey_duration_test() {
+    let repository = crate::repository::sample_repository();
+    let departure = Time::from_hms("07:00:00").unwrap();
+    let router = || {
+        repository
+            .router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()))
+            .departure_at(departure)
+    };
+    let arrival = router().solve().unwrap().legs.last().unwrap().arrival_time;
+
+    // The trip takes longer than ten minutes.
+    assert!(arrival > departure + Duration::from_minutes(10));
+    let result = router()
+        .max_journey_duration(Duration::from_minutes(10))
+        .solve();
+    assert!(matches!(result, Err(Error::NoRouteWithinTimeBudget)));
+
+    let itinerary = router()
+        .max_journey_duration(Duration::from_hours(2))
+        .solve()
+        .unwrap();
+    assert_eq!(itinerary.legs.last().unwrap().arrival_time, arrival);
+
+    // Arrival searches prune on the departure instead.
+    let router = || {
+        repository
+            .router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()))
+            .arrival_at(arrival)
+    };
+    let result = router()
+        .max_journey_duration(Duration::from_minutes(10))
+        .solve();
+    assert!(matches!(result, Err(Error::NoRouteWithinTimeBudget)));
+    assert!(
+        router()
+            .max_journey_duration(Duration::from_hours(2))
+            .solve()
+            .is_ok()
+    );
+
+    // Nothing serves the depot, no budget would help.
+    let result = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_X".into()))
+        .departure_at(departure)
+        .max_journey_duration(Duration::from_hours(2))
+        .solve();
+    assert!(matches!(result, Err(Error::NoRouteFound)));
+}
+
+#[test]
+fn service_day_aware_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        feed.append(
+            "trips.txt",
+            &["R1,WEEKDAY,R1_L_2445,East Terminal,,0,SH_R1"],
+        );
+        feed.append(
+            "stop_times.txt",
+            &[
+                "R1_L_2445,24:45:00,24:45:00,S_A1,1,,0,0,0,1,,",
+                "R1_L_2445,24:48:00,24:48:00,S_B,2,,0,0,1134,1,,",
+                "R1_L_2445,24:51:00,24:51:00,S_C1,3,,0,0,2268,1,,",
+            ],
+        );
+    });
+
+    let router = || {
+        repository
+            .router(Location::Stop("S_A1".into()), Location::Stop("S_C1".into()))
+            .departure_at(Time::from_hms("00:30:00").unwrap())
+    };
+
+    // A plain search waits for the first trip of the morning.
+    let itinerary = router().solve().unwrap();
+    assert_eq!(
+        itinerary.legs.first().unwrap().departue_time,
+        Time::from_hms("07:00:00").unwrap()
+    );
+
+    let itinerary = router().service_day_aware(true).solve().unwrap();
+    let leg = itinerary.legs.first().unwrap();
+    assert_eq!(leg.departue_time, Time::from_hms("24:45:00").unwrap());
+    assert_eq!(
+        itinerary.legs.last().unwrap().arrival_time,
+        Time::from_hms("24:51:00").unwrap()
+    );
+}
+
+#[test]
+fn transfer_time_by_mode_test() {
+    let repository = crate::repository::sample_repository();
+    let router = || {
+        repository
+            .router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()))
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+    };
+    // The Blue Line bus reaches S_C1 at 07:06, the Green Line train leaves S_C2.
+    let transfer = |itinerary: &Itinerary| {
+        itinerary
+            .legs
+            .iter()
+            .find(|leg| !matches!(leg.leg_type, LegType::Transit(_)))
+            .map(|leg| leg.arrival_time - leg.departue_time)
+            .unwrap()
+    };
+    let itinerary = router().solve().unwrap();
+    assert!(transfer(&itinerary).as_seconds() < Duration::from_minutes(15).as_seconds());
+
+    let times = HashMap::from([(
+        (RouteType::Bus, RouteType::Rail),
+        Duration::from_minutes(15),
+    )]);
+    let itinerary = router().transfer_time_by_mode(times).solve().unwrap();
+    assert_eq!(
+        transfer(&itinerary).as_seconds(),
+        Duration::from_minutes(15).as_seconds()
+    );
+    // Misses the 07:17 train and catches the 07:32 instead.
+    assert_eq!(
+        itinerary.legs.last().unwrap().arrival_time,
+        Time::from_hms("07:40:00").unwrap()
+    );
+}
+
+#[test]
+fn with_options_keeps_unset_test() {
+    let repository = crate::repository::sample_repository();
+    let departure = Time::from_hms("07:00:00").unwrap();
+    let raptor = repository
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_E1".into()))
+        .departure_at(departure)
+        .allow_walks(false)
+        .walk_distance(Distance::from_meters(100.0))
+        .with_options(RouteOptions::default());
+    assert!(matches!(raptor.time_constraint, TimeConstraint::Departure(time) if time == departure));
+    assert!(!raptor.allow_walks);
+    assert_eq!(raptor.walk_distance, Distance::from_meters(100.0));
+
+    let raptor = raptor.with_options(RouteOptions {
+        allow_walks: Some(true),
+        walk_distance: Some(AVERAGE_STOP_DISTANCE),
+        ..Default::default()
+    });
+    assert!(raptor.allow_walks);
+    assert_eq!(raptor.walk_distance, AVERAGE_STOP_DISTANCE);
+}
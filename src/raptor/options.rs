@@ -0,0 +1,27 @@
+use crate::{
+    raptor::TimeConstraint,
+    shared::{geo::Distance, time::Duration},
+};
+use chrono::NaiveDate;
+
+/// The common search settings bundled together, see [`Repository::route`].
+///
+/// Settings left as `None` keep what the router already has, which for [`Repository::route`]
+/// are its defaults. For anything not covered here use the builder on [`Raptor`](super::Raptor)
+/// directly.
+///
+/// [`Repository::route`]: crate::repository::Repository::route
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RouteOptions {
+    /// When to depart or arrive by, the router departs now unless given one.
+    pub time_constraint: Option<TimeConstraint>,
+    /// Only take trips running on this date, see [`Raptor::on_date`](super::Raptor::on_date).
+    pub service_date: Option<NaiveDate>,
+    /// Whether walking between nearby stops is allowed, the router allows it unless told not to.
+    pub allow_walks: Option<bool>,
+    /// The maximum distance walked between a coordinate and the stops around it, the router
+    /// uses [`AVERAGE_STOP_DISTANCE`](crate::shared::geo::AVERAGE_STOP_DISTANCE) unless given one.
+    pub walk_distance: Option<Distance>,
+    /// Discards journeys taking longer than this.
+    pub max_journey_duration: Option<Duration>,
+}
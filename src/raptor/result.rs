@@ -0,0 +1,105 @@
+use crate::{
+    raptor::{Allocator, LegType, MAX_ROUNDS, Point},
+    shared::{Time, time::Duration},
+};
+
+/// The raw state a search left behind, for building custom objectives on top of RAPTOR.
+///
+/// Returned by [`Raptor::solve_raw`](crate::raptor::Raptor::solve_raw). The labels are the
+/// real arrival times, penalties (e.g.
+/// [`Raptor::initial_wait_penalty`](crate::raptor::Raptor::initial_wait_penalty)) only decide
+/// which label wins and are kept apart, see [`RaptorResult::penalty_at`].
+pub struct RaptorResult {
+    allocator: Allocator,
+}
+
+/// How a stop was reached in a specific round.
+#[derive(Debug, Clone, Copy)]
+pub struct RaptorStep {
+    pub from: Point,
+    pub to: Point,
+    pub leg_type: LegType,
+    pub departure_time: Time,
+    pub arrival_time: Time,
+}
+
+impl RaptorResult {
+    pub(crate) fn new(allocator: Allocator) -> Self {
+        Self { allocator }
+    }
+
+    /// The best label for every stop across all rounds, indexed by `Stop.index`.
+    pub fn tau_star(&self) -> &[Option<Time>] {
+        &self.allocator.tau_star
+    }
+
+    /// The best label for a single stop, `None` if the search never reached it.
+    pub fn arrival_at(&self, stop_idx: u32) -> Option<Time> {
+        self.allocator
+            .tau_star
+            .get(stop_idx as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// The penalty the best label for a stop was compared with, `None` if the search never
+    /// reached it.
+    pub fn penalty_at(&self, stop_idx: u32) -> Option<Duration> {
+        self.arrival_at(stop_idx)?;
+        self.allocator
+            .tau_penalties
+            .get(stop_idx as usize)
+            .map(|penalty| penalty.total())
+    }
+
+    /// Number of rounds the search ran, parents exist for rounds below this.
+    pub fn rounds(&self) -> usize {
+        self.allocator.round.min(MAX_ROUNDS)
+    }
+
+    /// How the stop was reached in `round`, `None` if it did not improve in that round.
+    pub fn step(&self, round: usize, stop_idx: u32) -> Option<RaptorStep> {
+        if round >= MAX_ROUNDS {
+            return None;
+        }
+        let parent = self
+            .allocator
+            .get_parents(round)
+            .get(stop_idx as usize)?
+            .as_ref()?;
+        Some(RaptorStep {
+            from: parent.from,
+            to: parent.to,
+            leg_type: parent.parent_type.into(),
+            departure_time: parent.departure_time,
+            arrival_time: parent.arrival_time,
+        })
+    }
+
+    /// The target stop with the best label, `None` if the target was never reached.
+    pub fn target_stop(&self) -> Option<u32> {
+        self.allocator.target.best_stop
+    }
+
+    /// The round the best label at the target was found in.
+    pub fn target_round(&self) -> Option<usize> {
+        self.allocator.target.best_round
+    }
+
+    /// The best label at the target.
+    pub fn target_time(&self) -> Option<Time> {
+        self.target_stop()
+            .and_then(|stop_idx| self.arrival_at(stop_idx))
+    }
+
+    /// Every `(stop, round)` where the target improved, one entry per improving round.
+    pub fn journeys(&self) -> &[(u32, usize)] {
+        &self.allocator.target.journeys
+    }
+
+    /// Hands back the allocator so it can be reused for another search.
+    pub fn into_allocator(mut self) -> Allocator {
+        self.allocator.reset();
+        self.allocator
+    }
+}
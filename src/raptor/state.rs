@@ -2,12 +2,14 @@ use crate::{
     raptor::Point,
     shared::{Time, time},
 };
+use bitvec::prelude::*;
 
 #[derive(Debug, Clone)]
 pub(crate) struct Update {
     pub stop_idx: u32,
     pub arrival_time: Time,
     pub parent: Parent,
+    pub penalty: Penalty,
 }
 
 impl Update {
@@ -16,8 +18,39 @@ impl Update {
             stop_idx,
             arrival_time,
             parent,
+            penalty: Penalty::default(),
         }
     }
+
+    pub fn with_penalty(mut self, penalty: Penalty) -> Self {
+        self.penalty = penalty;
+        self
+    }
+}
+
+/// Makes a label compare as if it arrived later than it does, see
+/// [`crate::raptor::Raptor::initial_wait_penalty`] and [`crate::raptor::Raptor::prefer_agency`].
+///
+/// Penalties are only used to compare labels, the labels themselves always hold the real
+/// arrival so later rounds board and transfer from the time the rider is actually there.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Penalty {
+    /// From the time waited before the first boarding.
+    pub wait: time::Duration,
+    /// From riding another agency than the preferred one, counted once however many
+    /// such trips the journey takes.
+    pub agency: time::Duration,
+}
+
+impl Penalty {
+    pub fn total(&self) -> time::Duration {
+        self.wait + self.agency
+    }
+
+    /// The time `arrival_time` is compared by.
+    pub fn key(&self, arrival_time: Time) -> Time {
+        arrival_time.checked_add(self.total()).unwrap_or(time::MAX)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -81,9 +114,24 @@ impl ParentType {
 #[derive(Debug, Clone)]
 pub(crate) struct Target {
     pub stops: Vec<u32>,
+    /// The best comparison key at the target, the arrival time plus its [`Penalty`].
     pub tau_star: Time,
     pub best_stop: Option<u32>,
     pub best_round: Option<usize>,
+    /// Every `(stop, round)` where the target improved, one entry per improving round.
+    pub journeys: Vec<(u32, usize)>,
+    /// Latest arrival, or earliest departure for arrival searches, an update may have.
+    pub time_limit: Option<Time>,
+    /// Whether `time_limit` discarded an update that would have improved a label.
+    pub pruned: bool,
+    /// Whether either end was an area without service resolved to the stops around it.
+    pub area_fallback: bool,
+    /// The trips running on the service date of the search, `None` if all trips can be taken.
+    pub running: Option<BitVec<usize, Lsb0>>,
+    /// The trips that can be boarded in the first round, overriding `running` there. Set for a
+    /// route origin of a departure search or a route destination of an arrival search, where
+    /// the first round explores the last trip taken.
+    pub first_boarding: Option<BitVec<usize, Lsb0>>,
 }
 
 impl Target {
@@ -93,6 +141,12 @@ impl Target {
             tau_star: time::MAX,
             best_stop: None,
             best_round: None,
+            journeys: vec![],
+            time_limit: None,
+            pruned: false,
+            area_fallback: false,
+            running: None,
+            first_boarding: None,
         }
     }
 
@@ -101,5 +155,11 @@ impl Target {
         self.tau_star = time::MAX;
         self.best_stop = None;
         self.best_round = None;
+        self.journeys.clear();
+        self.time_limit = None;
+        self.pruned = false;
+        self.area_fallback = false;
+        self.running = None;
+        self.first_boarding = None;
     }
 }
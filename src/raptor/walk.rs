@@ -0,0 +1,98 @@
+use crate::{raptor::Parent, repository::Repository, shared::time::Time};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    estimate: f32,
+    stop_idx: u32,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.estimate
+            .total_cmp(&other.estimate)
+            .then(self.stop_idx.cmp(&other.stop_idx))
+    }
+}
+
+/// Finds the shortest walking path between two stops using A* over the walkable stop graph.
+///
+/// The cost of each edge is the network distance between the two stops and the heuristic
+/// is the network distance to the target, which never overestimates the remaining cost.
+/// Times on the returned path start at [`Time::default`] and grow with the walking duration.
+pub(crate) fn shortest_walk(
+    repository: &Repository,
+    from_stop_idx: u32,
+    to_stop_idx: u32,
+) -> Option<Vec<Parent>> {
+    let target = repository.stops.get(to_stop_idx as usize)?.coordinate;
+    repository.stops.get(from_stop_idx as usize)?;
+
+    let mut distances: Vec<f32> = vec![f32::INFINITY; repository.stops.len()];
+    let mut previous: Vec<Option<u32>> = vec![None; repository.stops.len()];
+    let mut queue = BinaryHeap::new();
+
+    distances[from_stop_idx as usize] = 0.0;
+    queue.push(Reverse(Candidate {
+        estimate: 0.0,
+        stop_idx: from_stop_idx,
+    }));
+
+    while let Some(Reverse(Candidate { stop_idx, .. })) = queue.pop() {
+        if stop_idx == to_stop_idx {
+            break;
+        }
+        let stop = &repository.stops[stop_idx as usize];
+        for next_stop_idx in repository.stop_to_walk_stop[stop_idx as usize].iter() {
+            let next_stop = &repository.stops[*next_stop_idx as usize];
+            let distance = distances[stop_idx as usize]
+                + stop
+                    .coordinate
+                    .network_distance(&next_stop.coordinate)
+                    .as_meters();
+            if distance < distances[*next_stop_idx as usize] {
+                distances[*next_stop_idx as usize] = distance;
+                previous[*next_stop_idx as usize] = Some(stop_idx);
+                let remaining = next_stop.coordinate.network_distance(&target).as_meters();
+                queue.push(Reverse(Candidate {
+                    estimate: distance + remaining,
+                    stop_idx: *next_stop_idx,
+                }));
+            }
+        }
+    }
+
+    if from_stop_idx != to_stop_idx && previous[to_stop_idx as usize].is_none() {
+        return None;
+    }
+
+    let mut stops = vec![to_stop_idx];
+    let mut current = to_stop_idx;
+    while let Some(stop_idx) = previous[current as usize] {
+        stops.push(stop_idx);
+        current = stop_idx;
+    }
+    stops.reverse();
+
+    let mut time = Time::default();
+    let path = stops
+        .windows(2)
+        .map(|pair| {
+            let departure_time = time;
+            time += repository.walk_time_between_stops(pair[0], pair[1]);
+            Parent::new_walk(pair[0].into(), pair[1].into(), departure_time, time)
+        })
+        .collect();
+    Some(path)
+}
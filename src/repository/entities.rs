@@ -1,8 +1,9 @@
+use chrono::{Datelike, NaiveDate};
 use std::sync::Arc;
 
 use crate::shared::{
     Identifiable,
-    geo::{Coordinate, Distance},
+    geo::{BoundingBox, Coordinate, Distance},
     time::{Duration, Time},
 };
 
@@ -19,6 +20,11 @@ pub struct Area {
 
     /// A search-optimized version of the name (e.g., lowercase, stripped of accents).
     pub normalized_name: Arc<str>,
+
+    /// The average coordinate of the stops in the area, computed at load.
+    pub centroid: Coordinate,
+    /// The box containing all stops in the area, `None` if it has no stops.
+    pub bbox: Option<BoundingBox>,
 }
 
 impl Identifiable for Area {
@@ -72,8 +78,16 @@ pub struct Stop {
     pub coordinate: Coordinate,
     /// The index of the parent station/platform
     pub parent_index: Option<u32>,
-    // The specific GTFS location classification.
-    // pub location_type: LocationType,
+    /// The fare zone the stop belongs to.
+    pub zone_id: Option<Arc<str>>,
+    /// The short code riders see at the stop (`stop_code`), not necessarily unique.
+    pub code: Option<Arc<str>>,
+    /// A description of the stop and its surroundings (`stop_desc`).
+    pub description: Option<Arc<str>>,
+    /// A web page about the stop (`stop_url`).
+    pub url: Option<Arc<str>>,
+    /// The specific GTFS location classification.
+    pub location_type: LocationType,
 }
 
 impl Identifiable for Stop {
@@ -135,6 +149,10 @@ pub struct StopTime {
     pub drop_off_type: StopAccessType,
     /// Indicates if times are exact or estimates.
     pub timepoint: Timepoint,
+    /// Policy for boarding anywhere between this stop and the next, overrides the route's when set.
+    pub continuous_pickup: Option<StopAccessType>,
+    /// Policy for alighting anywhere between this stop and the next, overrides the route's when set.
+    pub continuous_drop_off: Option<StopAccessType>,
 }
 
 /// Metadata describing a contiguous range within a global array.
@@ -167,10 +185,88 @@ pub struct Trip {
     pub id: Arc<str>,
     /// Pointer to the parent [`Route`].
     pub route_idx: u32,
-    /// Pointer to the optimized [`RaptorRoute`] used by the routing engine.
+    /// Pointer to the optimized [`RaptorRoute`] used by the routing engine,
+    /// `u32::MAX` for trips calling at fewer than two stops.
     pub raptor_route_idx: u32,
     pub head_sign: Option<Arc<str>>,
     pub short_name: Option<Arc<str>>,
+    /// The direction of travel (`direction_id`), `0` and `1` tell the two directions
+    /// of a route apart, e.g. outbound and inbound.
+    pub direction_id: Option<u8>,
+    /// Pointer to the [`Service`] the trip runs on, if the feed defines it.
+    pub service_idx: Option<u32>,
+}
+
+/// A set of days on which trips operate, built from `calendar.txt` and `calendar_dates.txt`.
+#[derive(Debug, Default, Clone)]
+pub struct Service {
+    pub index: u32,
+    pub id: Arc<str>,
+    pub days: ServiceDays,
+    /// First date (`YYYYMMDD`) of the regular schedule, if the service has one.
+    pub start_date: Option<u32>,
+    /// Last date (`YYYYMMDD`) of the regular schedule, if the service has one.
+    pub end_date: Option<u32>,
+}
+
+/// Which days a service operates.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ServiceDays {
+    /// Bitmask of the weekdays in the regular schedule, see [`ServiceDays::MONDAY`] and friends.
+    pub weekdays: u8,
+    /// Dates (`YYYYMMDD`) with service in addition to the regular schedule, sorted.
+    pub added_dates: Vec<u32>,
+    /// Dates (`YYYYMMDD`) without service despite the regular schedule, sorted.
+    pub removed_dates: Vec<u32>,
+}
+
+impl ServiceDays {
+    pub const MONDAY: u8 = 1 << 0;
+    pub const TUESDAY: u8 = 1 << 1;
+    pub const WEDNESDAY: u8 = 1 << 2;
+    pub const THURSDAY: u8 = 1 << 3;
+    pub const FRIDAY: u8 = 1 << 4;
+    pub const SATURDAY: u8 = 1 << 5;
+    pub const SUNDAY: u8 = 1 << 6;
+    pub const WEEKDAYS: u8 =
+        Self::MONDAY | Self::TUESDAY | Self::WEDNESDAY | Self::THURSDAY | Self::FRIDAY;
+    pub const WEEKEND: u8 = Self::SATURDAY | Self::SUNDAY;
+
+    /// Returns `true` if the regular schedule covers any of the given weekdays.
+    pub fn runs_on(&self, weekdays: u8) -> bool {
+        self.weekdays & weekdays != 0
+    }
+
+    /// Returns `true` if there is service on `date`, taking added and removed dates
+    /// into account. The range of the regular schedule is checked by [`Service::runs_on_date`].
+    pub fn runs_on_date(&self, date: NaiveDate) -> bool {
+        let key = date_key(date);
+        if self.added_dates.binary_search(&key).is_ok() {
+            return true;
+        }
+        if self.removed_dates.binary_search(&key).is_ok() {
+            return false;
+        }
+        self.runs_on(1 << date.weekday().num_days_from_monday())
+    }
+}
+
+impl Service {
+    /// Returns `true` if the service operates on `date`.
+    pub fn runs_on_date(&self, date: NaiveDate) -> bool {
+        let key = date_key(date);
+        if self.days.added_dates.binary_search(&key).is_ok() {
+            return true;
+        }
+        let in_range = self.start_date.is_none_or(|start| start <= key)
+            && self.end_date.is_none_or(|end| key <= end);
+        in_range && self.days.runs_on_date(date)
+    }
+}
+
+/// The `YYYYMMDD` form dates are stored in.
+fn date_key(date: NaiveDate) -> u32 {
+    date.year() as u32 * 10_000 + date.month() * 100 + date.day()
 }
 
 /// A grouping of trips that are displayed to riders under a single name (e.g., "Blue Line").
@@ -184,6 +280,100 @@ pub struct Route {
     /// Classification of the vehicle (0: Tram, 1: Subway, 3: Bus, etc.).
     pub route_type: i32,
     pub route_desc: Option<Arc<str>>,
+    /// Route color as a six-digit hex value (e.g. "0057B8"), if given by the feed.
+    pub color: Option<Arc<str>>,
+    /// Color of text drawn on top of `color`, if given by the feed.
+    pub text_color: Option<Arc<str>>,
+    /// Policy for boarding anywhere along the route, `None` if the feed leaves it empty.
+    pub continuous_pickup: Option<StopAccessType>,
+    /// Policy for alighting anywhere along the route, `None` if the feed leaves it empty.
+    pub continuous_drop_off: Option<StopAccessType>,
+}
+
+impl Route {
+    /// The typed [`RouteType`] of `route_type`.
+    pub fn mode(&self) -> RouteType {
+        self.route_type.into()
+    }
+}
+
+/// The kind of vehicle a route runs, from the GTFS `route_type`.
+///
+/// Extended route types (e.g. `700` for bus services) are mapped to the closest basic type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteType {
+    Tram,
+    Subway,
+    Rail,
+    Bus,
+    Ferry,
+    CableTram,
+    AerialLift,
+    Funicular,
+    Trolleybus,
+    Monorail,
+    Other(i32),
+}
+
+impl From<i32> for RouteType {
+    fn from(value: i32) -> Self {
+        match value {
+            12 | 405 => RouteType::Monorail,
+            0 | 900..=999 => RouteType::Tram,
+            1 | 400..=499 => RouteType::Subway,
+            2 | 100..=199 => RouteType::Rail,
+            3 | 200..=299 | 700..=799 => RouteType::Bus,
+            4 | 1000..=1099 | 1200..=1299 => RouteType::Ferry,
+            5 => RouteType::CableTram,
+            6 | 1300..=1399 => RouteType::AerialLift,
+            7 | 1400..=1499 => RouteType::Funicular,
+            11 | 800..=899 => RouteType::Trolleybus,
+            value => RouteType::Other(value),
+        }
+    }
+}
+
+/// The default route color defined by GTFS when a feed omits `route_color`.
+pub const DEFAULT_ROUTE_COLOR: &str = "FFFFFF";
+/// The default text color defined by GTFS when a feed omits `route_text_color`.
+pub const DEFAULT_ROUTE_TEXT_COLOR: &str = "000000";
+
+/// Everything needed to render a route with the agency's branding.
+#[derive(Debug, Clone)]
+pub struct RouteStyle {
+    /// Route color as a six-digit hex value, falling back to [`DEFAULT_ROUTE_COLOR`].
+    pub color: Arc<str>,
+    /// Text color as a six-digit hex value, falling back to [`DEFAULT_ROUTE_TEXT_COLOR`].
+    pub text_color: Arc<str>,
+    pub short_name: Option<Arc<str>>,
+    /// Classification of the vehicle (0: Tram, 1: Subway, 3: Bus, etc.).
+    pub route_type: i32,
+}
+
+/// Whether a [`RaptorRoute`] stops everywhere along its route or skips stops,
+/// see [`Repository::pattern_kind`](crate::repository::Repository::pattern_kind).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    /// Serves every stop of the route's most common pattern, or is that pattern.
+    Local,
+    /// Serves only some of the stops of the route's most common pattern, e.g. an express.
+    Limited,
+}
+
+/// A single result of [`Repository::search`](crate::repository::Repository::search).
+#[derive(Debug, Clone)]
+pub struct SearchHit<'a> {
+    pub kind: SearchHitKind<'a>,
+    /// How well the entity matched, higher is better.
+    pub score: f64,
+}
+
+/// The entity a [`SearchHit`] refers to.
+#[derive(Debug, Clone)]
+pub enum SearchHitKind<'a> {
+    Area(&'a Area),
+    Stop(&'a Stop),
+    Route(&'a Route),
 }
 
 #[derive(Debug, Default, Clone)]
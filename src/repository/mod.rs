@@ -1,84 +1,140 @@
 mod entities;
+// Nothing writes snapshots yet, the framing lands ahead of `Repository::save`/`load`.
+#[allow(dead_code)]
+pub(crate) mod snapshot;
 pub mod source;
+mod subgraph;
 
 use crate::{
-    raptor::{Location, Raptor},
+    raptor::{
+        self, Itinerary, Location, Raptor, RouteOptions, index_in_route, indices_in_route,
+        shortest_walk,
+    },
     shared::{
         self,
-        geo::{AVERAGE_STOP_DISTANCE, Coordinate, Distance},
+        geo::{AVERAGE_STOP_DISTANCE, Coordinate, DISTANCE_EPSILON, Distance},
+        time::{Duration, Time},
     },
 };
+use chrono::NaiveDate;
 pub use entities::*;
-use std::{collections::HashMap, sync::Arc};
+use rayon::prelude::*;
+pub use source::{LoadCounts, LoadReport, LoadStage};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use thiserror::Error;
 
 pub type Cell = (i32, i32);
 
+/// The most hits [`Repository::search`] returns.
+pub const MAX_SEARCH_HITS: usize = 20;
+
+/// An invariant broken by a [`RaptorRoute`], see [`Repository::validate_raptor_routes`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RaptorRouteError {
+    #[error("Trip {trip_id} calls at {found} stops but its raptor route {route_id} has {expected}")]
+    LengthMismatch {
+        route_id: Arc<str>,
+        trip_id: Arc<str>,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Trip {trip_id} does not follow the stop sequence of its raptor route {route_id}")]
+    StopMismatch {
+        route_id: Arc<str>,
+        trip_id: Arc<str>,
+    },
+}
+
 /// A read-only, memory-efficient data store containing all transit network information.
 ///
 /// The `Repository` acts as a flattened relational database, optimized for high-performance
-/// pathfinding algorithms like RAPTOR. It uses `Box<[T]>` instead of `Vec<T>` to minimize
-/// memory overhead and signal immutability after construction.
+/// pathfinding algorithms like RAPTOR. It uses `Arc<[T]>` instead of `Vec<T>` to minimize
+/// memory overhead and signal immutability after construction, and so that clones share
+/// the data instead of copying it.
 #[derive(Debug, Clone, Default)]
 pub struct Repository {
     // --- Core Entities ---
     /// Global list of all physical transit stops or stations.
-    pub stops: Box<[Stop]>,
+    pub stops: Arc<[Stop]>,
     /// Geographical or logical groupings of stops.
-    pub areas: Box<[Area]>,
+    pub areas: Arc<[Area]>,
     /// High-level transit routes (e.g., "Bus 42").
-    pub routes: Box<[Route]>,
+    pub routes: Arc<[Route]>,
     /// Specialized route structures where every trip follows an identical stop sequence.
     /// Required for the RAPTOR algorithm's optimization passes.
-    pub raptor_routes: Box<[RaptorRoute]>,
+    pub raptor_routes: Arc<[RaptorRoute]>,
     /// Individual vehicle journeys occurring at specific times.
-    pub trips: Box<[Trip]>,
+    pub trips: Arc<[Trip]>,
     /// The specific arrival/departure events linking trips to stops.
-    pub stop_times: Box<[StopTime]>,
+    pub stop_times: Arc<[StopTime]>,
     /// All known transfers.
-    pub transfers: Box<[Transfer]>,
+    pub transfers: Arc<[Transfer]>,
     /// All the shapes.
-    pub shapes: Box<[Shape]>,
+    pub shapes: Arc<[Shape]>,
+    /// Operating days referenced by trips.
+    pub services: Arc<[Service]>,
 
     // --- Primary Key Lookups ---
     /// Maps a unique `Stop.id` string to its index within the `stops` slice.
-    stop_lookup: HashMap<Arc<str>, u32>,
+    stop_lookup: Arc<HashMap<Arc<str>, u32>>,
+    /// Maps a rider-facing `Stop.code` to the indices of every stop sharing it, codes are not unique.
+    stop_code_lookup: Arc<HashMap<Arc<str>, Box<[u32]>>>,
     /// Maps a unique `Trip.id` string to its index within the `trips` slice.
-    trip_lookup: HashMap<Arc<str>, u32>,
+    trip_lookup: Arc<HashMap<Arc<str>, u32>>,
     /// Maps a unique `Area.id` string to its index within the `areas` slice.
-    area_lookup: HashMap<Arc<str>, u32>,
+    area_lookup: Arc<HashMap<Arc<str>, u32>>,
+    /// Maps an alias `Area.id`, e.g. the id of the same area in another feed, to its canonical id.
+    area_aliases: Arc<HashMap<Arc<str>, Arc<str>>>,
     /// Maps a unique `Route.id` string to its index within the `routes` slice.
-    route_lookup: HashMap<Arc<str>, u32>,
+    route_lookup: Arc<HashMap<Arc<str>, u32>>,
+    /// Maps a unique `Service.id` string to its index within the `services` slice.
+    service_lookup: Arc<HashMap<Arc<str>, u32>>,
     /// Spatial index used to find stops within specific grid cells.
-    stop_distance_lookup: HashMap<Cell, Box<[u32]>>,
+    stop_distance_lookup: Arc<HashMap<Cell, Box<[u32]>>>,
 
     // --- Relationship Indicies (Adjacency Lists) ---
     /// Index mapping: `route_index -> [trip_index, ...]`.
-    pub(crate) route_to_trips: Box<[Box<[u32]>]>,
+    pub(crate) route_to_trips: Arc<[Box<[u32]>]>,
     /// Index mapping: `trip_index -> route_index`.
-    pub(crate) trip_to_route: Box<[u32]>,
+    pub(crate) trip_to_route: Arc<[u32]>,
     /// Index mapping: `area_index -> [stop_index, ...]`.
-    pub(crate) area_to_stops: Box<[Box<[u32]>]>,
+    pub(crate) area_to_stops: Arc<[Box<[u32]>]>,
     /// Index mapping: `stop_index -> area_index`.
-    pub(crate) stop_to_area: Box<[Option<u32>]>,
+    pub(crate) stop_to_area: Arc<[Option<u32>]>,
     /// Index mapping: `stop_index -> [stop_index, ...]`.
-    pub(crate) station_to_stops: Box<[Box<[u32]>]>,
+    pub(crate) station_to_stops: Arc<[Box<[u32]>]>,
     /// Index mapping: `from.stop_index -> [transfer_index, ...]`.
-    pub(crate) stop_to_transfers: Box<[Box<[u32]>]>,
+    pub(crate) stop_to_transfers: Arc<[Box<[u32]>]>,
     /// Index mapping: `stop_index -> [trip_index, ...]`.
-    pub(crate) stop_to_trips: Box<[Box<[u32]>]>,
+    pub(crate) stop_to_trips: Arc<[Box<[u32]>]>,
     /// Defines the range within the `stop_times` slice that belongs to a specific trip.
-    pub(crate) trip_to_stop_times_slice: Box<[Slice]>,
+    pub(crate) trip_to_stop_times_slice: Arc<[Slice]>,
     /// Defines the range within the `shapes` slice that belongs to a specific raptor route.
 
     // --- RAPTOR Specialized Lookups ---
     /// Maps a standard route index to its corresponding `RaptorRoute` versions.
-    pub(crate) route_to_raptors: Box<[Box<[u32]>]>,
+    pub(crate) route_to_raptors: Arc<[Box<[u32]>]>,
     /// Maps a stop index to all `RaptorRoute` indices that serve it.
-    pub(crate) stop_to_raptors: Box<[Box<[u32]>]>,
+    pub(crate) stop_to_raptors: Arc<[Box<[u32]>]>,
     /// Maps a stop index to all walkable stops near it.
-    pub(crate) stop_to_walk_stop: Box<[Box<[u32]>]>,
+    pub(crate) stop_to_walk_stop: Arc<[Box<[u32]>]>,
+    /// Walking duration for every entry in `stop_to_walk_stop`, in the same order.
+    pub(crate) stop_to_walk_durations: Arc<[Box<[Duration]>]>,
+    /// Duration of every transfer, parallel to `transfers`.
+    pub(crate) transfer_durations: Arc<[Duration]>,
     /// Maps a stop index to all walkable stops near it.
-    pub(crate) raptor_to_shapes_slice: Box<[Option<Slice>]>,
+    pub(crate) raptor_to_shapes_slice: Arc<[Option<Slice>]>,
+
+    /// Index mapping: `row in stops.txt -> stop_index`, only set when colocated stops were merged
+    /// or on a subgraph, where rows of dropped stops map to `u32::MAX`.
+    pub(crate) source_to_stop: Arc<[u32]>,
+
+    /// What was skipped while loading the data.
+    pub(crate) load_report: LoadReport,
 }
 
 impl Repository {
@@ -87,6 +143,27 @@ impl Repository {
         Default::default()
     }
 
+    /// Returns a summary of the data that was skipped while loading the repository.
+    pub fn load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// Maps the position of a stop in the source feed to its index in `stops`.
+    ///
+    /// The two only differ when colocated stops were merged while loading, see
+    /// [`Config::dedupe_colocated_stops`](crate::gtfs::Config::dedupe_colocated_stops), or on
+    /// a [`Repository::subgraph_for_bbox`], which returns `None` for the stops it dropped.
+    pub fn stop_idx_by_source_idx(&self, source_idx: u32) -> Option<u32> {
+        if self.source_to_stop.is_empty() {
+            (source_idx < self.stops.len() as u32).then_some(source_idx)
+        } else {
+            self.source_to_stop
+                .get(source_idx as usize)
+                .copied()
+                .filter(|stop_idx| *stop_idx != u32::MAX)
+        }
+    }
+
     /// Initializes a new RAPTOR router instance tied to the lifetime of this repository.
     ///
     /// This is the entry point for performing pathfinding between two locations.
@@ -94,6 +171,91 @@ impl Repository {
         Raptor::new(self, from, to)
     }
 
+    /// Finds the best itinerary between two locations with the settings in `options`,
+    /// a shorthand for [`Repository::router`] without chaining builder methods.
+    pub fn route(
+        &self,
+        from: Location,
+        to: Location,
+        options: RouteOptions,
+    ) -> Result<Itinerary, raptor::Error> {
+        self.router(from, to).with_options(options).solve()
+    }
+
+    /// Estimates how long it takes to walk between two coordinates, using the
+    /// network distance and the average walking speed used by the router.
+    pub fn walk_time_between(&self, a: &Coordinate, b: &Coordinate) -> Duration {
+        raptor::time_to_walk(a.network_distance(b))
+    }
+
+    /// Same as [`Repository::walk_time_between`] but between two stops using their indices.
+    pub fn walk_time_between_stops(&self, a_stop_idx: u32, b_stop_idx: u32) -> Duration {
+        self.walk_time_between(
+            &self.stops[a_stop_idx as usize].coordinate,
+            &self.stops[b_stop_idx as usize].coordinate,
+        )
+    }
+
+    /// Estimates the walking distance between `coordinate` and a stop by following the shapes
+    /// of the routes serving the stop, a rough stand-in for the street the stop lies on.
+    ///
+    /// Both ends are snapped to the closest shape point and the distance along the shape
+    /// between them, point to point as the shape already follows the street, is added to the
+    /// two snapping distances. Shapes further from `coordinate` than the stop itself are
+    /// ignored, and without any the network distance is used.
+    pub fn shape_walk_distance(&self, coordinate: &Coordinate, stop_idx: u32) -> Distance {
+        let stop = &self.stops[stop_idx as usize].coordinate;
+        let straight = coordinate.network_distance(stop);
+        let closest = |points: &[Shape], to: &Coordinate| {
+            points
+                .iter()
+                .enumerate()
+                .map(|(idx, shape)| (idx, shape.coordinate.network_distance(to)))
+                .min_by(|a, b| a.1.as_meters().total_cmp(&b.1.as_meters()))
+        };
+        self.raptors_by_stop_idx(stop_idx)
+            .into_iter()
+            .filter_map(|raptor| self.shapes_by_trip_idx(*raptor.trips.first()?))
+            .filter_map(|points| {
+                let (from_idx, snap) = closest(points, coordinate)?;
+                let (to_idx, stop_snap) = closest(points, stop)?;
+                if snap > straight {
+                    return None;
+                }
+                let (start, end) = (from_idx.min(to_idx), from_idx.max(to_idx));
+                let along: f32 = points[start..=end]
+                    .windows(2)
+                    .map(|pair| {
+                        pair[0]
+                            .coordinate
+                            .euclidean_distance(&pair[1].coordinate)
+                            .as_meters()
+                    })
+                    .sum();
+                Some(snap.as_meters() + along + stop_snap.as_meters())
+            })
+            .min_by(f32::total_cmp)
+            .map(|meters| Distance::from_meters(meters.max(straight.as_meters())))
+            .unwrap_or(straight)
+    }
+
+    /// Finds the shortest walking path between two stops, ignoring all schedules.
+    ///
+    /// The path only uses the walkable connections between nearby stops, so the returned
+    /// [`Itinerary`] consists solely of walk legs. Leg times are relative to the start of
+    /// the walk, i.e. the first leg departs at `00:00:00`.
+    pub fn shortest_path_distance(
+        &self,
+        from_stop_idx: u32,
+        to_stop_idx: u32,
+    ) -> Result<Itinerary, raptor::Error> {
+        let path =
+            shortest_walk(self, from_stop_idx, to_stop_idx).ok_or(raptor::Error::NoRouteFound)?;
+        let from = &self.stops[from_stop_idx as usize];
+        let to = &self.stops[to_stop_idx as usize];
+        Ok(Itinerary::new(from.into(), to.into(), path, self))
+    }
+
     // --- Primary Key Lookups Functions ---
 
     /// Retrieves a [`Stop`] by its string identifier `Stop.id`.
@@ -103,11 +265,76 @@ impl Repository {
         Some(&self.stops[*stop_index as usize])
     }
 
-    /// Retrieves a [`Area`] by its string identifier `Area.id`.
+    /// Retrieves the coordinate of a [`Stop`] by its string identifier `Stop.id`.
+    /// Returns `None` if the ID does not exist.
+    pub fn coordinate_by_stop_id(&self, id: &str) -> Option<Coordinate> {
+        self.stop_by_id(id).map(|stop| stop.coordinate)
+    }
+
+    /// Returns every stop with the rider-facing code `code` (`Stop.code`).
+    pub fn stops_by_code(&self, code: &str) -> Vec<&Stop> {
+        self.stop_code_lookup
+            .get(code)
+            .map(|stop_indices| {
+                stop_indices
+                    .iter()
+                    .map(|stop_idx| &self.stops[*stop_idx as usize])
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Retrieves a [`Stop`] by its rider-facing code `Stop.code`. Codes are not guaranteed
+    /// to be unique, an ambiguous code resolves to the stop closest to `hint` if given,
+    /// otherwise to the first match. Returns `None` if no stop has the code.
+    pub fn stop_by_code(&self, code: &str, hint: Option<&Coordinate>) -> Option<&Stop> {
+        let mut stops = self.stop_code_lookup.get(code)?.iter();
+        let stop_idx = match hint {
+            Some(hint) => stops.min_by(|a, b| {
+                let distance = |stop_idx: u32| {
+                    self.stops[stop_idx as usize]
+                        .coordinate
+                        .network_distance(hint)
+                        .as_meters()
+                };
+                distance(**a).total_cmp(&distance(**b))
+            }),
+            None => stops.next(),
+        }?;
+        Some(&self.stops[*stop_idx as usize])
+    }
+
+    /// Retrieves a [`Area`] by its string identifier `Area.id`, resolving aliases.
     /// Returns `None` if the ID does not exist.
     pub fn area_by_id(&self, id: &str) -> Option<&Area> {
-        let area_index = self.area_lookup.get(id)?;
-        Some(&self.areas[*area_index as usize])
+        self.resolve_area(id)
+    }
+
+    /// Registers `alias` as another id for the area `canonical`, e.g. when two feeds describe
+    /// the same physical area under different ids. Lookups, searches and routing by the alias
+    /// then use the canonical area. Aliases may point to other aliases.
+    pub fn with_area_alias(
+        mut self,
+        alias: impl Into<Arc<str>>,
+        canonical: impl Into<Arc<str>>,
+    ) -> Self {
+        Arc::make_mut(&mut self.area_aliases).insert(alias.into(), canonical.into());
+        self
+    }
+
+    /// Resolves `id` to its canonical [`Area`] by following the registered aliases,
+    /// see [`Repository::with_area_alias`].
+    /// Returns `None` if neither the ID nor what it aliases exists.
+    pub fn resolve_area(&self, id: &str) -> Option<&Area> {
+        let mut id = id;
+        // Bounded by the number of aliases so cyclic aliases can't loop forever.
+        for _ in 0..=self.area_aliases.len() {
+            if let Some(area_idx) = self.area_lookup.get(id) {
+                return Some(&self.areas[*area_idx as usize]);
+            }
+            id = self.area_aliases.get(id)?;
+        }
+        None
     }
 
     /// Retrieves a [`Trip`] by its string identifier `Trip.id`.
@@ -124,6 +351,52 @@ impl Repository {
         Some(&self.routes[*index as usize])
     }
 
+    /// Retrieves a [`Stop`] by its index `Stop.index`.
+    /// Returns `None` if the index is out of range.
+    pub fn stop_by_idx(&self, stop_idx: u32) -> Option<&Stop> {
+        self.stops.get(stop_idx as usize)
+    }
+
+    /// Retrieves a [`Area`] by its index `Area.index`.
+    /// Returns `None` if the index is out of range.
+    pub fn area_by_idx(&self, area_idx: u32) -> Option<&Area> {
+        self.areas.get(area_idx as usize)
+    }
+
+    /// Retrieves a [`Trip`] by its index `Trip.index`, e.g. from [`LegType::Transit`].
+    /// Returns `None` if the index is out of range.
+    ///
+    /// [`LegType::Transit`]: crate::raptor::LegType::Transit
+    pub fn trip_by_idx(&self, trip_idx: u32) -> Option<&Trip> {
+        self.trips.get(trip_idx as usize)
+    }
+
+    /// Retrieves a [`Route`] by its index `Route.index`.
+    /// Returns `None` if the index is out of range.
+    pub fn route_by_idx(&self, route_idx: u32) -> Option<&Route> {
+        self.routes.get(route_idx as usize)
+    }
+
+    /// Iterates over every `Stop.id`, in index order.
+    pub fn stop_ids(&self) -> impl Iterator<Item = &str> {
+        self.stops.iter().map(|stop| &*stop.id)
+    }
+
+    /// Iterates over every `Area.id`, in index order.
+    pub fn area_ids(&self) -> impl Iterator<Item = &str> {
+        self.areas.iter().map(|area| &*area.id)
+    }
+
+    /// Iterates over every `Trip.id`, in index order.
+    pub fn trip_ids(&self) -> impl Iterator<Item = &str> {
+        self.trips.iter().map(|trip| &*trip.id)
+    }
+
+    /// Iterates over every `Route.id`, in index order.
+    pub fn route_ids(&self) -> impl Iterator<Item = &str> {
+        self.routes.iter().map(|route| &*route.id)
+    }
+
     // --- Relationship Indicies (Adjacency Lists) Functions ---
     /// Returns a list of all stops contained within a specific parent area.
     pub fn stops_by_area_idx(&self, area_idx: u32) -> Vec<&Stop> {
@@ -138,6 +411,14 @@ impl Repository {
             .collect()
     }
 
+    /// Returns all stops belonging to the fare zone `zone_id` (`Stop.zone_id`).
+    pub fn stops_by_zone(&self, zone_id: &str) -> Vec<&Stop> {
+        self.stops
+            .iter()
+            .filter(|stop| stop.zone_id.as_deref() == Some(zone_id))
+            .collect()
+    }
+
     /// Returns a list of all stops contained within a specific parent stop.
     pub fn stops_by_station(&self, stop_idx: u32) -> Vec<&Stop> {
         self.station_to_stops[stop_idx as usize]
@@ -154,13 +435,36 @@ impl Repository {
 
     /// Calculates the centroid/representative coordinate of an area by
     /// averaging the coordinates of all stops within it.
+    ///
+    /// The centroid is stored in `Area.centroid` when the repository is loaded, so this is `O(1)`.
     pub fn coordinate_by_area_idx(&self, area_idx: u32) -> Coordinate {
+        match self.areas.get(area_idx as usize) {
+            Some(area) if area.bbox.is_some() => area.centroid,
+            _ => self.centroid_by_area_idx(area_idx),
+        }
+    }
+
+    pub(crate) fn centroid_by_area_idx(&self, area_idx: u32) -> Coordinate {
         self.stops_by_area_idx(area_idx)
             .iter()
             .map(|stop| stop.coordinate)
             .sum()
     }
 
+    /// Returns the stop within an area that is closest to the area's centroid (its medoid).
+    ///
+    /// Unlike the centroid, which can be skewed towards clusters of platforms, the
+    /// medoid is always a real stop. Returns `None` if the area has no stops.
+    pub fn medoid_by_area_idx(&self, area_idx: u32) -> Option<&Stop> {
+        let centroid = self.coordinate_by_area_idx(area_idx);
+        self.stops_by_area_idx(area_idx).into_iter().min_by(|a, b| {
+            a.coordinate
+                .network_distance(&centroid)
+                .as_meters()
+                .total_cmp(&b.coordinate.network_distance(&centroid).as_meters())
+        })
+    }
+
     /// Retrieves all outbound [`Transfer`] connections available from a specific [`Stop`] using it's index (`Stop.index`).
     pub fn transfers_by_stop_idx(&self, stop_idx: u32) -> Vec<&Transfer> {
         let transfers = &self.stop_to_transfers[stop_idx as usize];
@@ -170,6 +474,40 @@ impl Repository {
             .collect()
     }
 
+    /// Returns every [`Transfer`] listed in the feed, useful for auditing which connections exist.
+    ///
+    /// The footpaths generated between nearby stops are not included, see
+    /// [`Repository::all_footpaths`].
+    pub fn all_transfers(&self) -> &[Transfer] {
+        &self.transfers
+    }
+
+    /// Returns every footpath generated between nearby stops as
+    /// `(from_stop_idx, to_stop_idx, walk duration)`, the counterpart of
+    /// [`Repository::all_transfers`] for the walks a search may take without a feed transfer.
+    pub fn all_footpaths(&self) -> impl Iterator<Item = (u32, u32, Duration)> + '_ {
+        self.stop_to_walk_stop
+            .iter()
+            .zip(self.stop_to_walk_durations.iter())
+            .enumerate()
+            .flat_map(|(from_stop_idx, (stops, durations))| {
+                stops
+                    .iter()
+                    .zip(durations.iter())
+                    .map(move |(to_stop_idx, duration)| {
+                        (from_stop_idx as u32, *to_stop_idx, *duration)
+                    })
+            })
+    }
+
+    /// Retrieves the [`Transfer`] going from stop `a_stop_idx` to stop `b_stop_idx`, if any.
+    pub fn transfers_between(&self, a_stop_idx: u32, b_stop_idx: u32) -> Option<&Transfer> {
+        self.stop_to_transfers[a_stop_idx as usize]
+            .iter()
+            .map(|transfer_idx| &self.transfers[*transfer_idx as usize])
+            .find(|transfer| transfer.to_stop_idx == b_stop_idx)
+    }
+
     /// Finds all trips that call at a specific [`Stop`] using it's index (`Stop.index`).
     pub fn trips_by_stop_idx(&self, stop_idx: u32) -> Vec<&Trip> {
         self.stop_to_trips[stop_idx as usize]
@@ -178,17 +516,85 @@ impl Repository {
             .collect()
     }
 
+    /// Counts the departures from a [`Stop`] using it's index (`Stop.index`) in each hour of
+    /// the day, e.g. `[7]` holds the departures between `07:00:00` and `07:59:59`.
+    ///
+    /// Times past midnight (e.g. `25:10:00`) count towards the hour they fall in on the clock.
+    /// The last stop of a trip and stops without pickup are not departures.
+    pub fn departures_per_hour(&self, stop_idx: u32) -> [u32; 24] {
+        let mut hours = [0; 24];
+        // A loop trip is listed once for every time it calls at the stop.
+        let mut trips = self.stop_to_trips[stop_idx as usize].to_vec();
+        trips.sort_unstable();
+        trips.dedup();
+        trips
+            .iter()
+            .flat_map(|trip_idx| {
+                let stop_times = self.stop_times_by_trip_idx(*trip_idx);
+                &stop_times[..stop_times.len().saturating_sub(1)]
+            })
+            .filter(|stop_time| {
+                stop_time.stop_idx == stop_idx
+                    && !matches!(stop_time.pickup_type, StopAccessType::NoneAvailable)
+            })
+            .for_each(|stop_time| {
+                let hour = stop_time.departure_time.as_seconds() / 3600 % 24;
+                hours[hour as usize] += 1;
+            });
+        hours
+    }
+
+    /// [`Repository::departures_per_hour`] for every stop, indexed by `Stop.index`.
+    pub fn departures_per_hour_heatmap(&self) -> Vec<[u32; 24]> {
+        self.stops
+            .par_iter()
+            .map(|stop| self.departures_per_hour(stop.index))
+            .collect()
+    }
+
     /// Returns true if a specific [`Stop`] using it's index (`Stop.index`) has any trips connected to it.
     pub fn stop_idx_has_trips(&self, stop_idx: u32) -> bool {
         !self.stop_to_trips[stop_idx as usize].is_empty()
     }
 
+    /// Returns all stops that are never served by any trip, neither directly nor
+    /// through one of their child stops (e.g. entrances or decommissioned stops).
+    pub fn unserved_stops(&self) -> Vec<&Stop> {
+        self.stops
+            .iter()
+            .filter(|stop| self.stop_idx_is_unserved(stop.index))
+            .collect()
+    }
+
+    /// Returns true if neither the [`Stop`] nor any of its child stops has trips connected to it.
+    pub(crate) fn stop_idx_is_unserved(&self, stop_idx: u32) -> bool {
+        let stop_idx = self.platform_by_stop_idx(stop_idx).index;
+        !self.stop_idx_has_trips(stop_idx)
+            && self.station_to_stops[stop_idx as usize]
+                .iter()
+                .all(|child_idx| !self.stop_idx_has_trips(*child_idx))
+    }
+
+    /// Resolves a boarding area to the platform it belongs to, any other [`Stop`] resolves to itself.
+    pub fn platform_by_stop_idx(&self, stop_idx: u32) -> &Stop {
+        let stop = &self.stops[stop_idx as usize];
+        match (&stop.location_type, stop.parent_index) {
+            (LocationType::Boarding, Some(parent_idx)) => &self.stops[parent_idx as usize],
+            _ => stop,
+        }
+    }
+
     /// Identifies which high-level [`Route`] a specific [`Trip`] belongs to using it's index (`Trip.index`).
     pub fn route_by_trip_idx(&self, trip_idx: u32) -> &Route {
         let route_idx = self.trip_to_route[trip_idx as usize];
         &self.routes[route_idx as usize]
     }
 
+    /// Resolves the display-level [`Route`] a [`RaptorRoute`] was split from.
+    pub fn display_route(&self, raptor: &RaptorRoute) -> &Route {
+        &self.routes[raptor.route_idx as usize]
+    }
+
     /// Retrieves all scheduled trips for a specific [`Route`].
     pub fn trips_by_route_idx(&self, route_idx: u32) -> Vec<&Trip> {
         self.route_to_trips[route_idx as usize]
@@ -197,6 +603,103 @@ impl Repository {
             .collect()
     }
 
+    /// Computes the average gap between consecutive departures of a [`Route`] within
+    /// the window starting at `at`, using the route's identifier `Route.id`.
+    ///
+    /// Only trips starting at the route's first stop (where its earliest trip starts)
+    /// are counted, so the opposite direction does not halve the headway.
+    /// Returns `None` if the route does not exist or has fewer than two departures in the window.
+    pub fn headway(&self, route_id: &str, at: Time, window: Duration) -> Option<Duration> {
+        let route = self.route_by_id(route_id)?;
+        let first_departures: Vec<_> = self.route_to_trips[route.index as usize]
+            .iter()
+            .filter_map(|trip_idx| self.stop_times_by_trip_idx(*trip_idx).first())
+            .map(|stop_time| (stop_time.stop_idx, stop_time.departure_time))
+            .collect();
+        let (first_stop_idx, _) = first_departures
+            .iter()
+            .min_by_key(|(_, departure)| *departure)?;
+
+        let end = at + window;
+        let mut departures: Vec<_> = first_departures
+            .iter()
+            .filter(|(stop_idx, departure)| {
+                stop_idx == first_stop_idx && *departure >= at && *departure <= end
+            })
+            .map(|(_, departure)| departure.as_seconds())
+            .collect();
+        if departures.len() < 2 {
+            return None;
+        }
+        departures.sort_unstable();
+        let span = departures[departures.len() - 1] - departures[0];
+        Some(Duration::from_seconds(span / (departures.len() as u32 - 1)))
+    }
+
+    /// Retrieves a [`Service`] by its string identifier `Service.id`.
+    /// Returns `None` if the ID does not exist.
+    pub fn service_by_id(&self, id: &str) -> Option<&Service> {
+        let service_idx = self.service_lookup.get(id)?;
+        self.services.get(*service_idx as usize)
+    }
+
+    /// Summarizes the days any trip of a [`Route`] operates, using the route's identifier `Route.id`.
+    ///
+    /// The weekdays are the union of the route's services. A date is only reported as
+    /// removed if none of the route's services adds it back.
+    /// Returns empty [`ServiceDays`] if the route does not exist.
+    pub fn service_days_for_route(&self, route_id: &str) -> ServiceDays {
+        let Some(route) = self.route_by_id(route_id) else {
+            return ServiceDays::default();
+        };
+        let mut service_indices: Vec<_> = self.route_to_trips[route.index as usize]
+            .iter()
+            .filter_map(|trip_idx| self.trips[*trip_idx as usize].service_idx)
+            .collect();
+        service_indices.sort_unstable();
+        service_indices.dedup();
+
+        let mut days = ServiceDays::default();
+        for service_idx in service_indices {
+            let service = &self.services[service_idx as usize].days;
+            days.weekdays |= service.weekdays;
+            days.added_dates.extend(&service.added_dates);
+            days.removed_dates.extend(&service.removed_dates);
+        }
+        days.added_dates.sort_unstable();
+        days.added_dates.dedup();
+        days.removed_dates.sort_unstable();
+        days.removed_dates.dedup();
+        days.removed_dates
+            .retain(|date| days.added_dates.binary_search(date).is_err());
+        days
+    }
+
+    /// Returns `true` if the trip runs on `date`. Trips without a service run every day.
+    pub fn trip_runs_on(&self, trip_idx: u32, date: NaiveDate) -> bool {
+        self.trips[trip_idx as usize]
+            .service_idx
+            .is_none_or(|service_idx| self.services[service_idx as usize].runs_on_date(date))
+    }
+
+    /// Returns the indices of all trips whose span (first departure to last arrival)
+    /// overlaps the window between `from` and `to`.
+    pub fn trips_active_between(&self, from: Time, to: Time) -> Vec<u32> {
+        self.trips
+            .par_iter()
+            .filter_map(|trip| {
+                let stop_times = self.stop_times_by_trip_idx(trip.index);
+                let first_departure = stop_times.first()?.departure_time;
+                let last_arrival = stop_times.last()?.arrival_time;
+                if first_departure <= to && last_arrival >= from {
+                    Some(trip.index)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// Retrieves the full schedule (arrival/departure times) for every trip on a [`Route`].
     pub fn stop_times_by_route_idx(&self, route_idx: u32) -> Vec<&[StopTime]> {
         self.route_to_trips[route_idx as usize]
@@ -205,6 +708,27 @@ impl Repository {
             .collect()
     }
 
+    /// Like [`Repository::stop_times_by_route_idx`] but yields every trip's index together with
+    /// its schedule as it goes, without collecting them first.
+    pub fn iter_stop_times_by_route_idx(
+        &self,
+        route_idx: u32,
+    ) -> impl ExactSizeIterator<Item = (u32, &[StopTime])> {
+        self.route_to_trips[route_idx as usize]
+            .iter()
+            .map(|trip_idx| (*trip_idx, self.stop_times_by_trip_idx(*trip_idx)))
+    }
+
+    /// Parallel version of [`Repository::iter_stop_times_by_route_idx`].
+    pub fn par_iter_stop_times_by_route_idx(
+        &self,
+        route_idx: u32,
+    ) -> impl IndexedParallelIterator<Item = (u32, &[StopTime])> {
+        self.route_to_trips[route_idx as usize]
+            .par_iter()
+            .map(|trip_idx| (*trip_idx, self.stop_times_by_trip_idx(*trip_idx)))
+    }
+
     /// Efficiently retrieves a slice of [`StopTime`] entries for a specific trip.
     ///
     /// This uses a pre-computed pointer slice (start/count) into the global
@@ -216,13 +740,80 @@ impl Repository {
         &self.stop_times[start..end]
     }
 
+    /// Checked version of `stop_times_by_trip_idx(trip_idx)[idx]`.
+    ///
+    /// Returns `None` instead of panicking if the trip has fewer stop times than expected.
+    pub fn stop_time_at_idx(&self, trip_idx: u32, idx: usize) -> Option<&StopTime> {
+        self.stop_times_by_trip_idx(trip_idx).get(idx)
+    }
+
+    /// Returns the ordered [`Stop`]s a trip calls at, using the trip's identifier `Trip.id`.
+    /// Returns `None` if the ID does not exist.
+    pub fn stops_for_trip(&self, trip_id: &str) -> Option<Vec<&Stop>> {
+        let stops = self
+            .timed_stops_for_trip(trip_id)?
+            .into_iter()
+            .map(|(stop, _)| stop)
+            .collect();
+        Some(stops)
+    }
+
+    /// Same as [`Repository::stops_for_trip`] but with the [`StopTime`] of every call alongside.
+    pub fn timed_stops_for_trip(&self, trip_id: &str) -> Option<Vec<(&Stop, &StopTime)>> {
+        let trip = self.trip_by_id(trip_id)?;
+        let stops = self
+            .stop_times_by_trip_idx(trip.index)
+            .iter()
+            .map(|stop_time| (&self.stops[stop_time.stop_idx as usize], stop_time))
+            .collect();
+        Some(stops)
+    }
+
+    /// Finds the two consecutive [`StopTime`]s of a trip that bracket `now`, together with
+    /// how far (`0.0..=1.0`) the vehicle has come between them.
+    ///
+    /// The fraction assumes a constant speed, so it can be applied directly to the
+    /// `distance_traveled` of the two stop times to place the vehicle on the shape.
+    /// Before the first departure the first segment is returned with `0.0`, and after
+    /// the last arrival the final segment is returned with `1.0`.
+    pub fn closest_stop_time(
+        &self,
+        trip_idx: u32,
+        now: Time,
+    ) -> Option<(&StopTime, &StopTime, f64)> {
+        let stop_times = self.stop_times_by_trip_idx(trip_idx);
+        if stop_times.len() < 2 {
+            return None;
+        }
+        let next = stop_times.partition_point(|stop_time| stop_time.arrival_time <= now);
+        if next == 0 {
+            return Some((&stop_times[0], &stop_times[1], 0.0));
+        }
+        if next == stop_times.len() {
+            let last = stop_times.len() - 1;
+            return Some((&stop_times[last - 1], &stop_times[last], 1.0));
+        }
+        let from = &stop_times[next - 1];
+        let to = &stop_times[next];
+        if now <= from.departure_time {
+            return Some((from, to, 0.0));
+        }
+        let elapsed = (now - from.departure_time).as_seconds() as f64;
+        let total = (to.arrival_time - from.departure_time).as_seconds() as f64;
+        Some((from, to, (elapsed / total).clamp(0.0, 1.0)))
+    }
+
     /// Efficiently retrieves a slice of [`Shape`] entries for a specific trip.
     ///
     /// This uses a pre-computed pointer slice (start/count) into the global
     /// `shapes` array for `O(1)` access.
     pub fn shapes_by_trip_idx(&self, trip_idx: u32) -> Option<&[Shape]> {
         let trip = &self.trips[trip_idx as usize];
-        let slice = self.raptor_to_shapes_slice[trip.raptor_route_idx as usize]?;
+        let slice = self
+            .raptor_to_shapes_slice
+            .get(trip.raptor_route_idx as usize)
+            .copied()
+            .flatten()?;
         let start = slice.start_idx as usize;
         let end = start + slice.count as usize;
         Some(&self.shapes[start..end])
@@ -233,32 +824,108 @@ impl Repository {
     /// This uses a grid-based cell lookup for performance, followed by an
     /// exact distance filter using the network distance metric.
     pub fn stops_by_coordinate(&self, coordinate: &Coordinate, distance: Distance) -> Vec<&Stop> {
+        self.stops_with_distance_by_coordinate(coordinate, distance)
+            .map(|(stop, _)| stop)
+            .collect()
+    }
+
+    /// Spatial query: Returns the single stop closest to a coordinate and its distance.
+    ///
+    /// Grid rings around the coordinate are scanned outwards until no unscanned cell can
+    /// hold a closer stop, far away coordinates fall back to checking every stop.
+    /// Only returns `None` if the network has no stops.
+    pub fn nearest_stop<'a>(&'a self, coordinate: &Coordinate) -> Option<(&'a Stop, Distance)> {
+        const MAX_RINGS: i32 = 16;
+        let closest = |best: Option<(&'a Stop, Distance)>, stop_idx: &u32| {
+            let stop = &self.stops[*stop_idx as usize];
+            let distance = stop.coordinate.network_distance(coordinate);
+            match best {
+                Some((_, best_distance)) if best_distance <= distance => best,
+                _ => Some((stop, distance)),
+            }
+        };
+
+        // Cells are narrower east to west than north to south away from the equator.
+        let cell_width =
+            AVERAGE_STOP_DISTANCE.as_meters() * coordinate.latitude.to_radians().cos().abs();
+        let (origin_x, origin_y) = coordinate.to_cell();
+        let mut best: Option<(&Stop, Distance)> = None;
+        for ring in 0..=MAX_RINGS {
+            // Every stop outside the scanned rings is at least `ring - 1` cells away.
+            if let Some((_, distance)) = best
+                && distance.as_meters() <= (ring - 1) as f32 * cell_width
+            {
+                return best;
+            }
+            best = (-ring..=ring)
+                .flat_map(|x| (-ring..=ring).map(move |y| (x, y)))
+                .filter(|(x, y)| x.abs() == ring || y.abs() == ring)
+                .filter_map(|(x, y)| self.stop_distance_lookup.get(&(origin_x + x, origin_y + y)))
+                .flat_map(|stop_idxs| stop_idxs.iter())
+                .fold(best, closest);
+        }
+        self.stop_distance_lookup
+            .values()
+            .flat_map(|stop_idxs| stop_idxs.iter())
+            .fold(None, closest)
+    }
+
+    /// Spatial query: Returns all stops within a certain distance of a coordinate
+    /// together with their distance, sorted with the closest stop first.
+    ///
+    /// The network distance is only computed once per stop.
+    pub fn stops_by_coordinate_sorted(
+        &self,
+        coordinate: &Coordinate,
+        distance: Distance,
+    ) -> Vec<(&Stop, Distance)> {
+        let mut stops: Vec<_> = self
+            .stops_with_distance_by_coordinate(coordinate, distance)
+            .collect();
+        stops.sort_by(|(_, a), (_, b)| a.as_meters().total_cmp(&b.as_meters()));
+        stops
+    }
+
+    /// Spatial query: Returns every stop within a `max` walk of a coordinate together with
+    /// the time it takes to walk there, closest first.
+    ///
+    /// The walk time uses the network distance and the walking speed used by the router,
+    /// so at 1.5 m/s a 10 minute budget reaches stops up to 900 meters away.
+    pub fn stops_within_walk_time(
+        &self,
+        coordinate: &Coordinate,
+        max: Duration,
+    ) -> Vec<(&Stop, Duration)> {
+        let mut stops: Vec<_> = self
+            .stops_with_distance_by_coordinate(coordinate, raptor::walk_distance_in(max))
+            .map(|(stop, distance)| (stop, raptor::time_to_walk(distance)))
+            .filter(|(_, duration)| *duration <= max)
+            .collect();
+        stops.sort_by_key(|(_, duration)| *duration);
+        stops
+    }
+
+    fn stops_with_distance_by_coordinate(
+        &self,
+        coordinate: &Coordinate,
+        distance: Distance,
+    ) -> impl Iterator<Item = (&Stop, Distance)> {
         let reach = (distance / AVERAGE_STOP_DISTANCE).as_meters().ceil().abs() as i32 + 1;
         let (origin_x, origin_y) = coordinate.to_cell();
         (-reach..=reach)
-            .flat_map(|x| {
-                (-reach..=reach)
-                    .flat_map(move |y| {
-                        let cell = (origin_x + x, origin_y + y);
-                        if let Some(stop_idxs) = self.stop_distance_lookup.get(&cell) {
-                            stop_idxs
-                                .iter()
-                                .filter_map(|stop_idx| {
-                                    let stop = &self.stops[*stop_idx as usize];
-                                    if stop.coordinate.network_distance(coordinate) <= distance {
-                                        Some(stop)
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<Vec<_>>()
-                        } else {
-                            Vec::new()
-                        }
-                    })
-                    .collect::<Vec<_>>()
+            .flat_map(move |x| (-reach..=reach).map(move |y| (origin_x + x, origin_y + y)))
+            .filter_map(|cell| self.stop_distance_lookup.get(&cell))
+            .flat_map(|stop_idxs| stop_idxs.iter())
+            .filter_map(move |stop_idx| {
+                let stop = &self.stops[*stop_idx as usize];
+                let stop_distance = stop.coordinate.network_distance(coordinate);
+                if stop_distance <= distance || stop_distance.approx_eq(&distance, DISTANCE_EPSILON)
+                {
+                    Some((stop, stop_distance))
+                } else {
+                    None
+                }
             })
-            .collect()
     }
 
     /// Spatial query: Returns all logical areas within range of a coordinate.  
@@ -279,6 +946,244 @@ impl Repository {
             .collect()
     }
 
+    /// Returns the stops with service that can't be reached from `origin` when leaving at
+    /// `departure`, e.g. to find isolated parts of the network or broken data.
+    /// Stops without any service are left out, see [`Repository::unserved_stops`].
+    pub fn unreachable_from(
+        &self,
+        origin: Location,
+        departure: Time,
+    ) -> Result<Vec<&Stop>, raptor::Error> {
+        let result = Raptor::new(self, origin.clone(), origin)
+            .departure_at(departure)
+            .one_to_all()
+            .solve_raw()?;
+        Ok(self
+            .stops
+            .iter()
+            .filter(|stop| {
+                self.stop_idx_has_trips(stop.index) && result.arrival_at(stop.index).is_none()
+            })
+            .collect())
+    }
+
+    /// Returns the index of the next trip on the same pattern (`RaptorRoute`) as `trip_idx`
+    /// that departs its first stop after `after`, `None` at the end of service.
+    pub fn next_trip_on_pattern(&self, trip_idx: u32, after: Time) -> Option<u32> {
+        let raptor = self
+            .raptor_routes
+            .get(self.trips[trip_idx as usize].raptor_route_idx as usize)?;
+        // Trips are sorted by their departure from the first stop.
+        let position = raptor.trips.iter().position(|idx| *idx == trip_idx)?;
+        let later = &raptor.trips[position + 1..];
+        let idx = later.partition_point(|&idx| {
+            raptor::get_departure_time(self, idx, 0).is_some_and(|time| time <= after)
+        });
+        later.get(idx).copied()
+    }
+
+    /// Returns every trip of the route `route_id` that serves `from_stop_idx` and later
+    /// `to_stop_idx`, departing `from_stop_idx` at or after `after`. Each entry is the trip
+    /// index with its departure from the first stop and arrival at the second, ordered
+    /// by departure.
+    pub fn trips_between_stops_on_route(
+        &self,
+        route_id: &str,
+        from_stop_idx: u32,
+        to_stop_idx: u32,
+        after: Time,
+    ) -> Vec<(u32, Time, Time)> {
+        let mut trips = self.rides_between(
+            self.patterns_for_route(route_id),
+            from_stop_idx,
+            to_stop_idx,
+            after,
+        );
+        trips.sort_by_key(|(_, departure, _)| *departure);
+        trips
+    }
+
+    /// Returns every trip riding from `from_stop_idx` to `to_stop_idx` without a transfer,
+    /// departing at or after `after`. Each entry is the trip index with its departure from
+    /// the first stop and arrival at the second, ordered by arrival.
+    pub fn one_seat_rides(
+        &self,
+        from_stop_idx: u32,
+        to_stop_idx: u32,
+        after: Time,
+    ) -> Vec<(u32, Time, Time)> {
+        let mut trips = self.rides_between(
+            self.raptors_by_stop_idx(from_stop_idx),
+            from_stop_idx,
+            to_stop_idx,
+            after,
+        );
+        trips.sort_by_key(|(_, departure, arrival)| (*arrival, *departure));
+        trips
+    }
+
+    /// The trips of `raptors` calling at `to_stop_idx` after `from_stop_idx`, unordered.
+    fn rides_between<'a>(
+        &'a self,
+        raptors: Vec<&'a RaptorRoute>,
+        from_stop_idx: u32,
+        to_stop_idx: u32,
+        after: Time,
+    ) -> Vec<(u32, Time, Time)> {
+        raptors
+            .into_iter()
+            .filter_map(|raptor| {
+                let from = raptor::index_in_route(raptor, from_stop_idx)?;
+                let to = raptor::indices_in_route(raptor, to_stop_idx).find(|to| *to > from)?;
+                Some((raptor, from as usize, to as usize))
+            })
+            .flat_map(|(raptor, from, to)| {
+                raptor.trips.iter().filter_map(move |&trip_idx| {
+                    let departure = raptor::get_departure_time(self, trip_idx, from)?;
+                    let arrival = raptor::get_arrival_time(self, trip_idx, to)?;
+                    (departure >= after).then_some((trip_idx, departure, arrival))
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that every trip of every [`RaptorRoute`] calls at exactly the route's stops,
+    /// in order. The router relies on this to index stop times by the route's stop positions.
+    ///
+    /// Runs after building the raptor routes in debug builds, returns the first broken invariant.
+    pub fn validate_raptor_routes(&self) -> Result<(), RaptorRouteError> {
+        self.raptor_routes.iter().try_for_each(|raptor| {
+            raptor.trips.iter().try_for_each(|trip_idx| {
+                let stop_times = self.stop_times_by_trip_idx(*trip_idx);
+                let ids = || {
+                    (
+                        self.routes[raptor.route_idx as usize].id.clone(),
+                        self.trips[*trip_idx as usize].id.clone(),
+                    )
+                };
+                if stop_times.len() != raptor.stops.len() {
+                    let (route_id, trip_id) = ids();
+                    return Err(RaptorRouteError::LengthMismatch {
+                        route_id,
+                        trip_id,
+                        expected: raptor.stops.len(),
+                        found: stop_times.len(),
+                    });
+                }
+                if stop_times
+                    .iter()
+                    .zip(raptor.stops.iter())
+                    .any(|(stop_time, stop_idx)| stop_time.stop_idx != *stop_idx)
+                {
+                    let (route_id, trip_id) = ids();
+                    return Err(RaptorRouteError::StopMismatch { route_id, trip_id });
+                }
+                Ok(())
+            })
+        })
+    }
+
+    /// Returns the distinct stop patterns (e.g. short-turns or express variants) a route runs,
+    /// using the route's identifier `Route.id`. Every pattern is a `RaptorRoute`.
+    pub fn patterns_for_route(&self, route_id: &str) -> Vec<&RaptorRoute> {
+        self.route_by_id(route_id)
+            .map(|route| self.raptors_by_route_idx(route.index))
+            .unwrap_or_default()
+    }
+
+    /// Returns the ordered stops and the number of trips of every pattern a route runs,
+    /// most frequent pattern first.
+    pub fn pattern_stops_for_route(&self, route_id: &str) -> Vec<(Vec<&Stop>, usize)> {
+        let mut patterns: Vec<_> = self
+            .patterns_for_route(route_id)
+            .into_iter()
+            .map(|raptor| {
+                let stops = raptor
+                    .stops
+                    .iter()
+                    .map(|stop_idx| &self.stops[*stop_idx as usize])
+                    .collect();
+                (stops, raptor.trips.len())
+            })
+            .collect();
+        patterns.sort_by(|(_, a), (_, b)| b.cmp(a));
+        patterns
+    }
+
+    /// Scores how well the stop order of a `RaptorRoute` follows its shape, from `0.0` to `1.0`.
+    ///
+    /// Every stop is projected onto its closest shape point and the score is the share of
+    /// consecutive stops that move forward along the shape. Well-formed routes score `1.0`,
+    /// reversed or scrambled stop sequences from bad feeds score low.
+    /// Returns `None` if the route has no shape or fewer than two stops.
+    pub fn check_stop_shape_alignment(&self, raptor_idx: u32) -> Option<f64> {
+        let raptor = &self.raptor_routes[raptor_idx as usize];
+        let slice = self.raptor_to_shapes_slice[raptor_idx as usize]?;
+        let start = slice.start_idx as usize;
+        let shapes = &self.shapes[start..start + slice.count as usize];
+        if shapes.is_empty() || raptor.stops.len() < 2 {
+            return None;
+        }
+
+        let positions: Vec<_> = raptor
+            .stops
+            .iter()
+            .map(|stop_idx| {
+                let coordinate = &self.stops[*stop_idx as usize].coordinate;
+                shapes
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| {
+                        a.coordinate
+                            .network_distance(coordinate)
+                            .as_meters()
+                            .total_cmp(&b.coordinate.network_distance(coordinate).as_meters())
+                    })
+                    .map(|(i, _)| i)
+                    .unwrap_or_default()
+            })
+            .collect();
+        let forward = positions
+            .windows(2)
+            .filter(|pair| pair[0] <= pair[1])
+            .count();
+        Some(forward as f64 / (positions.len() - 1) as f64)
+    }
+
+    /// Returns the display style of the [`Route`] behind a `RaptorRoute`,
+    /// using the GTFS default colors where the feed omits them.
+    pub fn route_style(&self, raptor_idx: u32) -> RouteStyle {
+        let route = self.display_route(&self.raptor_routes[raptor_idx as usize]);
+        let non_empty = |color: &Option<Arc<str>>| color.clone().filter(|color| !color.is_empty());
+        RouteStyle {
+            color: non_empty(&route.color).unwrap_or(DEFAULT_ROUTE_COLOR.into()),
+            text_color: non_empty(&route.text_color).unwrap_or(DEFAULT_ROUTE_TEXT_COLOR.into()),
+            short_name: route.short_name.clone(),
+            route_type: route.route_type,
+        }
+    }
+
+    /// Labels a `RaptorRoute` as [`PatternKind::Limited`] when its stops are a strict subset
+    /// of the stops of its route's most common pattern, the one with the most trips.
+    /// Every other pattern, including the most common one, is [`PatternKind::Local`].
+    pub fn pattern_kind(&self, raptor_idx: u32) -> PatternKind {
+        let raptor = &self.raptor_routes[raptor_idx as usize];
+        let Some(common) = self
+            .raptors_by_route_idx(raptor.route_idx)
+            .into_iter()
+            .max_by_key(|other| (other.trips.len(), other.stops.len(), Reverse(other.index)))
+        else {
+            return PatternKind::Local;
+        };
+        let common_stops: HashSet<u32> = common.stops.iter().copied().collect();
+        let stops: HashSet<u32> = raptor.stops.iter().copied().collect();
+        if stops.len() < common_stops.len() && stops.is_subset(&common_stops) {
+            PatternKind::Limited
+        } else {
+            PatternKind::Local
+        }
+    }
+
     /// Identifies which optimized RAPTOR routes pass through a specific stop.
     pub fn raptors_by_stop_idx(&self, stop_idx: u32) -> Vec<&RaptorRoute> {
         self.stop_to_raptors[stop_idx as usize]
@@ -287,6 +1192,33 @@ impl Repository {
             .collect()
     }
 
+    /// Returns true if any stop pattern of the [`Route`] starts or ends at the stop, i.e. the
+    /// stop is where some of the route's trips begin or terminate.
+    pub fn is_terminal_for_route(&self, stop_idx: u32, route_idx: u32) -> bool {
+        self.route_to_raptors[route_idx as usize]
+            .iter()
+            .map(|raptor_idx| &self.raptor_routes[*raptor_idx as usize].stops)
+            .any(|stops| stops.first() == Some(&stop_idx) || stops.last() == Some(&stop_idx))
+    }
+
+    /// Returns the indices of all RAPTOR routes that serve `from_stop_idx` before `to_stop_idx`,
+    /// i.e. every route that could carry a rider between the two stops without a transfer.
+    pub fn raptor_routes_between(&self, from_stop_idx: u32, to_stop_idx: u32) -> Vec<u32> {
+        self.raptor_routes
+            .iter()
+            .filter(|route| {
+                matches!(
+                    (
+                        index_in_route(route, from_stop_idx),
+                        indices_in_route(route, to_stop_idx).last(),
+                    ),
+                    (Some(from), Some(to)) if from < to
+                )
+            })
+            .map(|route| route.index)
+            .collect()
+    }
+
     /// Returns all possible walkable stops by stop_idx.  
     pub fn nearby_stops_by_stop_idx(&self, stop_idx: u32) -> Vec<&Stop> {
         let stops = &self.stop_to_walk_stop[stop_idx as usize];
@@ -307,4 +1239,1371 @@ impl Repository {
     pub fn search_stops_by_name<'a>(&'a self, needle: &'a str) -> Vec<&'a Stop> {
         shared::search(needle, &self.stops)
     }
+
+    /// Performs a fuzzy text search against the names of the stops within the area `area_id`.
+    /// Returns an empty list if the area does not exist.
+    pub fn search_stops_in_area(&self, area_id: &str, needle: &str) -> Vec<&Stop> {
+        let Some(area) = self.resolve_area(area_id) else {
+            return vec![];
+        };
+        let stops = self.stops_by_area_idx(area.index);
+        shared::search(needle, &stops)
+            .into_iter()
+            .copied()
+            .collect()
+    }
+
+    /// Performs a fuzzy text search across areas, stops and routes at once, e.g. for a single
+    /// autocomplete source.
+    ///
+    /// Every word of `needle` is matched on its own as well, so `"4 Central"` finds both
+    /// route `4` and Central Station. Routes match on their short and long names.
+    /// Returns at most [`MAX_SEARCH_HITS`] hits, best match first.
+    pub fn search(&self, needle: &str) -> Vec<SearchHit<'_>> {
+        let needle = needle.to_lowercase();
+        let score = |hay: &str| shared::fuzzy::score_any(&needle, hay);
+        let areas = self.areas.par_iter().map(|area| SearchHit {
+            score: score(&area.normalized_name),
+            kind: SearchHitKind::Area(area),
+        });
+        let stops = self.stops.par_iter().map(|stop| SearchHit {
+            score: score(&stop.normalized_name),
+            kind: SearchHitKind::Stop(stop),
+        });
+        let routes = self.routes.par_iter().map(|route| SearchHit {
+            score: route
+                .short_name
+                .iter()
+                .chain(route.long_name.iter())
+                .map(|name| score(&name.to_lowercase()))
+                .fold(0.0, f64::max),
+            kind: SearchHitKind::Route(route),
+        });
+        let mut hits: Vec<_> = areas
+            .chain(stops)
+            .chain(routes)
+            .filter(|hit| hit.score > 0.1)
+            .collect();
+        hits.par_sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(MAX_SEARCH_HITS);
+        hits
+    }
+
+    /// Performs a fuzzy text search against the headsigns of trips, both `Trip.head_sign`
+    /// and the headsigns of its stop times, best match first.
+    pub fn trips_by_headsign(&self, headsign: &str) -> Vec<&Trip> {
+        let needle = headsign.to_lowercase();
+        let mut results: Vec<(&Trip, f64)> = self
+            .trips
+            .par_iter()
+            .filter_map(|trip| {
+                let stop_time_headsigns = self
+                    .stop_times_by_trip_idx(trip.index)
+                    .iter()
+                    .filter_map(|stop_time| stop_time.headsign.as_ref());
+                let score = trip
+                    .head_sign
+                    .iter()
+                    .chain(stop_time_headsigns)
+                    .map(|hay| shared::fuzzy::score(&needle, &hay.to_lowercase()))
+                    .fold(0.0, f64::max);
+                // Headsigns are short and similar, so require a closer match than name search.
+                if score > 0.6 {
+                    Some((trip, score))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        results.par_sort_unstable_by(|(_, a), (_, b)| b.total_cmp(a));
+        results.into_iter().map(|(trip, _)| trip).collect()
+    }
+}
+
+/// Loads the small GTFS feed in `tests/fixtures/sample` used throughout the tests.
+#[cfg(test)]
+pub(crate) fn sample_repository() -> Repository {
+    let gtfs = crate::gtfs::GtfsReader::new().from_directory(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/sample"
+    ));
+    Repository::new()
+        .load_gtfs(gtfs)
+        .expect("Failed to load sample feed")
+}
+
+/// Loads a copy of the sample feed after `edit` changed its files, see [`SampleFeed`].
+#[cfg(test)]
+pub(crate) fn sample_repository_with(edit: impl FnOnce(&SampleFeed)) -> Repository {
+    let feed = SampleFeed::new();
+    edit(&feed);
+    feed.load(crate::gtfs::Config::default())
+}
+
+/// A directory of its own under the system temp directory, removed again on drop so a
+/// failing test leaves nothing behind for the next run.
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("blaise_test_{}_{count}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A copy of `tests/fixtures/sample` that a test can edit without touching the fixture
+/// or the copies of other tests running in parallel.
+#[cfg(test)]
+pub(crate) struct SampleFeed(TempDir);
+
+#[cfg(test)]
+impl SampleFeed {
+    pub(crate) fn new() -> Self {
+        let directory = TempDir::new();
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sample");
+        for entry in std::fs::read_dir(fixture).unwrap() {
+            let path = entry.unwrap().path();
+            std::fs::copy(&path, directory.path().join(path.file_name().unwrap())).unwrap();
+        }
+        Self(directory)
+    }
+
+    pub(crate) fn path(&self) -> &std::path::Path {
+        self.0.path()
+    }
+
+    pub(crate) fn read(&self, file: &str) -> String {
+        std::fs::read_to_string(self.path().join(file)).unwrap()
+    }
+
+    pub(crate) fn write(&self, file: &str, content: impl AsRef<[u8]>) {
+        std::fs::write(self.path().join(file), content).unwrap();
+    }
+
+    /// Appends `rows` to `file`, one per line.
+    pub(crate) fn append(&self, file: &str, rows: &[&str]) {
+        let mut content = self.read(file);
+        rows.iter().for_each(|row| {
+            content.push_str(row);
+            content.push('\n');
+        });
+        self.write(file, content);
+    }
+
+    pub(crate) fn reader(&self, config: crate::gtfs::Config) -> crate::gtfs::GtfsReader {
+        crate::gtfs::GtfsReader::new()
+            .with_config(config)
+            .from_directory(self.path())
+    }
+
+    pub(crate) fn load(&self, config: crate::gtfs::Config) -> Repository {
+        Repository::new()
+            .load_gtfs(self.reader(config))
+            .expect("Failed to load sample feed")
+    }
+}
+
+#[test]
+fn trips_active_between_test() {
+    let repository = sample_repository();
+    let from = Time::from_hms("08:00:00").unwrap();
+    let to = Time::from_hms("09:00:00").unwrap();
+    let active = repository.trips_active_between(from, to);
+    assert_eq!(active.len(), 20);
+    active.iter().for_each(|trip_idx| {
+        let stop_times = repository.stop_times_by_trip_idx(*trip_idx);
+        assert!(stop_times.first().unwrap().departure_time <= to);
+        assert!(stop_times.last().unwrap().arrival_time >= from);
+    });
+}
+
+#[test]
+fn raptor_routes_between_test() {
+    let repository = sample_repository();
+    let stop_idx = |id: &str| repository.stop_by_id(id).unwrap().index;
+    let route = repository.route_by_id("R1").unwrap();
+
+    let routes = repository.raptor_routes_between(stop_idx("S_A1"), stop_idx("S_E1"));
+    assert_eq!(routes.len(), 2);
+    routes.iter().for_each(|raptor_idx| {
+        assert_eq!(
+            repository.raptor_routes[*raptor_idx as usize].route_idx,
+            route.index
+        )
+    });
+
+    let routes = repository.raptor_routes_between(stop_idx("S_B"), stop_idx("S_D"));
+    assert_eq!(routes.len(), 1);
+
+    let routes = repository.raptor_routes_between(stop_idx("S_E1"), stop_idx("S_A1"));
+    assert!(routes.is_empty());
+}
+
+#[test]
+fn shortest_path_distance_test() {
+    let mut repository = Repository::new();
+    // A straight line of stops ~300m apart (network distance), plus one stop off to the side.
+    let coordinates = [
+        Coordinate::new(59.33, 18.000),
+        Coordinate::new(59.33, 18.004),
+        Coordinate::new(59.33, 18.008),
+        Coordinate::new(59.332, 18.006),
+        Coordinate::new(59.33, 18.012),
+        Coordinate::new(59.33, 18.016),
+    ];
+    repository.stops = coordinates
+        .iter()
+        .enumerate()
+        .map(|(i, coordinate)| Stop {
+            index: i as u32,
+            id: format!("{i}").into(),
+            coordinate: *coordinate,
+            ..Default::default()
+        })
+        .collect();
+    repository.stop_to_walk_stop = repository
+        .stops
+        .iter()
+        .map(|a| {
+            repository
+                .stops
+                .iter()
+                .filter(|b| {
+                    a.index != b.index
+                        && a.coordinate.network_distance(&b.coordinate) <= AVERAGE_STOP_DISTANCE
+                })
+                .map(|b| b.index)
+                .collect()
+        })
+        .collect();
+
+    let target = repository.stops[5].coordinate;
+    let itinerary = repository.shortest_path_distance(0, 5).unwrap();
+    assert!(!itinerary.legs.is_empty());
+    let mut remaining = repository.stops[0].coordinate.network_distance(&target);
+    let mut time = Time::default();
+    itinerary.legs.iter().for_each(|leg| {
+        assert!(matches!(leg.leg_type, crate::raptor::LegType::Walk));
+        assert_eq!(leg.departue_time, time);
+        assert!(leg.arrival_time > leg.departue_time);
+        time = leg.arrival_time;
+        let Location::Stop(id) = &leg.to else {
+            panic!("Expected walk legs between stops");
+        };
+        let next = repository.stops[id.parse::<usize>().unwrap()].coordinate;
+        let next_remaining = next.network_distance(&target);
+        assert!(next_remaining < remaining);
+        remaining = next_remaining;
+    });
+    assert!(matches!(&itinerary.legs.last().unwrap().to, Location::Stop(id) if id.as_ref() == "5"));
+
+    assert!(
+        repository
+            .shortest_path_distance(0, 0)
+            .unwrap()
+            .legs
+            .is_empty()
+    );
+}
+
+#[test]
+fn unserved_stops_test() {
+    let repository = sample_repository();
+    let unserved = repository.unserved_stops();
+    assert_eq!(unserved.len(), 1);
+    assert_eq!(unserved[0].id.as_ref(), "S_X");
+}
+
+#[test]
+fn closest_stop_time_test() {
+    let repository = sample_repository();
+    let trip = repository.trip_by_id("R1_L_0700").unwrap();
+    let stop_id = |stop_time: &StopTime| repository.stops[stop_time.stop_idx as usize].id.clone();
+
+    let (from, to, fraction) = repository
+        .closest_stop_time(trip.index, Time::from_hms("07:01:30").unwrap())
+        .unwrap();
+    assert_eq!(stop_id(from).as_ref(), "S_A1");
+    assert_eq!(stop_id(to).as_ref(), "S_B");
+    assert!((fraction - 0.5).abs() < f64::EPSILON);
+
+    let (from, _, fraction) = repository
+        .closest_stop_time(trip.index, Time::from_hms("06:00:00").unwrap())
+        .unwrap();
+    assert_eq!(stop_id(from).as_ref(), "S_A1");
+    assert_eq!(fraction, 0.0);
+
+    let (_, to, fraction) = repository
+        .closest_stop_time(trip.index, Time::from_hms("10:00:00").unwrap())
+        .unwrap();
+    assert_eq!(stop_id(to).as_ref(), "S_E1");
+    assert_eq!(fraction, 1.0);
+}
+
+#[test]
+fn medoid_by_area_idx_test() {
+    let mut repository = Repository::new();
+    // A station with three platforms clustered together and one far away.
+    let coordinates = [
+        Coordinate::new(59.33, 18.0),
+        Coordinate::new(59.33, 18.0),
+        Coordinate::new(59.3301, 18.0001),
+        Coordinate::new(59.33, 18.0002),
+        Coordinate::new(59.34, 18.02),
+    ];
+    repository.stops = coordinates
+        .iter()
+        .enumerate()
+        .map(|(i, coordinate)| Stop {
+            index: i as u32,
+            coordinate: *coordinate,
+            parent_index: if i == 0 { None } else { Some(0) },
+            ..Default::default()
+        })
+        .collect();
+    repository.station_to_stops = vec![
+        vec![1, 2, 3, 4].into(),
+        [].into(),
+        [].into(),
+        [].into(),
+        [].into(),
+    ]
+    .into();
+    repository.area_to_stops = vec![vec![0].into()].into();
+
+    let centroid = repository.coordinate_by_area_idx(0);
+    let medoid = repository.medoid_by_area_idx(0).unwrap();
+    assert!([1, 2, 3].contains(&medoid.index));
+    repository.stops_by_area_idx(0).iter().for_each(|stop| {
+        assert!(stop.coordinate.network_distance(&centroid) > Distance::from_meters(300.0));
+    });
+}
+
+#[test]
+fn display_route_test() {
+    let repository = sample_repository();
+    let blue_line = repository.route_by_id("R1").unwrap();
+    repository.route_to_raptors[blue_line.index as usize]
+        .iter()
+        .for_each(|raptor_idx| {
+            let raptor = &repository.raptor_routes[*raptor_idx as usize];
+            assert_eq!(repository.display_route(raptor).id.as_ref(), "R1");
+        });
+}
+
+#[test]
+fn next_trip_on_pattern_test() {
+    let repository = sample_repository();
+    let trip_idx = |id: &str| repository.trip_by_id(id).unwrap().index;
+    let time = |hms: &str| Time::from_hms(hms).unwrap();
+
+    let first = trip_idx("R1_L_0700");
+    assert_eq!(
+        repository.next_trip_on_pattern(first, time("07:00:00")),
+        Some(trip_idx("R1_L_0710"))
+    );
+    // Missed a few, the express runs a different pattern and is never returned.
+    assert_eq!(
+        repository.next_trip_on_pattern(first, time("07:25:00")),
+        Some(trip_idx("R1_L_0730"))
+    );
+    assert_eq!(
+        repository.next_trip_on_pattern(first, time("09:00:00")),
+        None
+    );
+    assert_eq!(
+        repository.next_trip_on_pattern(trip_idx("R1_L_0900"), time("07:00:00")),
+        None
+    );
+}
+
+#[test]
+fn trips_between_stops_on_route_test() {
+    let repository = sample_repository();
+    let stop_idx = |id: &str| repository.stop_by_id(id).unwrap().index;
+    let time = |hms: &str| Time::from_hms(hms).unwrap();
+    let trip_id = |trip_idx: u32| repository.trips[trip_idx as usize].id.to_string();
+
+    let trips = repository.trips_between_stops_on_route(
+        "R1",
+        stop_idx("S_A1"),
+        stop_idx("S_C1"),
+        time("07:00:00"),
+    );
+    let first: Vec<_> = trips
+        .iter()
+        .take(3)
+        .map(|(trip_idx, departure, arrival)| (trip_id(*trip_idx), *departure, *arrival))
+        .collect();
+    // The express runs its own pattern but serves both stops too.
+    assert_eq!(
+        first,
+        vec![
+            ("R1_L_0700".into(), time("07:00:00"), time("07:06:00")),
+            ("R1_X_0705".into(), time("07:05:00"), time("07:09:00")),
+            ("R1_L_0710".into(), time("07:10:00"), time("07:16:00")),
+        ]
+    );
+    assert!(trips.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+    // Later departures only, and never against the direction of travel.
+    let later = repository.trips_between_stops_on_route(
+        "R1",
+        stop_idx("S_A1"),
+        stop_idx("S_C1"),
+        time("07:06:00"),
+    );
+    assert_eq!(trip_id(later[0].0), "R1_L_0710");
+    assert!(
+        repository
+            .trips_between_stops_on_route(
+                "R1",
+                stop_idx("S_C1"),
+                stop_idx("S_A1"),
+                time("07:00:00")
+            )
+            .is_empty()
+    );
+}
+
+#[test]
+fn stop_by_code_test() {
+    let repository = sample_repository();
+    let id = |stop: Option<&Stop>| stop.map(|stop| stop.id.to_string());
+    assert_eq!(
+        id(repository.stop_by_code("1001", None)).as_deref(),
+        Some("S_A1")
+    );
+    assert_eq!(repository.stops_by_code("2002").len(), 2);
+    // Ambiguous codes fall back to the first match or the one closest to the hint.
+    assert_eq!(
+        id(repository.stop_by_code("2002", None)).as_deref(),
+        Some("S_B")
+    );
+    let garden = repository.coordinate_by_stop_id("S_G").unwrap();
+    assert_eq!(
+        id(repository.stop_by_code("2002", Some(&garden))).as_deref(),
+        Some("S_G")
+    );
+    assert!(repository.stop_by_code("9999", None).is_none());
+}
+
+#[test]
+fn clone_shares_data_test() {
+    let repository = sample_repository();
+    let clone = repository.clone();
+    // Clones point at the same entity arrays instead of copying them.
+    assert!(Arc::ptr_eq(&repository.stops, &clone.stops));
+    assert!(Arc::ptr_eq(&repository.trips, &clone.trips));
+    assert!(Arc::ptr_eq(&repository.stop_times, &clone.stop_times));
+    assert!(Arc::ptr_eq(&repository.raptor_routes, &clone.raptor_routes));
+    assert!(Arc::ptr_eq(&repository.shapes, &clone.shapes));
+    assert!(Arc::ptr_eq(&repository.stop_lookup, &clone.stop_lookup));
+    assert!(Arc::ptr_eq(
+        &repository.stop_to_walk_stop,
+        &clone.stop_to_walk_stop
+    ));
+    drop(repository);
+
+    let itinerary = clone
+        .router(Location::Stop("S_A1".into()), Location::Stop("S_G".into()))
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    assert_eq!(
+        itinerary.legs.last().unwrap().to,
+        Location::Stop("S_G".into())
+    );
+}
+
+#[test]
+fn unreachable_from_test() {
+    let repository = sample_repository();
+    let ids = |stops: Vec<&Stop>| {
+        let mut ids: Vec<_> = stops.iter().map(|stop| stop.id.to_string()).collect();
+        ids.sort();
+        ids
+    };
+    let hub = Location::Stop("S_C1".into());
+
+    // The lake loop has no connection to the rest of the network, and the Blue Line
+    // only runs east so the stops before the hub can't be reached either.
+    let unreachable = repository
+        .unreachable_from(hub.clone(), Time::from_hms("07:00:00").unwrap())
+        .unwrap();
+    assert_eq!(
+        ids(unreachable),
+        ["S_A1", "S_B", "S_L1", "S_L2", "S_L3", "S_L4", "S_L5"]
+    );
+
+    // After the last departures only the hub itself and its walks are reachable.
+    let late = ids(repository
+        .unreachable_from(hub, Time::from_hms("23:00:00").unwrap())
+        .unwrap());
+    assert!(late.contains(&"S_E1".to_string()));
+    assert!(!late.contains(&"S_C1".to_string()));
+
+    assert!(matches!(
+        repository.unreachable_from(Location::Stop("MISSING".into()), Time::default()),
+        Err(raptor::Error::InvalidStopID)
+    ));
+}
+
+#[test]
+fn route_style_test() {
+    let repository = sample_repository();
+    let blue_line = repository.route_by_id("R1").unwrap();
+    let raptor_idx = repository.route_to_raptors[blue_line.index as usize][0];
+    let style = repository.route_style(raptor_idx);
+    assert_eq!(style.color.as_ref(), "0057B8");
+    assert_eq!(style.text_color.as_ref(), "FFFFFF");
+    assert_eq!(style.short_name.as_deref(), Some("1"));
+    assert_eq!(style.route_type, 3);
+
+    let lake_loop = repository.route_by_id("R4").unwrap();
+    let raptor_idx = repository.route_to_raptors[lake_loop.index as usize][0];
+    let style = repository.route_style(raptor_idx);
+    assert_eq!(style.color.as_ref(), DEFAULT_ROUTE_COLOR);
+    assert_eq!(style.text_color.as_ref(), DEFAULT_ROUTE_TEXT_COLOR);
+}
+
+#[test]
+fn boarding_area_test() {
+    let repository = sample_repository();
+    let boarding = repository.stop_by_id("S_C1_B").unwrap();
+    assert!(matches!(boarding.location_type, LocationType::Boarding));
+    let platform = repository.platform_by_stop_idx(boarding.index);
+    assert_eq!(platform.id.as_ref(), "S_C1");
+    assert!(!repository.stop_idx_is_unserved(boarding.index));
+
+    let location = Location::Stop("S_C1_B".into());
+    let stops = raptor::stops_by_location(
+        &repository,
+        &location,
+        AVERAGE_STOP_DISTANCE,
+        Default::default(),
+        None,
+    )
+    .unwrap()
+    .stops;
+    let mut ids: Vec<_> = stops.iter().map(|stop| stop.id.as_ref()).collect();
+    ids.sort();
+    assert_eq!(ids, ["S_C1", "S_C2"]);
+}
+
+#[test]
+fn stops_by_coordinate_sorted_test() {
+    let repository = sample_repository();
+    let coordinate = Coordinate {
+        latitude: 59.33,
+        longitude: 18.03,
+    };
+    let distance = Distance::from_meters(2000.0);
+    let sorted = repository.stops_by_coordinate_sorted(&coordinate, distance);
+    assert!(!sorted.is_empty());
+    assert!(sorted.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+    let mut sorted_ids: Vec<_> = sorted.iter().map(|(stop, _)| stop.index).collect();
+    let mut unsorted_ids: Vec<_> = repository
+        .stops_by_coordinate(&coordinate, distance)
+        .iter()
+        .map(|stop| stop.index)
+        .collect();
+    sorted_ids.sort();
+    unsorted_ids.sort();
+    assert_eq!(sorted_ids, unsorted_ids);
+}
+
+#[test]
+fn area_centroid_cache_test() {
+    let repository = sample_repository();
+    repository.areas.iter().for_each(|area| {
+        let cached = repository.coordinate_by_area_idx(area.index);
+        let computed = repository.centroid_by_area_idx(area.index);
+        assert_eq!(cached.latitude, computed.latitude);
+        assert_eq!(cached.longitude, computed.longitude);
+        assert_eq!(repository.coordinate_by_area_idx(area.index), cached);
+    });
+}
+
+#[test]
+fn shape_walk_distance_test() {
+    // A route looping north around a bay, the two stops are close as the crow flies.
+    let repository = sample_repository_with(|feed| {
+        feed.append(
+            "stops.txt",
+            &[
+                "S_BAY_W,Bay West,59.6,18.0,0,,,,",
+                "S_BAY_E,Bay East,59.6,18.01,0,,,,",
+            ],
+        );
+        feed.append("routes.txt", &["R_BAY,AG1,9,Bay Line,3,,,,,"]);
+        feed.append(
+            "trips.txt",
+            &["R_BAY,WEEKDAY,R_BAY_0800,Bay East,,0,SH_BAY"],
+        );
+        feed.append(
+            "stop_times.txt",
+            &[
+                "R_BAY_0800,08:00:00,08:00:00,S_BAY_W,1,,0,0,,1,,",
+                "R_BAY_0800,08:10:00,08:10:00,S_BAY_E,2,,0,0,,1,,",
+            ],
+        );
+        feed.append(
+            "shapes.txt",
+            &[
+                "SH_BAY,59.6,18.0,1,",
+                "SH_BAY,59.61,18.0,2,",
+                "SH_BAY,59.61,18.01,3,",
+                "SH_BAY,59.6,18.01,4,",
+            ],
+        );
+    });
+
+    let east = repository.stop_by_id("S_BAY_E").unwrap();
+    // Just off the western end of the bay.
+    let origin = Coordinate::new(59.6001, 18.0);
+    let straight = origin.network_distance(&east.coordinate);
+    let along = repository.shape_walk_distance(&origin, east.index);
+    let around = Coordinate::new(59.6, 18.0).euclidean_distance(&Coordinate::new(59.61, 18.0));
+    assert!(along.as_meters() > straight.as_meters() + 2.0 * around.as_meters() * 0.9);
+    // The shape itself is followed as drawn, only the snap onto it is a network estimate.
+    let shape = [(59.6, 18.0), (59.61, 18.0), (59.61, 18.01), (59.6, 18.01)]
+        .map(|(latitude, longitude)| Coordinate::new(latitude, longitude));
+    let expected = shape
+        .windows(2)
+        .map(|pair| pair[0].euclidean_distance(&pair[1]))
+        .fold(origin.network_distance(&shape[0]), |total, distance| {
+            total + distance
+        });
+    assert!(along.approx_eq(&expected, Distance::from_meters(1.0)));
+
+    // Next to the stop the detour vanishes.
+    let beside = Coordinate::new(59.6001, 18.01);
+    assert_eq!(
+        repository.shape_walk_distance(&beside, east.index),
+        beside.network_distance(&east.coordinate)
+    );
+
+    let router = || {
+        repository
+            .router(
+                Location::Coordinate(origin),
+                Location::Stop("S_BAY_E".into()),
+            )
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+            .allow_walks(false)
+    };
+    let itinerary = router().solve().unwrap();
+    let shaped = router().shape_walks(true).solve().unwrap();
+    let access_stop = itinerary.access_stop.unwrap();
+    assert_eq!(
+        itinerary.access_walk_distance,
+        origin.network_distance(&repository.stops[access_stop as usize].coordinate)
+    );
+    assert_eq!(
+        shaped.access_walk_distance,
+        repository.shape_walk_distance(&origin, access_stop)
+    );
+}
+
+#[test]
+fn area_fallback_test() {
+    let repository = sample_repository_with(|feed| {
+        feed.append(
+            "stops.txt",
+            &[
+                // Two stations without service on either side of S_B.
+                "ST_U1,Birch West,59.33,18.019,1,,,,",
+                "S_U1,Birch West,59.33,18.019,0,ST_U1,1,Z1,",
+                "ST_U2,Birch East,59.3302,18.021,1,,,,",
+                "S_U2,Birch East,59.3302,18.021,0,ST_U2,1,Z1,",
+                // A single station without service next to S_D.
+                "ST_U3,Dock Yard,59.3303,18.0605,1,,,,",
+                "S_U3,Dock Yard,59.3303,18.0605,0,ST_U3,1,Z2,",
+            ],
+        );
+        feed.append(
+            "areas.txt",
+            &["AR_BIRCH,Birch,meta_stop", "AR_DOCK,Dock,meta_stop"],
+        );
+        feed.append(
+            "stop_areas.txt",
+            &["AR_BIRCH,ST_U1", "AR_BIRCH,ST_U2", "AR_DOCK,ST_U3"],
+        );
+    });
+
+    let resolve = |id: &str| {
+        let location = Location::Area(id.into());
+        raptor::stops_by_location(
+            &repository,
+            &location,
+            AVERAGE_STOP_DISTANCE,
+            Default::default(),
+            None,
+        )
+        .unwrap()
+    };
+    assert!(!resolve("AR_CENTRAL").fallback);
+
+    let area = repository.area_by_id("AR_BIRCH").unwrap();
+    let bbox = area.bbox.unwrap();
+    let radius = area.centroid.euclidean_distance(&bbox.max);
+    let resolved = resolve("AR_BIRCH");
+    assert!(resolved.fallback);
+    let ids: Vec<_> = resolved.stops.iter().map(|stop| stop.id.as_ref()).collect();
+    assert_eq!(ids, ["S_B"]);
+    assert!(
+        resolved
+            .stops
+            .iter()
+            .all(|stop| area.centroid.euclidean_distance(&stop.coordinate) <= radius)
+    );
+
+    let itinerary = repository
+        .router(
+            Location::Area("AR_BIRCH".into()),
+            Location::Stop("S_E1".into()),
+        )
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    assert!(itinerary.area_fallback);
+
+    // A single stop area has no footprint, it falls back to the stops within a stop distance.
+    let resolved = resolve("AR_DOCK");
+    assert!(resolved.fallback);
+    let ids: Vec<_> = resolved.stops.iter().map(|stop| stop.id.as_ref()).collect();
+    assert_eq!(ids, ["S_D"]);
+}
+
+#[test]
+fn area_bounds_test() {
+    let repository = sample_repository();
+    repository.areas.iter().for_each(|area| {
+        let stops = repository.stops_by_area_idx(area.index);
+        let count = stops.len() as f32;
+        let latitude = stops
+            .iter()
+            .map(|stop| stop.coordinate.latitude)
+            .sum::<f32>()
+            / count;
+        let longitude = stops
+            .iter()
+            .map(|stop| stop.coordinate.longitude)
+            .sum::<f32>()
+            / count;
+        assert!((area.centroid.latitude - latitude).abs() < 1e-5);
+        assert!((area.centroid.longitude - longitude).abs() < 1e-5);
+
+        let bbox = area.bbox.unwrap();
+        assert!(stops.iter().all(|stop| bbox.contains(&stop.coordinate)));
+        assert!(bbox.contains(&area.centroid));
+    });
+    // Central Station spans both of its platforms.
+    let central = repository.area_by_id("AR_CENTRAL").unwrap().bbox.unwrap();
+    assert_eq!(central.min, Coordinate::new(59.33, 18.04));
+    assert_eq!(central.max, Coordinate::new(59.3305, 18.0405));
+}
+
+#[test]
+fn coordinate_by_stop_id_test() {
+    let repository = sample_repository();
+    assert_eq!(
+        repository.coordinate_by_stop_id("S_C2"),
+        Some(Coordinate::new(59.3305, 18.0405))
+    );
+    assert_eq!(repository.coordinate_by_stop_id("MISSING"), None);
+}
+
+#[test]
+fn transfers_between_test() {
+    let repository = sample_repository();
+    assert_eq!(repository.all_transfers().len(), 2);
+    let c1 = repository.stop_by_id("S_C1").unwrap().index;
+    let c2 = repository.stop_by_id("S_C2").unwrap().index;
+    let b = repository.stop_by_id("S_B").unwrap().index;
+
+    // The generated walks are listed separately, e.g. between the two platforms of ST_C.
+    let footpaths: Vec<_> = repository.all_footpaths().collect();
+    assert_eq!(
+        footpaths.len(),
+        repository
+            .stops
+            .iter()
+            .map(|stop| repository.nearby_stops_by_stop_idx(stop.index).len())
+            .sum::<usize>()
+    );
+    let (_, _, duration) = footpaths
+        .iter()
+        .find(|(from, to, _)| (*from, *to) == (c1, c2))
+        .unwrap();
+    assert_eq!(*duration, repository.walk_time_between_stops(c1, c2));
+
+    let transfer = repository.transfers_between(c1, c2).unwrap();
+    assert_eq!(transfer.from_stop_idx, c1);
+    assert_eq!(transfer.to_stop_idx, c2);
+    assert_eq!(
+        transfer.min_transfer_time,
+        Some(Duration::from_seconds(120))
+    );
+    assert!(repository.transfers_between(c2, c1).is_some());
+    assert!(repository.transfers_between(c1, b).is_none());
+}
+
+#[test]
+fn stops_for_trip_test() {
+    let repository = sample_repository();
+    let ids: Vec<_> = repository
+        .stops_for_trip("R1_X_0705")
+        .unwrap()
+        .iter()
+        .map(|stop| stop.id.as_ref())
+        .collect();
+    assert_eq!(ids, ["S_A1", "S_C1", "S_E1"]);
+
+    let timed = repository.timed_stops_for_trip("R4_0700").unwrap();
+    assert_eq!(timed.len(), 6);
+    assert!(
+        timed
+            .windows(2)
+            .all(|pair| pair[0].1.sequence < pair[1].1.sequence)
+    );
+    assert_eq!(timed[1].0.id.as_ref(), "S_L2");
+    assert_eq!(timed[4].0.id.as_ref(), "S_L2");
+    assert_eq!(timed[5].1.arrival_time, Time::from_hms("07:15:00").unwrap());
+
+    assert!(repository.stops_for_trip("MISSING").is_none());
+}
+
+#[test]
+fn headway_test() {
+    let repository = sample_repository();
+    let at = Time::from_hms("07:00:00").unwrap();
+    assert_eq!(
+        repository.headway("R2", at, Duration::from_hours(1)),
+        Some(Duration::from_minutes(15))
+    );
+    // The 07:05 express departs between the 07:00 and 07:10 locals.
+    assert_eq!(
+        repository.headway("R1", at, Duration::from_minutes(10)),
+        Some(Duration::from_minutes(5))
+    );
+    assert_eq!(repository.headway("R5", at, Duration::from_hours(1)), None);
+    assert_eq!(
+        repository.headway("MISSING", at, Duration::from_hours(1)),
+        None
+    );
+}
+
+#[test]
+fn service_days_for_route_test() {
+    let repository = sample_repository();
+    let days = repository.service_days_for_route("R1");
+    assert_eq!(days.weekdays, ServiceDays::WEEKDAYS);
+    assert!(!days.runs_on(ServiceDays::WEEKEND));
+    assert_eq!(days.added_dates, vec![20251227]);
+    assert_eq!(days.removed_dates, vec![20251225]);
+    assert_eq!(
+        repository.service_days_for_route("MISSING"),
+        ServiceDays::default()
+    );
+}
+
+#[test]
+fn is_terminal_for_route_test() {
+    let repository = sample_repository();
+    let route = repository.route_by_id("R1").unwrap();
+    let is_terminal = |stop_id: &str| {
+        let stop = repository.stop_by_id(stop_id).unwrap();
+        repository.is_terminal_for_route(stop.index, route.index)
+    };
+    assert!(is_terminal("S_A1"));
+    assert!(is_terminal("S_E1"));
+    assert!(!is_terminal("S_B"));
+    assert!(!is_terminal("S_C1"));
+    // Served by other routes only.
+    assert!(!is_terminal("S_G"));
+}
+
+#[test]
+fn walk_time_between_test() {
+    let repository = sample_repository();
+    let a = repository.stop_by_id("S_A1").unwrap();
+    let b = repository.stop_by_id("S_B").unwrap();
+    // 0.02 degrees of longitude at 59.33 N is about 1136 m, 1477 m with the circuity
+    // factor, which at 1.5 m/s takes 985 seconds.
+    let duration = repository.walk_time_between(&a.coordinate, &b.coordinate);
+    assert!(duration.as_seconds().abs_diff(985) <= 2);
+    assert_eq!(
+        repository.walk_time_between_stops(a.index, b.index),
+        duration
+    );
+    assert_eq!(
+        repository.walk_time_between(&a.coordinate, &a.coordinate),
+        Duration::from_seconds(0)
+    );
+}
+
+#[test]
+fn ids_test() {
+    let repository = sample_repository();
+    assert_eq!(repository.stop_ids().count(), repository.stops.len());
+    assert_eq!(repository.area_ids().count(), repository.areas.len());
+    assert_eq!(repository.trip_ids().count(), repository.trips.len());
+    assert_eq!(repository.route_ids().count(), repository.routes.len());
+    assert!(repository.route_ids().any(|id| id == "R4"));
+    assert!(
+        repository
+            .stop_ids()
+            .all(|id| repository.stop_by_id(id).is_some())
+    );
+}
+
+#[test]
+fn search_test() {
+    let repository = sample_repository();
+    let hits = repository.search("4 Central");
+    assert!(hits.len() <= MAX_SEARCH_HITS);
+    assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    assert!(
+        hits.iter()
+            .any(|hit| matches!(hit.kind, SearchHitKind::Route(route) if &*route.id == "R4"))
+    );
+    assert!(
+        hits.iter()
+            .any(|hit| matches!(hit.kind, SearchHitKind::Area(area) if &*area.id == "AR_CENTRAL"))
+    );
+    assert!(
+        hits.iter()
+            .any(|hit| matches!(hit.kind, SearchHitKind::Stop(stop) if &*stop.id == "S_C1"))
+    );
+}
+
+#[test]
+fn trips_by_headsign_test() {
+    let repository = sample_repository();
+    let trips = repository.trips_by_headsign("garden");
+    assert_eq!(trips.len(), 10);
+    assert!(
+        trips
+            .iter()
+            .all(|trip| trip.head_sign.as_deref() == Some("Garden Square"))
+    );
+
+    let trips = repository.trips_by_headsign("Centrl");
+    assert_eq!(trips.len(), 9);
+    assert!(trips.iter().all(|trip| trip.id.starts_with("R2_S_")));
+
+    assert!(repository.trips_by_headsign("Nowhere").is_empty());
+}
+
+#[test]
+fn stops_by_coordinate_edge_test() {
+    let repository = sample_repository();
+    let stop = repository.stop_by_id("S_B").unwrap();
+    let coordinate = Coordinate::new(59.3312, 18.0237);
+    let includes = |distance: Distance| {
+        repository
+            .stops_by_coordinate(&coordinate, distance)
+            .iter()
+            .any(|found| found.index == stop.index)
+    };
+
+    // A stop precisely at the radius is included, no matter which way the distance was computed.
+    assert!(includes(coordinate.network_distance(&stop.coordinate)));
+    assert!(includes(stop.coordinate.network_distance(&coordinate)));
+    let edge = coordinate.network_distance(&stop.coordinate);
+    assert!(includes(Distance::from_meters(edge.as_meters() - 0.005)));
+    assert!(!includes(Distance::from_meters(edge.as_meters() - 1.0)));
+}
+
+#[test]
+fn stops_by_zone_test() {
+    let repository = sample_repository();
+    let ids: Vec<_> = repository
+        .stops_by_zone("Z2")
+        .iter()
+        .map(|stop| stop.id.as_ref())
+        .collect();
+    assert_eq!(ids, ["S_D", "S_E1", "S_F", "S_G"]);
+    assert!(repository.stop_by_id("ST_C").unwrap().zone_id.is_none());
+    assert!(repository.stops_by_zone("MISSING").is_empty());
+}
+
+#[test]
+fn patterns_for_route_test() {
+    let repository = sample_repository();
+    assert_eq!(repository.patterns_for_route("R1").len(), 2);
+    assert!(repository.patterns_for_route("MISSING").is_empty());
+
+    let patterns = repository.pattern_stops_for_route("R1");
+    let ids = |stops: &[&Stop]| {
+        stops
+            .iter()
+            .map(|stop| stop.id.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&patterns[0].0), ["S_A1", "S_B", "S_C1", "S_D", "S_E1"]);
+    assert_eq!(patterns[0].1, 13);
+    assert_eq!(ids(&patterns[1].0), ["S_A1", "S_C1", "S_E1"]);
+    assert_eq!(patterns[1].1, 4);
+}
+
+#[test]
+fn check_stop_shape_alignment_test() {
+    let mut repository = sample_repository();
+    let blue_line = repository.route_by_id("R1").unwrap();
+    let raptor_idx = repository.route_to_raptors[blue_line.index as usize][0];
+    assert_eq!(repository.check_stop_shape_alignment(raptor_idx), Some(1.0));
+
+    let lake_loop = repository.route_by_id("R4").unwrap();
+    let loop_idx = repository.route_to_raptors[lake_loop.index as usize][0];
+    assert_eq!(repository.check_stop_shape_alignment(loop_idx), None);
+
+    // A reversed stop sequence never moves forward along the shape.
+    let mut stops = repository.raptor_routes[raptor_idx as usize].stops.to_vec();
+    stops.reverse();
+    Arc::make_mut(&mut repository.raptor_routes)[raptor_idx as usize].stops = stops.into();
+    assert_eq!(repository.check_stop_shape_alignment(raptor_idx), Some(0.0));
+}
+
+#[test]
+fn nearest_stop_test() {
+    let repository = sample_repository();
+    let coordinate = Coordinate::new(59.3302, 18.0198);
+    let (stop, distance) = repository.nearest_stop(&coordinate).unwrap();
+    assert_eq!(stop.id.as_ref(), "S_B");
+    assert_eq!(distance, stop.coordinate.network_distance(&coordinate));
+
+    // Far outside the network every stop is checked.
+    let (stop, _) = repository
+        .nearest_stop(&Coordinate::new(59.6, 18.0))
+        .unwrap();
+    assert!(stop.id.starts_with("S_L"));
+
+    assert!(Repository::new().nearest_stop(&coordinate).is_none());
+}
+
+#[test]
+fn search_stops_in_area_test() {
+    let repository = sample_repository();
+    let hits = repository.search_stops_in_area("AR_CENTRAL", "Station");
+    assert!(!hits.is_empty());
+    assert!(
+        hits.iter()
+            .all(|stop| stop.name.as_ref() == "Central Station")
+    );
+
+    let all = repository.search_stops_by_name("Station");
+    assert!(all.iter().any(|stop| stop.name.as_ref() == "Alpha Station"));
+
+    let hits = repository.search_stops_in_area("AR_ALPHA", "Central");
+    assert!(
+        hits.iter()
+            .all(|stop| stop.name.as_ref() == "Alpha Station")
+    );
+    assert!(
+        repository
+            .search_stops_in_area("AR_MISSING", "Station")
+            .is_empty()
+    );
+}
+
+#[test]
+fn route_test() {
+    let repository = sample_repository();
+    let departure = Time::from_hms("07:00:00").unwrap();
+    let options = RouteOptions {
+        time_constraint: Some(raptor::TimeConstraint::Departure(departure)),
+        ..Default::default()
+    };
+    let itinerary = repository
+        .route(
+            Location::Area("AR_ALPHA".into()),
+            Location::Area("AR_EAST".into()),
+            options,
+        )
+        .unwrap();
+    let chained = repository
+        .router(
+            Location::Area("AR_ALPHA".into()),
+            Location::Area("AR_EAST".into()),
+        )
+        .departure_at(departure)
+        .solve()
+        .unwrap();
+    assert_eq!(itinerary.from, chained.from);
+    assert_eq!(itinerary.to, chained.to);
+    assert_eq!(itinerary.legs.len(), chained.legs.len());
+    assert_eq!(
+        itinerary.legs.last().unwrap().arrival_time,
+        chained.legs.last().unwrap().arrival_time
+    );
+
+    let options = RouteOptions {
+        max_journey_duration: Some(Duration::from_minutes(1)),
+        ..options
+    };
+    assert!(matches!(
+        repository.route(
+            Location::Area("AR_ALPHA".into()),
+            Location::Area("AR_EAST".into()),
+            options,
+        ),
+        Err(raptor::Error::NoRouteWithinTimeBudget)
+    ));
+}
+
+#[test]
+fn departures_per_hour_test() {
+    let repository = sample_repository();
+    let stop = repository.stop_by_id("S_C1").unwrap();
+    let hours = repository.departures_per_hour(stop.index);
+    // Six locals and two expresses an hour, then the last local at 09:06.
+    assert_eq!(hours[7], 8);
+    assert_eq!(hours[8], 8);
+    assert_eq!(hours[9], 1);
+    assert_eq!(hours.iter().sum::<u32>(), 17);
+
+    // Nothing departs from the end of the line.
+    let terminal = repository.stop_by_id("S_E1").unwrap();
+    assert_eq!(repository.departures_per_hour(terminal.index), [0; 24]);
+
+    let heatmap = repository.departures_per_hour_heatmap();
+    assert_eq!(heatmap.len(), repository.stops.len());
+    assert_eq!(heatmap[stop.index as usize], hours);
+
+    // The loop calls twice at S_L2, each call is one departure.
+    let stop = repository.stop_by_id("S_L2").unwrap();
+    let hours = repository.departures_per_hour(stop.index);
+    assert_eq!(hours[7], 4);
+    assert_eq!(hours[8], 2);
+    assert_eq!(hours.iter().sum::<u32>(), 6);
+}
+
+#[test]
+fn iter_stop_times_by_route_idx_test() {
+    let repository = sample_repository();
+    for route in repository.routes.iter() {
+        let nested = repository.stop_times_by_route_idx(route.index);
+        let iterated: Vec<_> = repository
+            .iter_stop_times_by_route_idx(route.index)
+            .collect();
+        let parallel: Vec<_> = repository
+            .par_iter_stop_times_by_route_idx(route.index)
+            .collect();
+        assert_eq!(iterated.len(), nested.len());
+        assert_eq!(parallel.len(), nested.len());
+        for (((trip_idx, stop_times), (par_trip_idx, par_stop_times)), expected) in
+            iterated.iter().zip(&parallel).zip(&nested)
+        {
+            assert_eq!(trip_idx, par_trip_idx);
+            assert!(std::ptr::eq(*stop_times, *expected));
+            assert!(std::ptr::eq(*par_stop_times, *expected));
+            assert!(
+                stop_times
+                    .iter()
+                    .all(|stop_time| stop_time.trip_idx == *trip_idx)
+            );
+        }
+    }
+}
+
+#[test]
+fn one_seat_rides_test() {
+    let repository = sample_repository();
+    let stop_idx = |id: &str| repository.stop_by_id(id).unwrap().index;
+    let time = |hms: &str| Time::from_hms(hms).unwrap();
+    let trip_id = |trip_idx: u32| repository.trips[trip_idx as usize].id.to_string();
+
+    let rides = repository.one_seat_rides(stop_idx("S_A1"), stop_idx("S_E1"), time("07:00:00"));
+    let first: Vec<_> = rides
+        .iter()
+        .take(3)
+        .map(|(trip_idx, departure, arrival)| (trip_id(*trip_idx), *departure, *arrival))
+        .collect();
+    assert_eq!(
+        first,
+        vec![
+            ("R1_L_0700".into(), time("07:00:00"), time("07:12:00")),
+            ("R1_X_0705".into(), time("07:05:00"), time("07:13:00")),
+            ("R1_L_0710".into(), time("07:10:00"), time("07:22:00")),
+        ]
+    );
+    assert!(rides.windows(2).all(|pair| pair[0].2 <= pair[1].2));
+
+    // Reaching the rail line takes a transfer, and the bus never runs back west.
+    assert!(
+        repository
+            .one_seat_rides(stop_idx("S_A1"), stop_idx("S_G"), time("07:00:00"))
+            .is_empty()
+    );
+    assert!(
+        repository
+            .one_seat_rides(stop_idx("S_E1"), stop_idx("S_A1"), time("07:00:00"))
+            .is_empty()
+    );
+}
+
+#[test]
+fn validate_raptor_routes_test() {
+    let mut repository = sample_repository();
+    assert_eq!(repository.validate_raptor_routes(), Ok(()));
+
+    // Group an express trip with the locals, bypassing the grouping by stop sequence.
+    let express = repository.trip_by_id("R1_X_0705").unwrap().index;
+    let local = repository.trip_by_id("R1_L_0700").unwrap().raptor_route_idx;
+    let raptor = &mut Arc::make_mut(&mut repository.raptor_routes)[local as usize];
+    raptor.trips = raptor.trips.iter().copied().chain([express]).collect();
+    assert_eq!(
+        repository.validate_raptor_routes(),
+        Err(RaptorRouteError::LengthMismatch {
+            route_id: "R1".into(),
+            trip_id: "R1_X_0705".into(),
+            expected: 5,
+            found: 3,
+        })
+    );
+
+    // Same length but a different stop.
+    let mut repository = sample_repository();
+    let raptor = &mut Arc::make_mut(&mut repository.raptor_routes)[local as usize];
+    let mut stops = raptor.stops.to_vec();
+    stops.swap(1, 2);
+    raptor.stops = stops.into();
+    assert!(matches!(
+        repository.validate_raptor_routes(),
+        Err(RaptorRouteError::StopMismatch { route_id, .. }) if &*route_id == "R1"
+    ));
+}
+
+#[test]
+fn by_idx_test() {
+    let repository = sample_repository();
+    let stop = repository.stop_by_id("S_B").unwrap();
+    assert_eq!(repository.stop_by_idx(stop.index).unwrap().id, stop.id);
+    let area = repository.area_by_id("AR_EAST").unwrap();
+    assert_eq!(repository.area_by_idx(area.index).unwrap().id, area.id);
+    let trip = repository.trip_by_id("R1_L_0700").unwrap();
+    assert_eq!(repository.trip_by_idx(trip.index).unwrap().id, trip.id);
+    let route = repository.route_by_id("R2").unwrap();
+    assert_eq!(repository.route_by_idx(route.index).unwrap().id, route.id);
+
+    assert!(
+        repository
+            .stop_by_idx(repository.stops.len() as u32)
+            .is_none()
+    );
+    assert!(
+        repository
+            .area_by_idx(repository.areas.len() as u32)
+            .is_none()
+    );
+    assert!(
+        repository
+            .trip_by_idx(repository.trips.len() as u32)
+            .is_none()
+    );
+    assert!(repository.route_by_idx(u32::MAX).is_none());
+}
+
+#[test]
+fn stops_within_walk_time_test() {
+    let repository = sample_repository();
+    // Halfway between S_A1 and S_B, both about 740 meters away along the network.
+    let origin = Coordinate::new(59.33, 18.01);
+    let max = Duration::from_minutes(10);
+    let stops = repository.stops_within_walk_time(&origin, max);
+    let ids: Vec<_> = stops.iter().map(|(stop, _)| &*stop.id).collect();
+    assert!(ids.contains(&"S_A1"));
+    assert!(ids.contains(&"S_B"));
+    assert!(!ids.contains(&"S_C1"));
+    assert!(stops.iter().all(|(stop, duration)| {
+        *duration <= max && stop.coordinate.network_distance(&origin).as_meters() <= 900.0
+    }));
+    assert!(stops.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+    assert!(
+        repository
+            .stops_within_walk_time(&origin, Duration::from_minutes(5))
+            .is_empty()
+    );
+}
+
+#[test]
+fn area_alias_test() {
+    let repository = sample_repository()
+        .with_area_alias("FEED_B:CENTRAL", "AR_CENTRAL")
+        .with_area_alias("FEED_C:CENTRAL", "FEED_B:CENTRAL")
+        .with_area_alias("LOOP_A", "LOOP_B")
+        .with_area_alias("LOOP_B", "LOOP_A");
+
+    let central = repository.resolve_area("AR_CENTRAL").unwrap();
+    let alias = repository.resolve_area("FEED_B:CENTRAL").unwrap();
+    assert_eq!(alias.index, central.index);
+    assert_eq!(alias.id, central.id);
+    assert_eq!(
+        repository.resolve_area("FEED_C:CENTRAL").unwrap().index,
+        central.index
+    );
+    assert_eq!(
+        repository.area_by_id("FEED_B:CENTRAL").unwrap().index,
+        central.index
+    );
+    assert_eq!(
+        repository
+            .search_stops_in_area("FEED_B:CENTRAL", "Central")
+            .len(),
+        repository
+            .search_stops_in_area("AR_CENTRAL", "Central")
+            .len()
+    );
+    assert!(repository.resolve_area("LOOP_A").is_none());
+    assert!(repository.resolve_area("MISSING").is_none());
+}
+
+#[test]
+fn pattern_kind_test() {
+    let repository = sample_repository();
+    let trip_raptor = |trip_id: &str| {
+        let trip = repository.trip_by_id(trip_id).unwrap();
+        repository
+            .raptor_routes
+            .iter()
+            .find(|raptor| raptor.trips.contains(&trip.index))
+            .unwrap()
+            .index
+    };
+    let local = trip_raptor("R1_L_0700");
+    let express = trip_raptor("R1_X_0705");
+    assert_ne!(local, express);
+    assert_eq!(repository.pattern_kind(local), PatternKind::Local);
+    assert_eq!(repository.pattern_kind(express), PatternKind::Limited);
+    // The two directions of the rail line serve the same stops and are neither limited.
+    assert_eq!(
+        repository.pattern_kind(trip_raptor("R2_N_0702")),
+        PatternKind::Local
+    );
+    assert_eq!(
+        repository.pattern_kind(trip_raptor("R2_S_0710")),
+        PatternKind::Local
+    );
 }
@@ -0,0 +1,74 @@
+//! Framing for binary [`Repository`](super::Repository) snapshots.
+//!
+//! Every snapshot starts with [`MAGIC`] followed by [`VERSION`] as a little endian `u32`.
+//! The version must be bumped whenever the layout of the repository changes, so snapshots
+//! written by an older crate are rejected instead of being read back as garbage.
+
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// Marks a file as a blaise repository snapshot.
+pub const MAGIC: [u8; 8] = *b"BLAISE\0\0";
+/// The snapshot layout written by this version of the crate.
+pub const VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Not a repository snapshot")]
+    InvalidMagic,
+    #[error("Incompatible snapshot version {found}, expected {expected}")]
+    IncompatibleSnapshot { found: u32, expected: u32 },
+}
+
+/// Writes the magic bytes and the current [`VERSION`].
+pub fn write_header(writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())
+}
+
+/// Reads and validates a header written by [`write_header`], leaving `reader` at the
+/// start of the snapshot body.
+pub fn read_header(reader: &mut impl Read) -> Result<(), Error> {
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(Error::InvalidMagic);
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let found = u32::from_le_bytes(version);
+    if found != VERSION {
+        return Err(Error::IncompatibleSnapshot {
+            found,
+            expected: VERSION,
+        });
+    }
+    Ok(())
+}
+
+#[test]
+fn header_round_trip_test() {
+    let mut bytes = vec![];
+    write_header(&mut bytes).unwrap();
+    bytes.extend_from_slice(b"body");
+    let mut reader = bytes.as_slice();
+    read_header(&mut reader).unwrap();
+    assert_eq!(reader, b"body");
+}
+
+#[test]
+fn incompatible_version_test() {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&(VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        read_header(&mut bytes.as_slice()),
+        Err(Error::IncompatibleSnapshot { found, expected })
+            if found == VERSION + 1 && expected == VERSION
+    ));
+    assert!(matches!(
+        read_header(&mut b"not a snapshot".as_slice()),
+        Err(Error::InvalidMagic)
+    ));
+}
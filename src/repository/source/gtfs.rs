@@ -1,28 +1,77 @@
 use crate::{
     gtfs::{self, GtfsReader},
-    raptor::get_departure_time,
+    raptor::{get_departure_time, transfer_duration},
     repository::{
-        Area, Cell, RaptorRoute, Repository, Route, Slice, Stop, StopTime, Transfer, Trip,
+        Area, Cell, LoadCounts, LoadStage, LocationType, RaptorRoute, Repository, Route, Service,
+        ServiceDays, Slice, Stop, StopTime, Transfer, Trip,
+    },
+    shared::{
+        AVERAGE_STOP_DISTANCE, BoundingBox, Coordinate, Distance,
+        time::{self, Duration, Time},
     },
-    shared::{AVERAGE_STOP_DISTANCE, Coordinate, Distance, time::Duration},
 };
 use rayon::prelude::*;
 use std::{collections::HashMap, sync::Arc, time::Instant};
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// The longest `min_transfer_time` taken from a feed, longer times are clamped to it.
+pub const MAX_TRANSFER_TIME: Duration = Duration::from_hours(1);
 
 impl Repository {
-    pub fn load_gtfs(mut self, mut gtfs: GtfsReader) -> Result<Self, gtfs::Error> {
+    pub fn load_gtfs(self, gtfs: GtfsReader) -> Result<Self, gtfs::Error> {
+        self.load_gtfs_with_progress(gtfs, |_, _| {})
+    }
+
+    /// Same as [`Repository::load_gtfs`] but calls `progress` with every [`LoadStage`]
+    /// once it's done, together with how long the stage took.
+    pub fn load_gtfs_with_progress(
+        mut self,
+        mut gtfs: GtfsReader,
+        mut progress: impl FnMut(LoadStage, std::time::Duration),
+    ) -> Result<Self, gtfs::Error> {
+        let mut now = Instant::now();
+        let mut stage_durations = Vec::with_capacity(LoadStage::ALL.len());
+        let mut done = |stage: LoadStage| {
+            let elapsed = now.elapsed();
+            stage_durations.push((stage, elapsed));
+            progress(stage, elapsed);
+            now = Instant::now();
+        };
         self.load_stops(&mut gtfs)?;
+        if let Some(tolerance) = gtfs.config().dedupe_colocated_stops {
+            self.dedupe_colocated_stops(tolerance);
+        }
+        done(LoadStage::Stops);
         self.load_areas(&mut gtfs)?;
         self.load_area_to_stops(&mut gtfs)?;
-        let shapes_lookup = self.load_shapes(&mut gtfs)?;
+        done(LoadStage::Areas);
         self.load_routes(&mut gtfs)?;
+        done(LoadStage::Routes);
+        self.load_services(&mut gtfs)?;
+        let shapes_lookup = self.load_shapes(&mut gtfs)?;
         let trip_to_shape_slice = self.load_trips(&mut gtfs, shapes_lookup)?;
+        done(LoadStage::Trips);
         self.load_transfers(&mut gtfs)?;
+        done(LoadStage::Transfers);
         self.load_stop_times(&mut gtfs)?;
-        self.generate_geo_hash();
-        self.generate_raptor_routes(trip_to_shape_slice);
+        done(LoadStage::StopTimes);
+        self.generate_geo_hash(gtfs.config().exclude_unserved_stops);
+        done(LoadStage::GeoHash);
+        self.generate_raptor_routes(trip_to_shape_slice, gtfs.config().deterministic);
+        done(LoadStage::Raptor);
         self.generate_walks();
+        done(LoadStage::Walks);
+        self.load_report.stage_durations = stage_durations;
+        self.load_report.loaded = LoadCounts {
+            stops: self.stops.len(),
+            areas: self.areas.len(),
+            routes: self.routes.len(),
+            services: self.services.len(),
+            shape_points: self.shapes.len(),
+            trips: self.trips.len(),
+            transfers: self.transfers.len(),
+            stop_times: self.stop_times.len(),
+        };
         Ok(self)
     }
 
@@ -30,15 +79,27 @@ impl Repository {
         debug!("Loading stops...");
         let now = Instant::now();
         let mut stop_lookup: HashMap<Arc<str>, u32> = HashMap::new();
+        let mut stop_code_lookup: HashMap<Arc<str>, Vec<u32>> = HashMap::new();
         let mut stops: Vec<(Stop, Option<String>)> = Vec::new();
-        gtfs.stream_stops(|(i, mut stop)| {
-            let parent_station = stop.parent_station.take();
+        gtfs.stream_stops(|(i, stop)| {
+            let parent_station = stop.parent_station.clone();
             let mut value: Stop = stop.into();
             value.index = i as u32;
             stop_lookup.insert(value.id.clone(), i as u32);
+            if let Some(code) = &value.code {
+                stop_code_lookup
+                    .entry(code.clone())
+                    .or_default()
+                    .push(i as u32);
+            }
             stops.push((value, parent_station));
         })?;
-        self.stop_lookup = stop_lookup;
+        self.stop_lookup = stop_lookup.into();
+        let stop_code_lookup: HashMap<Arc<str>, Box<[u32]>> = stop_code_lookup
+            .into_iter()
+            .map(|(code, stop_indices)| (code, stop_indices.into()))
+            .collect();
+        self.stop_code_lookup = stop_code_lookup.into();
 
         let mut station_to_stops: Vec<Vec<u32>> = vec![Vec::new(); stops.len()];
         stops
@@ -67,6 +128,90 @@ impl Repository {
         Ok(())
     }
 
+    /// Merges regular stops sharing a parent that are within `tolerance` of each other
+    /// into the first one listed. Has to run before anything referencing stops is loaded,
+    /// the merged ids are kept in the lookup so later files resolve to the kept stop.
+    fn dedupe_colocated_stops(&mut self, tolerance: Distance) {
+        debug!("Deduping colocated stops...");
+        let now = Instant::now();
+        let mut cells: HashMap<Cell, Vec<u32>> = HashMap::new();
+        let mut source_to_stop: Vec<u32> = Vec::with_capacity(self.stops.len());
+        let mut stops: Vec<Stop> = Vec::with_capacity(self.stops.len());
+        for stop in self.stops.iter() {
+            let mergeable = matches!(
+                stop.location_type,
+                LocationType::Stop | LocationType::Platform { .. }
+            );
+            let (x, y) = stop.coordinate.to_cell();
+            let existing = (x - 1..=x + 1)
+                .flat_map(|x| (y - 1..=y + 1).map(move |y| (x, y)))
+                .filter(|_| mergeable)
+                .filter_map(|cell| cells.get(&cell))
+                .flatten()
+                .copied()
+                .find(|stop_idx| {
+                    let other = &stops[*stop_idx as usize];
+                    other.parent_index == stop.parent_index
+                        && other.coordinate.euclidean_distance(&stop.coordinate) <= tolerance
+                });
+            if let Some(stop_idx) = existing {
+                Arc::make_mut(&mut self.stop_lookup).insert(stop.id.clone(), stop_idx);
+                source_to_stop.push(stop_idx);
+                continue;
+            }
+
+            let stop_idx = stops.len() as u32;
+            if mergeable {
+                cells
+                    .entry(stop.coordinate.to_cell())
+                    .or_default()
+                    .push(stop_idx);
+            }
+            Arc::make_mut(&mut self.stop_lookup).insert(stop.id.clone(), stop_idx);
+            source_to_stop.push(stop_idx);
+            stops.push(Stop {
+                index: stop_idx,
+                ..stop.clone()
+            });
+        }
+
+        let merged = self.stops.len() - stops.len();
+        let mut station_to_stops: Vec<Vec<u32>> = vec![Vec::new(); stops.len()];
+        for stop in stops.iter_mut() {
+            stop.parent_index = stop
+                .parent_index
+                .map(|parent_idx| source_to_stop[parent_idx as usize]);
+            if let Some(parent_idx) = stop.parent_index {
+                station_to_stops[parent_idx as usize].push(stop.index);
+            }
+        }
+        self.stops = stops.into();
+        self.station_to_stops = station_to_stops
+            .into_iter()
+            .map(|stops| stops.into())
+            .collect();
+        // Codes of merged stops keep resolving, to the stop they were merged into.
+        let stop_code_lookup: HashMap<Arc<str>, Box<[u32]>> = self
+            .stop_code_lookup
+            .iter()
+            .map(|(code, stop_indices)| {
+                let mut stop_indices: Vec<u32> = stop_indices
+                    .iter()
+                    .map(|source_idx| source_to_stop[*source_idx as usize])
+                    .collect();
+                stop_indices.sort_unstable();
+                stop_indices.dedup();
+                (code.clone(), stop_indices.into())
+            })
+            .collect();
+        self.stop_code_lookup = stop_code_lookup.into();
+        self.source_to_stop = source_to_stop.into();
+        debug!(
+            "Deduping colocated stops merged {merged} stops and took {:?}",
+            now.elapsed()
+        );
+    }
+
     fn load_areas(&mut self, gtfs: &mut GtfsReader) -> Result<(), gtfs::Error> {
         debug!("Loading areas...");
         let now = Instant::now();
@@ -79,7 +224,7 @@ impl Repository {
             areas.push(value);
         })?;
         self.areas = areas.into();
-        self.area_lookup = area_lookup;
+        self.area_lookup = area_lookup.into();
         debug!("Loading areas took {:?}", now.elapsed());
         Ok(())
     }
@@ -90,23 +235,127 @@ impl Repository {
 
         let mut area_to_stops: Vec<Vec<u32>> = vec![Vec::new(); self.areas.len()];
         let mut stop_to_area: Vec<Option<u32>> = vec![None; self.stops.len()];
+        let mut skipped_stop_areas = 0;
         gtfs.stream_stop_areas(|(_, value)| {
-            // TEMP
-            let stop_idx = self.stop_lookup.get(value.stop_id.as_str()).unwrap();
-            // TEMP
-            let area_idx = self.area_lookup.get(value.area_id.as_str()).unwrap();
+            let (Some(stop_idx), Some(area_idx)) = (
+                self.stop_lookup.get(value.stop_id.as_str()),
+                self.area_lookup.get(value.area_id.as_str()),
+            ) else {
+                skipped_stop_areas += 1;
+                return;
+            };
 
             stop_to_area[*stop_idx as usize] = Some(*area_idx);
             area_to_stops[*area_idx as usize].push(*stop_idx);
         })?;
+        if skipped_stop_areas > 0 {
+            warn!("Skipped {skipped_stop_areas} stop areas referencing an unknown area or stop");
+        }
+        self.load_report.skipped_stop_areas = skipped_stop_areas;
         self.stop_to_area = stop_to_area.into();
         let area_to_stops: Box<[Box<[u32]>]> =
             area_to_stops.into_iter().map(|val| val.into()).collect();
-        self.area_to_stops = area_to_stops;
+        self.area_to_stops = area_to_stops.into();
+        let bounds: Vec<_> = (0..self.areas.len() as u32)
+            .map(|area_idx| {
+                let coordinates = self
+                    .stops_by_area_idx(area_idx)
+                    .into_iter()
+                    .map(|stop| stop.coordinate);
+                (
+                    self.centroid_by_area_idx(area_idx),
+                    BoundingBox::from_coordinates(coordinates),
+                )
+            })
+            .collect();
+        Arc::make_mut(&mut self.areas)
+            .iter_mut()
+            .zip(bounds)
+            .for_each(|(area, (centroid, bbox))| {
+                area.centroid = centroid;
+                area.bbox = bbox;
+            });
         debug!("Loading area to stops took {:?}", now.elapsed());
         Ok(())
     }
 
+    /// Loads `calendar.txt` and `calendar_dates.txt`. Both files are optional in GTFS,
+    /// a missing file is treated as empty.
+    fn load_services(&mut self, gtfs: &mut GtfsReader) -> Result<(), gtfs::Error> {
+        debug!("Loading services...");
+        let now = Instant::now();
+        let mut service_lookup: HashMap<Arc<str>, u32> = HashMap::new();
+        let mut services: Vec<Service> = Vec::new();
+        let result = gtfs.stream_calendar(|(i, calendar)| {
+            let weekdays = [
+                calendar.monday,
+                calendar.tuesday,
+                calendar.wednesday,
+                calendar.thursday,
+                calendar.friday,
+                calendar.saturday,
+                calendar.sunday,
+            ]
+            .into_iter()
+            .enumerate()
+            .filter(|(_, runs)| *runs == 1)
+            .fold(0, |mask, (day, _)| mask | 1 << day);
+            let value = Service {
+                index: i as u32,
+                id: calendar.service_id.into(),
+                days: ServiceDays {
+                    weekdays,
+                    ..Default::default()
+                },
+                start_date: Some(calendar.start_date),
+                end_date: Some(calendar.end_date),
+            };
+            service_lookup.insert(value.id.clone(), i as u32);
+            services.push(value);
+        });
+        match result {
+            Err(err) if !err.is_missing_file() => return Err(err),
+            _ => {}
+        }
+
+        let result = gtfs.stream_calendar_dates(|(_, date)| {
+            let service_idx = *service_lookup
+                .entry(date.service_id.as_str().into())
+                .or_insert_with_key(|id| {
+                    services.push(Service {
+                        index: services.len() as u32,
+                        id: id.clone(),
+                        ..Default::default()
+                    });
+                    services.len() as u32 - 1
+                });
+            let days = &mut services[service_idx as usize].days;
+            match date.exception_type {
+                1 => days.added_dates.push(date.date),
+                2 => days.removed_dates.push(date.date),
+                _ => warn!(
+                    "Unknown exception type {} for service {}",
+                    date.exception_type, date.service_id
+                ),
+            }
+        });
+        match result {
+            Err(err) if !err.is_missing_file() => return Err(err),
+            _ => {}
+        }
+
+        for service in services.iter_mut() {
+            service.days.added_dates.sort_unstable();
+            service.days.added_dates.dedup();
+            service.days.removed_dates.sort_unstable();
+            service.days.removed_dates.dedup();
+        }
+        self.services = services.into();
+        self.service_lookup = service_lookup.into();
+        debug!("Loading services took {:?}", now.elapsed());
+        Ok(())
+    }
+
     fn load_shapes(
         &mut self,
         gtfs: &mut GtfsReader,
@@ -115,13 +364,16 @@ impl Repository {
 
         debug!("Loading shapes...");
         let now = Instant::now();
+        let unit = gtfs.config().shape_dist_unit;
         let mut shapes: HashMap<String, Vec<Shape>> = HashMap::new();
         gtfs.stream_shapes(|(_, shape)| {
             let value = Shape {
                 index: u32::MAX,
                 coordinate: Coordinate::new(shape.shape_pt_lat, shape.shape_pt_lon),
                 sequence: shape.shape_pt_sequence,
-                distance_traveled: shape.shape_dist_traveled.map(Distance::from_meters),
+                distance_traveled: shape
+                    .shape_dist_traveled
+                    .map(|value| unit.to_distance(value)),
                 slice: Slice {
                     start_idx: u32::MAX,
                     count: u32::MAX,
@@ -131,6 +383,11 @@ impl Repository {
             shapes.entry(shape.shape_id).or_default().push(value);
         })?;
 
+        let mut shapes: Vec<_> = shapes.into_iter().collect();
+        if gtfs.config().deterministic {
+            shapes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+
         let mut idx = 0;
         let mut shapes_lookup: HashMap<String, Slice> = HashMap::new();
         let shapes: Vec<_> = shapes
@@ -170,7 +427,7 @@ impl Repository {
             routes.push(value);
         })?;
         self.routes = routes.into();
-        self.route_lookup = route_lookup;
+        self.route_lookup = route_lookup.into();
         debug!("Loading routes took {:?}", now.elapsed());
         Ok(())
     }
@@ -188,13 +445,19 @@ impl Repository {
         let mut route_to_trips: Vec<Vec<u32>> = vec![Vec::new(); self.routes.len()];
         let mut trip_to_route: Vec<u32> = Vec::new();
         let mut trips: Vec<Trip> = Vec::new();
-        gtfs.stream_trips(|(i, trip)| {
+        let mut skipped_trips = 0;
+        gtfs.stream_trips(|(_, trip)| {
+            let Some(route_index) = self.route_lookup.get(trip.route_id.as_str()) else {
+                skipped_trips += 1;
+                return;
+            };
+            let i = trips.len();
             let shape_slice = trip
                 .shape_id
                 .and_then(|shape_id| shapes_lookup.get(&shape_id))
                 .copied();
             trip_to_shapes_slice.push(shape_slice);
-            let route_index = self.route_lookup.get(trip.route_id.as_str()).unwrap();
+            let service_idx = self.service_lookup.get(trip.service_id.as_str()).copied();
             let value = Trip {
                 index: i as u32,
                 id: trip.trip_id.into(),
@@ -202,18 +465,24 @@ impl Repository {
                 raptor_route_idx: 0,
                 head_sign: trip.trip_headsign.map(|val| val.into()),
                 short_name: trip.trip_short_name.map(|val| val.into()),
+                direction_id: trip.direction_id,
+                service_idx,
             };
             route_to_trips[*route_index as usize].push(i as u32);
             trip_to_route.push(*route_index);
             trip_lookup.insert(value.id.clone(), i as u32);
             trips.push(value);
         })?;
+        if skipped_trips > 0 {
+            warn!("Skipped {skipped_trips} trips referencing an unknown route");
+        }
+        self.load_report.skipped_trips = skipped_trips;
         self.trips = trips.into();
-        self.trip_lookup = trip_lookup;
+        self.trip_lookup = trip_lookup.into();
         self.trip_to_route = trip_to_route.into();
         let route_to_trips: Box<[Box<[u32]>]> =
             route_to_trips.into_iter().map(|val| val.into()).collect();
-        self.route_to_trips = route_to_trips;
+        self.route_to_trips = route_to_trips.into();
         debug!("Loading trips took {:?}", now.elapsed());
         Ok(trip_to_shapes_slice)
     }
@@ -223,41 +492,68 @@ impl Repository {
         let now = Instant::now();
         let mut transfers: Vec<Transfer> = Vec::new();
         let mut stop_to_transfers: Vec<Vec<u32>> = vec![Vec::new(); self.stops.len()];
-        gtfs.stream_transfers(|(i, transfer)| {
-            let from_stop_idx = *self
-                .stop_lookup
-                .get(transfer.from_stop_id.as_str())
-                .unwrap();
-
-            let to_stop_idx = *self.stop_lookup.get(transfer.to_stop_id.as_str()).unwrap();
-
-            let from_trip_idx = if let Some(trip_id) = transfer.from_trip_id {
-                let trip_idx = *self.trip_lookup.get(trip_id.as_str()).unwrap();
-                Some(trip_idx)
-            } else {
-                None
+        let mut skipped_self_transfers = 0;
+        let mut clamped_transfer_times = 0;
+        let mut skipped_transfers = 0;
+        gtfs.stream_transfers(|(_, transfer)| {
+            let trip_idx = |trip_id: &Option<String>| match trip_id {
+                Some(trip_id) => self.trip_lookup.get(trip_id.as_str()).map(|idx| Some(*idx)),
+                None => Some(None),
             };
-
-            let to_trip_idx = if let Some(trip_id) = transfer.to_trip_id {
-                let trip_idx = *self.trip_lookup.get(trip_id.as_str()).unwrap();
-                Some(trip_idx)
-            } else {
-                None
+            let (Some(from_stop_idx), Some(to_stop_idx), Some(from_trip_idx), Some(to_trip_idx)) = (
+                self.stop_lookup
+                    .get(transfer.from_stop_id.as_str())
+                    .copied(),
+                self.stop_lookup.get(transfer.to_stop_id.as_str()).copied(),
+                trip_idx(&transfer.from_trip_id),
+                trip_idx(&transfer.to_trip_id),
+            ) else {
+                skipped_transfers += 1;
+                return;
             };
+            // A transfer to the same stop is a zero length edge the router could loop on.
+            if from_stop_idx == to_stop_idx {
+                skipped_self_transfers += 1;
+                return;
+            }
 
-            stop_to_transfers[from_stop_idx as usize].push(i as u32);
+            let min_transfer_time = transfer.min_transfer_time.map(|seconds| {
+                let clamped = seconds.clamp(0, MAX_TRANSFER_TIME.as_seconds() as i64);
+                if clamped != seconds {
+                    clamped_transfer_times += 1;
+                }
+                Duration::from_seconds(clamped as u32)
+            });
+
+            stop_to_transfers[from_stop_idx as usize].push(transfers.len() as u32);
 
             let value = Transfer {
                 from_stop_idx,
                 to_stop_idx,
                 from_trip_idx,
                 to_trip_idx,
-                min_transfer_time: transfer.min_transfer_time.map(Duration::from_seconds),
+                min_transfer_time,
             };
 
             transfers.push(value);
         })?;
+        if skipped_transfers > 0 {
+            warn!("Skipped {skipped_transfers} transfers referencing an unknown stop or trip");
+        }
+        self.load_report.skipped_transfers = skipped_transfers;
+        if skipped_self_transfers > 0 || clamped_transfer_times > 0 {
+            warn!(
+                "Skipped {skipped_self_transfers} self transfers and clamped {clamped_transfer_times} transfer times"
+            );
+        }
+        self.load_report.skipped_self_transfers = skipped_self_transfers;
+        self.load_report.clamped_transfer_times = clamped_transfer_times;
         self.transfers = transfers.into();
+        self.transfer_durations = self
+            .transfers
+            .par_iter()
+            .map(|transfer| transfer_duration(self, transfer))
+            .collect();
         self.stop_to_transfers = stop_to_transfers
             .into_iter()
             .map(|val| val.into())
@@ -275,9 +571,27 @@ impl Repository {
         let mut last_trip: Option<&Trip> = None;
         let mut start_idx = 0;
         let mut buffer: Vec<StopTime> = vec![];
-        gtfs.stream_stop_times(|(i, stop_time)| {
-            // TEMP
-            let trip_idx = self.trip_lookup.get(stop_time.trip_id.as_str()).unwrap();
+        let mut skipped_stop_times = 0;
+        let mut malformed_stop_times = 0;
+        let unit = gtfs.config().shape_dist_unit;
+        gtfs.stream_stop_times(|(_, stop_time)| {
+            // Partial feeds often contain dangling references, skip those rows instead of failing.
+            let (Some(trip_idx), Some(stop_idx)) = (
+                self.trip_lookup.get(stop_time.trip_id.as_str()),
+                self.stop_lookup.get(stop_time.stop_id.as_str()),
+            ) else {
+                skipped_stop_times += 1;
+                return;
+            };
+            // Guessing a time for a garbled one would hide the defect, unlike an empty one.
+            if stop_time.has_malformed_time() {
+                debug!(
+                    "Skipping stop time of trip {} with malformed times {:?} and {:?}",
+                    stop_time.trip_id, stop_time.arrival_time, stop_time.departure_time
+                );
+                malformed_stop_times += 1;
+                return;
+            }
             let trip = &self.trips[*trip_idx as usize];
 
             if last_trip.is_none() {
@@ -293,6 +607,7 @@ impl Repository {
                 };
 
                 buffer.par_sort_by_key(|val| val.sequence);
+                interpolate_stop_times(&mut buffer);
                 buffer.iter_mut().enumerate().for_each(|(j, st)| {
                     st.inner_idx = j as u32;
                     st.slice = stop_time_slice;
@@ -301,13 +616,12 @@ impl Repository {
                 trip_to_stop_times_slice[ct.index as usize] = stop_time_slice;
                 stop_times.append(&mut buffer);
                 last_trip = Some(trip);
-                start_idx = i;
+                start_idx = stop_times.len();
             }
 
-            // TEMP
-            let stop_idx = self.stop_lookup.get(stop_time.stop_id.as_str()).unwrap();
-
+            let distance_traveled = stop_time.shape_dist_traveled;
             let mut value: StopTime = stop_time.into();
+            value.distance_traveled = distance_traveled.map(|value| unit.to_distance(value));
             value.trip_idx = *trip_idx;
             value.stop_idx = *stop_idx;
             buffer.push(value);
@@ -322,6 +636,7 @@ impl Repository {
                 count: buffer.len() as u32,
             };
             buffer.par_sort_by_key(|val| val.sequence);
+            interpolate_stop_times(&mut buffer);
             buffer.iter_mut().enumerate().for_each(|(j, st)| {
                 st.inner_idx = j as u32;
                 st.slice = stop_time_slice;
@@ -336,13 +651,22 @@ impl Repository {
 
         let stop_to_trips: Box<[Box<[u32]>]> =
             stop_to_trips.into_iter().map(|val| val.into()).collect();
-        self.stop_to_trips = stop_to_trips;
+        self.stop_to_trips = stop_to_trips.into();
+
+        if skipped_stop_times > 0 {
+            warn!("Skipped {skipped_stop_times} stop times referencing an unknown trip or stop");
+        }
+        self.load_report.skipped_stop_times = skipped_stop_times;
+        if malformed_stop_times > 0 {
+            warn!("Skipped {malformed_stop_times} stop times with malformed times");
+        }
+        self.load_report.skipped_malformed_stop_times = malformed_stop_times;
 
         debug!("Loading stop times took {:?}", now.elapsed());
         Ok(())
     }
 
-    fn generate_geo_hash(&mut self) {
+    fn generate_geo_hash(&mut self, exclude_unserved_stops: bool) {
         // Link area->stop->real world stop (stops that are linked to any trip)
         // This has to be last because it ties togheter alot
         // To save space and not having a O(n^2) operation trying to map each stop
@@ -350,22 +674,29 @@ impl Repository {
         debug!("Generating geo spatial hash...");
         let now = Instant::now();
         let mut stop_distance_lookup: HashMap<Cell, Vec<u32>> = HashMap::new();
-        self.stops.iter().for_each(|stop| {
-            let cell = stop.coordinate.to_cell();
-            stop_distance_lookup
-                .entry(cell)
-                .or_default()
-                .push(stop.index);
-        });
+        self.stops
+            .iter()
+            .filter(|stop| !exclude_unserved_stops || !self.stop_idx_is_unserved(stop.index))
+            .for_each(|stop| {
+                let cell = stop.coordinate.to_cell();
+                stop_distance_lookup
+                    .entry(cell)
+                    .or_default()
+                    .push(stop.index);
+            });
         let stop_distance_lookup: HashMap<Cell, Box<[u32]>> = stop_distance_lookup
             .into_iter()
             .map(|(cell, stops)| (cell, stops.into()))
             .collect();
-        self.stop_distance_lookup = stop_distance_lookup;
+        self.stop_distance_lookup = stop_distance_lookup.into();
         debug!("Generating geo spatial hash took {:?}", now.elapsed());
     }
 
-    fn generate_raptor_routes(&mut self, trip_to_shapes_slice: Vec<Option<Slice>>) {
+    pub(crate) fn generate_raptor_routes(
+        &mut self,
+        trip_to_shapes_slice: Vec<Option<Slice>>,
+        deterministic: bool,
+    ) {
         // Raptor mappings
         // Raptor requires each route's trips to have an identical set of stops.
         // Gtfs does not have this requirement, so we split each route
@@ -379,19 +710,66 @@ impl Repository {
         let mut route_to_raptors: Vec<Vec<u32>> = vec![Vec::new(); self.routes.len()];
         let mut stop_to_raptors: Vec<Vec<u32>> = vec![Vec::new(); self.stops.len()];
         let mut raptor_to_shapes_slice: Vec<Option<Slice>> = Vec::new();
+        let mut short_trips: Vec<u32> = Vec::new();
+        let mut zero_duration_trips: Vec<u32> = Vec::new();
+        let mut untimed_trips: Vec<u32> = Vec::new();
         self.routes.iter().for_each(|route| {
-            let trips = self.stop_times_by_route_idx(route.index);
             let mut raptor_trips: HashMap<Vec<u32>, Vec<u32>> = HashMap::new();
-            trips.into_iter().for_each(|trip| {
-                let index = trip.first().unwrap().trip_idx;
-                let signature: Vec<_> = trip.iter().map(|st| st.stop_idx).collect();
-                raptor_trips.entry(signature).or_default().push(index);
-            });
+            self.iter_stop_times_by_route_idx(route.index)
+                .for_each(|(trip_idx, trip)| {
+                    // A trip has to call at two stops to take anyone anywhere.
+                    if trip.len() < 2 {
+                        debug!(
+                            "Skipping trip {} with {} stops",
+                            self.trips[trip_idx as usize].id,
+                            trip.len()
+                        );
+                        short_trips.push(trip_idx);
+                        return;
+                    }
+                    // Without a single time there is nothing to interpolate from, the stops
+                    // are left at the `time::MAX` placeholder.
+                    if trip.iter().all(|st| st.arrival_time == time::MAX) {
+                        debug!(
+                            "Skipping trip {} without any times",
+                            self.trips[trip_idx as usize].id
+                        );
+                        untimed_trips.push(trip_idx);
+                        return;
+                    }
+                    // Every stop sharing one time is a data defect, riding it would
+                    // teleport across the whole sequence.
+                    let time = trip[0].departure_time;
+                    if trip
+                        .iter()
+                        .all(|st| st.arrival_time == time && st.departure_time == time)
+                    {
+                        debug!(
+                            "Skipping trip {} where all {} stops share the time {}",
+                            self.trips[trip_idx as usize].id,
+                            trip.len(),
+                            time.to_hms_string()
+                        );
+                        zero_duration_trips.push(trip_idx);
+                        return;
+                    }
+                    let signature: Vec<_> = trip.iter().map(|st| st.stop_idx).collect();
+                    raptor_trips.entry(signature).or_default().push(trip_idx);
+                });
+
+            let mut raptor_trips: Vec<_> = raptor_trips.into_iter().collect();
+            if deterministic {
+                raptor_trips.sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
 
             raptor_trips.into_iter().for_each(|(key, mut value)| {
                 let index = raptor_routes.len();
                 key.iter().for_each(|stop_idx| {
-                    stop_to_raptors[*stop_idx as usize].push(index as u32);
+                    // Loop routes repeat stops, only list the route once per stop.
+                    let raptors = &mut stop_to_raptors[*stop_idx as usize];
+                    if raptors.last() != Some(&(index as u32)) {
+                        raptors.push(index as u32);
+                    }
                 });
                 route_to_raptors[route.index as usize].push(index as u32);
 
@@ -409,36 +787,71 @@ impl Repository {
                 let raptor = RaptorRoute {
                     index: index as u32,
                     route_idx: route.index,
-                    // route_id: route.id.clone(),
                     stops: key.into(),
                     trips: value.into(),
                 };
                 raptor_routes.push(raptor);
             });
         });
+        let trips = Arc::make_mut(&mut self.trips);
+        raptor_routes.iter().for_each(|raptor| {
+            raptor.trips.iter().for_each(|trip_idx| {
+                trips[*trip_idx as usize].raptor_route_idx = raptor.index;
+            });
+        });
+        if !short_trips.is_empty() {
+            warn!(
+                "Skipped {} trips with fewer than two stops",
+                short_trips.len()
+            );
+        }
+        if !zero_duration_trips.is_empty() {
+            warn!(
+                "Skipped {} trips where every stop shares the same time",
+                zero_duration_trips.len()
+            );
+        }
+        if !untimed_trips.is_empty() {
+            warn!("Skipped {} trips without any times", untimed_trips.len());
+        }
+        short_trips
+            .iter()
+            .chain(&zero_duration_trips)
+            .chain(&untimed_trips)
+            .for_each(|trip_idx| {
+                trips[*trip_idx as usize].raptor_route_idx = u32::MAX;
+            });
+        self.load_report.skipped_short_trips = short_trips.len();
+        self.load_report.skipped_zero_duration_trips = zero_duration_trips.len();
+        self.load_report.skipped_untimed_trips = untimed_trips.len();
         self.raptor_routes = raptor_routes.into();
         let route_to_raptors: Box<[Box<[u32]>]> =
             route_to_raptors.into_iter().map(|val| val.into()).collect();
-        self.route_to_raptors = route_to_raptors;
+        self.route_to_raptors = route_to_raptors.into();
         self.raptor_to_shapes_slice = raptor_to_shapes_slice.into();
 
         self.stop_to_raptors = stop_to_raptors.into_iter().map(|val| val.into()).collect();
+        #[cfg(debug_assertions)]
+        if let Err(err) = self.validate_raptor_routes() {
+            panic!("Invalid raptor routes: {err}");
+        }
         debug!("Generating raptor routes took {:?}", now.elapsed());
     }
 
-    fn generate_walks(&mut self) {
+    pub(crate) fn generate_walks(&mut self) {
         debug!("Generating stop to walkable stop mapping...");
         let now = Instant::now();
-        let stops: Vec<(u32, Vec<u32>)> = self
+        let stops: Vec<(u32, Vec<(u32, Duration)>)> = self
             .stops
             .par_iter()
             .map(|sa| {
-                let nearby: Vec<u32> = self
+                let nearby: Vec<(u32, Duration)> = self
                     .stops_by_coordinate(&sa.coordinate, AVERAGE_STOP_DISTANCE)
                     .into_iter()
                     .filter_map(|sb| {
                         if sa.index != sb.index {
-                            Some(sb.index)
+                            let duration = self.walk_time_between(&sa.coordinate, &sb.coordinate);
+                            Some((sb.index, duration))
                         } else {
                             None
                         }
@@ -450,17 +863,589 @@ impl Repository {
             .collect();
 
         let mut stop_to_walk_stop: Vec<Vec<u32>> = vec![Vec::new(); self.stops.len()];
+        let mut stop_to_walk_durations: Vec<Vec<Duration>> = vec![Vec::new(); self.stops.len()];
         stops.into_iter().for_each(|(idx, stops)| {
-            stop_to_walk_stop[idx as usize].extend(stops);
+            stops.into_iter().for_each(|(stop_idx, duration)| {
+                stop_to_walk_stop[idx as usize].push(stop_idx);
+                stop_to_walk_durations[idx as usize].push(duration);
+            });
         });
 
         self.stop_to_walk_stop = stop_to_walk_stop
             .into_iter()
             .map(|val| val.into())
             .collect();
+        self.stop_to_walk_durations = stop_to_walk_durations
+            .into_iter()
+            .map(|val| val.into())
+            .collect();
         debug!(
             "Generating stop to walkable stop mapping took {:?}",
             now.elapsed()
         );
     }
 }
+
+/// Fills in stop times that were loaded without times (marked `Timepoint::Approximate`) by
+/// interpolating linearly between the surrounding timed stops of the sorted trip. Uses
+/// `distance_traveled` when all involved stops have one, otherwise the stop count.
+/// Leading and trailing stops without times copy the closest known time.
+fn interpolate_stop_times(stop_times: &mut [StopTime]) {
+    let missing = |st: &StopTime| st.arrival_time == time::MAX;
+    let known: Vec<usize> = (0..stop_times.len())
+        .filter(|&i| !missing(&stop_times[i]))
+        .collect();
+    if known.len() == stop_times.len() {
+        return;
+    }
+    let (Some(&first), Some(&last)) = (known.first(), known.last()) else {
+        return;
+    };
+
+    for i in 0..first {
+        let time = stop_times[first].arrival_time;
+        stop_times[i].arrival_time = time;
+        stop_times[i].departure_time = time;
+    }
+    for i in last + 1..stop_times.len() {
+        let time = stop_times[last].departure_time;
+        stop_times[i].arrival_time = time;
+        stop_times[i].departure_time = time;
+    }
+
+    for window in known.windows(2) {
+        let (prev, next) = (window[0], window[1]);
+        if next - prev < 2 {
+            continue;
+        }
+        let start = stop_times[prev].departure_time.as_seconds() as f32;
+        let end = stop_times[next].arrival_time.as_seconds() as f32;
+        let distances = (prev..=next)
+            .map(|i| stop_times[i].distance_traveled.map(|d| d.as_meters()))
+            .collect::<Option<Vec<f32>>>()
+            .filter(|d| d[d.len() - 1] > d[0]);
+        for i in prev + 1..next {
+            let fraction = match &distances {
+                Some(d) => (d[i - prev] - d[0]) / (d[d.len() - 1] - d[0]),
+                None => (i - prev) as f32 / (next - prev) as f32,
+            };
+            let time =
+                Time::from_seconds((start + (end - start) * fraction.clamp(0.0, 1.0)) as u32);
+            stop_times[i].arrival_time = time;
+            stop_times[i].departure_time = time;
+        }
+    }
+}
+
+#[test]
+fn precomputed_durations_test() {
+    let repository = crate::repository::sample_repository();
+    assert_eq!(
+        repository.transfer_durations.len(),
+        repository.transfers.len()
+    );
+    repository
+        .transfers
+        .iter()
+        .zip(repository.transfer_durations.iter())
+        .for_each(|(transfer, duration)| {
+            assert_eq!(transfer_duration(&repository, transfer), *duration)
+        });
+
+    repository.stops.iter().for_each(|stop| {
+        let walk_stops = &repository.stop_to_walk_stop[stop.index as usize];
+        let durations = &repository.stop_to_walk_durations[stop.index as usize];
+        assert_eq!(walk_stops.len(), durations.len());
+        walk_stops
+            .iter()
+            .zip(durations.iter())
+            .for_each(|(next_stop_idx, duration)| {
+                let next_stop = &repository.stops[*next_stop_idx as usize];
+                let distance = stop.coordinate.network_distance(&next_stop.coordinate);
+                assert_eq!(crate::raptor::time_to_walk(distance), *duration);
+            });
+    });
+}
+
+#[test]
+fn exclude_unserved_stops_test() {
+    let config = gtfs::Config {
+        exclude_unserved_stops: true,
+        ..Default::default()
+    };
+    let gtfs = GtfsReader::new()
+        .with_config(config)
+        .from_directory(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/sample"
+        ));
+    let repository = Repository::new().load_gtfs(gtfs).unwrap();
+    let depot = repository.stop_by_id("S_X").unwrap();
+    assert!(
+        repository
+            .stops_by_coordinate(&depot.coordinate, AVERAGE_STOP_DISTANCE)
+            .is_empty()
+    );
+    let central = repository.stop_by_id("ST_C").unwrap();
+    assert!(
+        repository
+            .stops_by_coordinate(&central.coordinate, AVERAGE_STOP_DISTANCE)
+            .iter()
+            .any(|stop| stop.index == central.index)
+    );
+}
+
+#[test]
+fn trip_raptor_route_idx_test() {
+    let repository = crate::repository::sample_repository();
+    repository.trips.iter().for_each(|trip| {
+        let raptor = &repository.raptor_routes[trip.raptor_route_idx as usize];
+        assert!(raptor.trips.contains(&trip.index));
+    });
+}
+
+#[test]
+fn dangling_stop_times_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        feed.append(
+            "stop_times.txt",
+            &[
+                "R1_L_0700,07:20:00,07:20:00,S_MISSING,6,,0,0,,1,,",
+                "MISSING,07:20:00,07:20:00,S_A1,1,,0,0,,1,,",
+            ],
+        );
+    });
+
+    assert_eq!(repository.load_report().skipped_stop_times, 2);
+    let trip = repository.trip_by_id("R1_L_0700").unwrap();
+    assert_eq!(repository.stop_times_by_trip_idx(trip.index).len(), 5);
+    assert_eq!(
+        repository.stop_times.len(),
+        crate::repository::sample_repository().stop_times.len()
+    );
+    assert!(
+        repository
+            .stop_times
+            .iter()
+            .enumerate()
+            .all(|(i, stop_time)| stop_time.index as usize == i)
+    );
+}
+
+#[test]
+fn short_trips_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        feed.append(
+            "trips.txt",
+            &[
+                "R1,WEEKDAY,R1_SHORT,East Terminal,,0,SH_R1",
+                "R1,WEEKDAY,R1_EMPTY,East Terminal,,0,SH_R1",
+            ],
+        );
+        feed.append(
+            "stop_times.txt",
+            &["R1_SHORT,07:20:00,07:20:00,S_A1,1,,0,0,0,1,,"],
+        );
+    });
+
+    assert_eq!(repository.load_report().skipped_short_trips, 2);
+    assert_eq!(
+        repository.raptor_routes.len(),
+        crate::repository::sample_repository().raptor_routes.len()
+    );
+    for id in ["R1_SHORT", "R1_EMPTY"] {
+        let trip = repository.trip_by_id(id).unwrap();
+        assert_eq!(trip.raptor_route_idx, u32::MAX);
+        assert!(repository.shapes_by_trip_idx(trip.index).is_none());
+        assert!(
+            repository
+                .raptor_routes
+                .iter()
+                .all(|raptor| !raptor.trips.contains(&trip.index))
+        );
+    }
+}
+
+#[test]
+fn zero_duration_trips_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        feed.append(
+            "trips.txt",
+            &["R1,WEEKDAY,R1_TELEPORT,East Terminal,,0,SH_R1"],
+        );
+        feed.append(
+            "stop_times.txt",
+            &[
+                "R1_TELEPORT,07:20:00,07:20:00,S_A1,1,,0,0,0,1,,",
+                "R1_TELEPORT,07:20:00,07:20:00,S_B,2,,0,0,0,1,,",
+                "R1_TELEPORT,07:20:00,07:20:00,S_E1,3,,0,0,0,1,,",
+            ],
+        );
+    });
+
+    assert_eq!(repository.load_report().skipped_zero_duration_trips, 1);
+    assert_eq!(repository.load_report().skipped_short_trips, 0);
+    let trip = repository.trip_by_id("R1_TELEPORT").unwrap();
+    assert_eq!(trip.raptor_route_idx, u32::MAX);
+    assert!(
+        repository
+            .raptor_routes
+            .iter()
+            .all(|raptor| !raptor.trips.contains(&trip.index))
+    );
+}
+
+#[test]
+fn stop_description_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        // Add the two columns, only filled in for Birch Street.
+        let stops: String = feed
+            .read("stops.txt")
+            .lines()
+            .map(|line| match line {
+                _ if line.starts_with("stop_id,") => format!("{line},stop_desc,stop_url\n"),
+                _ if line.starts_with("S_B,") => {
+                    format!("{line},Shelter by the bakery,https://example.com/stops/S_B\n")
+                }
+                _ => format!("{line},,\n"),
+            })
+            .collect();
+        feed.write("stops.txt", stops);
+    });
+
+    let stop = repository.stop_by_id("S_B").unwrap();
+    assert_eq!(stop.description.as_deref(), Some("Shelter by the bakery"));
+    assert_eq!(stop.url.as_deref(), Some("https://example.com/stops/S_B"));
+    let stop = repository.stop_by_id("S_D").unwrap();
+    assert_eq!(stop.description, None);
+    assert_eq!(stop.url, None);
+}
+
+#[test]
+fn load_report_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        feed.append("stop_areas.txt", &["AR_ALPHA,S_MISSING", "AR_MISSING,S_B"]);
+        feed.append(
+            "trips.txt",
+            &["R_MISSING,WEEKDAY,R_MISSING_0700,Nowhere,,0,"],
+        );
+        feed.append(
+            "transfers.txt",
+            &["S_B,S_MISSING,2,60,,", "S_B,S_D,2,60,T_MISSING,"],
+        );
+        // Rows of the dropped trip are dangling as well.
+        feed.append(
+            "stop_times.txt",
+            &[
+                "R_MISSING_0700,07:00:00,07:00:00,S_A1,1,,0,0,0,1,,",
+                "R1_L_0700,07:20:00,07:20:00,S_MISSING,9,,0,0,0,1,,",
+            ],
+        );
+    });
+
+    let report = repository.load_report();
+    assert_eq!(report.skipped_stop_areas, 2);
+    assert_eq!(report.skipped_trips, 1);
+    assert_eq!(report.skipped_transfers, 2);
+    assert_eq!(report.skipped_stop_times, 2);
+    assert_eq!(report.dropped_rows(), 7);
+    assert!(repository.trip_by_id("R_MISSING_0700").is_none());
+
+    let sample = crate::repository::sample_repository();
+    assert_eq!(report.loaded.trips, sample.trips.len());
+    assert_eq!(report.loaded.stop_times, sample.stop_times.len());
+    assert_eq!(report.loaded.transfers, sample.transfers.len());
+    let stages: Vec<_> = report
+        .stage_durations
+        .iter()
+        .map(|(stage, _)| *stage)
+        .collect();
+    assert_eq!(stages, LoadStage::ALL);
+    assert!(report.to_string().contains("dropped 7 rows"));
+}
+
+#[test]
+fn dedupe_colocated_stops_test() {
+    let feed = crate::repository::SampleFeed::new();
+    // The coach stops at its own copy of Birch Street.
+    feed.append("stops.txt", &["S_B2,Birch Street,59.33,18.02,0,,,Z1,2099"]);
+    let stop_times = feed
+        .read("stop_times.txt")
+        .lines()
+        .map(|line| {
+            if line.starts_with("R5_0703,") {
+                line.replace(",S_B,", ",S_B2,")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    feed.write("stop_times.txt", stop_times + "\n");
+    let repository = feed.load(gtfs::Config {
+        dedupe_colocated_stops: Some(Distance::from_meters(1.0)),
+        ..Default::default()
+    });
+
+    let sample = crate::repository::sample_repository();
+    assert_eq!(repository.stops.len(), sample.stops.len());
+    let birch = repository.stop_by_id("S_B").unwrap();
+    assert_eq!(repository.stop_by_id("S_B2").unwrap().index, birch.index);
+    assert_eq!(
+        repository.stop_idx_by_source_idx(sample.stops.len() as u32),
+        Some(birch.index)
+    );
+    let coach = repository.trip_by_id("R5_0703").unwrap();
+    assert_eq!(
+        repository.stop_times_by_trip_idx(coach.index)[0].stop_idx,
+        birch.index
+    );
+    // The code of the merged stop resolves to the stop it was merged into.
+    assert_eq!(
+        repository.stop_by_code("2099", None).unwrap().index,
+        birch.index
+    );
+    assert_eq!(repository.stops_by_code("2002").len(), 2);
+    // Central station and its platforms keep their relations.
+    let central = repository.stop_by_id("ST_C").unwrap();
+    assert_eq!(repository.stops_by_station(central.index).len(), 2);
+
+    let itinerary = repository
+        .router(
+            crate::raptor::Location::Stop("S_B2".into()),
+            crate::raptor::Location::Stop("S_G".into()),
+        )
+        .departure_at(crate::shared::time::Time::from_hms("07:00:00").unwrap())
+        .solve()
+        .unwrap();
+    assert!(!itinerary.legs.is_empty());
+}
+
+#[test]
+fn shape_dist_unit_test() {
+    let feed = crate::repository::SampleFeed::new();
+    // Rewrite the distance column of a file from meters to kilometers.
+    let to_kilometers = |file: &str, column: usize| {
+        let content = feed.read(file);
+        let mut lines = content.lines();
+        let mut result = format!("{}\n", lines.next().unwrap());
+        for line in lines {
+            let mut fields: Vec<String> = line.split(',').map(String::from).collect();
+            if let Ok(meters) = fields[column].parse::<f32>() {
+                fields[column] = (meters / 1000.0).to_string();
+            }
+            result.push_str(&fields.join(","));
+            result.push('\n');
+        }
+        feed.write(file, result);
+    };
+    to_kilometers("shapes.txt", 4);
+    to_kilometers("stop_times.txt", 8);
+    let repository = feed.load(gtfs::Config {
+        shape_dist_unit: gtfs::DistanceUnit::Kilometers,
+        ..Default::default()
+    });
+
+    // Every stop of the local lines up with a shape point at the same distance.
+    let trip = repository.trip_by_id("R1_L_0700").unwrap();
+    let shapes = repository.shapes_by_trip_idx(trip.index).unwrap();
+    let epsilon = Distance::from_meters(0.5);
+    for stop_time in repository.stop_times_by_trip_idx(trip.index) {
+        let distance = stop_time.distance_traveled.unwrap();
+        assert!(shapes.iter().any(|shape| {
+            shape
+                .distance_traveled
+                .is_some_and(|value| value.approx_eq(&distance, epsilon))
+        }));
+    }
+    let last = repository
+        .stop_times_by_trip_idx(trip.index)
+        .last()
+        .unwrap();
+    let sample = crate::repository::sample_repository();
+    let sample_trip = sample.trip_by_id("R1_L_0700").unwrap();
+    let sample_last = sample
+        .stop_times_by_trip_idx(sample_trip.index)
+        .last()
+        .unwrap();
+    assert!(
+        last.distance_traveled
+            .unwrap()
+            .approx_eq(&sample_last.distance_traveled.unwrap(), epsilon)
+    );
+}
+
+#[test]
+fn transfer_validation_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        feed.append(
+            "transfers.txt",
+            &["S_B,S_B,2,60,,", "S_D,S_E1,2,86400,,", "S_E1,S_D,2,-30,,"],
+        );
+    });
+
+    let report = repository.load_report();
+    assert_eq!(report.skipped_self_transfers, 1);
+    assert_eq!(report.clamped_transfer_times, 2);
+    assert!(
+        repository
+            .all_transfers()
+            .iter()
+            .all(|transfer| transfer.from_stop_idx != transfer.to_stop_idx)
+    );
+    let stop_idx = |id: &str| repository.stop_by_id(id).unwrap().index;
+    let transfer = repository
+        .transfers_between(stop_idx("S_D"), stop_idx("S_E1"))
+        .unwrap();
+    assert_eq!(transfer.min_transfer_time, Some(MAX_TRANSFER_TIME));
+    let transfer = repository
+        .transfers_between(stop_idx("S_E1"), stop_idx("S_D"))
+        .unwrap();
+    assert_eq!(transfer.min_transfer_time, Some(Duration::from_seconds(0)));
+    // The transfer indices still line up after dropping a row.
+    repository.stops.iter().for_each(|stop| {
+        repository
+            .transfers_by_stop_idx(stop.index)
+            .iter()
+            .for_each(|transfer| assert_eq!(transfer.from_stop_idx, stop.index));
+    });
+}
+
+#[test]
+fn deterministic_test() {
+    let load = || {
+        let config = gtfs::Config {
+            deterministic: true,
+            ..Default::default()
+        };
+        let gtfs = GtfsReader::new()
+            .with_config(config)
+            .from_directory(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/sample"
+            ));
+        Repository::new().load_gtfs(gtfs).unwrap()
+    };
+    let a = load();
+    let b = load();
+
+    let stop_times = |repository: &Repository| {
+        repository
+            .stop_times
+            .iter()
+            .map(|stop_time| (stop_time.index, stop_time.trip_idx, stop_time.stop_idx))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(stop_times(&a), stop_times(&b));
+
+    let shapes = |repository: &Repository| {
+        repository
+            .shapes
+            .iter()
+            .map(|shape| (shape.index, shape.sequence, shape.slice.start_idx))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(shapes(&a), shapes(&b));
+
+    let raptor_routes = |repository: &Repository| {
+        repository
+            .raptor_routes
+            .iter()
+            .map(|raptor| (raptor.route_idx, raptor.stops.clone(), raptor.trips.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(raptor_routes(&a), raptor_routes(&b));
+}
+
+#[test]
+fn load_progress_test() {
+    let gtfs = GtfsReader::new().from_directory(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/sample"
+    ));
+    let mut stages = Vec::new();
+    let repository = Repository::new()
+        .load_gtfs_with_progress(gtfs, |stage, _| stages.push(stage))
+        .unwrap();
+    assert!(!repository.raptor_routes.is_empty());
+    assert_eq!(stages, LoadStage::ALL);
+    assert_eq!(LoadStage::Walks.percent(), 100.0);
+}
+
+#[test]
+fn interpolate_stop_times_test() {
+    let repository = crate::repository::sample_repository_with(|feed| {
+        let template = feed
+            .read("trips.txt")
+            .lines()
+            .find(|line| line.contains("R1_L_0700"))
+            .unwrap()
+            .to_string();
+        feed.append(
+            "trips.txt",
+            &[
+                &template.replace("R1_L_0700", "R1_DIST"),
+                &template.replace("R1_L_0700", "R1_COUNT"),
+                &template.replace("R1_L_0700", "R1_GARBLED"),
+                &template.replace("R1_L_0700", "R1_UNTIMED"),
+            ],
+        );
+        feed.append(
+            "stop_times.txt",
+            &[
+                "R1_DIST,07:00:00,07:00:00,S_A1,1,,0,0,0,1,,",
+                "R1_DIST,,,S_B,2,,0,0,567,0,,",
+                "R1_DIST,07:06:00,07:06:00,S_C1,3,,0,0,2268,1,,",
+                "R1_COUNT,07:00:00,07:00:00,S_A1,1,,0,0,,1,,",
+                "R1_COUNT,,,S_B,2,,0,0,,0,,",
+                "R1_COUNT,,,S_C1,3,,0,0,,0,,",
+                "R1_COUNT,07:09:00,07:09:00,S_D,4,,0,0,,1,,",
+                "R1_COUNT,,,S_E1,5,,0,0,,0,,",
+                "R1_GARBLED,07:00:00,07:00:00,S_A1,1,,0,0,,1,,",
+                "R1_GARBLED,07:0x:00,,S_B,2,,0,0,,1,,",
+                "R1_GARBLED,07:06:00,07:06:00,S_C1,3,,0,0,,1,,",
+                "R1_UNTIMED,,,S_A1,1,,0,0,,0,,",
+                "R1_UNTIMED,,,S_B,2,,0,0,,0,,",
+            ],
+        );
+    });
+
+    let times = |trip_id: &str| -> Vec<(u32, bool)> {
+        let trip = repository.trip_by_id(trip_id).unwrap();
+        repository
+            .stop_times_by_trip_idx(trip.index)
+            .iter()
+            .map(|st| {
+                assert_eq!(st.arrival_time, st.departure_time);
+                (
+                    st.arrival_time.as_seconds(),
+                    matches!(st.timepoint, crate::repository::Timepoint::Exact),
+                )
+            })
+            .collect()
+    };
+    let h = 7 * 3600;
+    assert_eq!(
+        times("R1_DIST"),
+        vec![(h, true), (h + 90, false), (h + 360, true)]
+    );
+    assert_eq!(
+        times("R1_COUNT"),
+        vec![
+            (h, true),
+            (h + 180, false),
+            (h + 360, false),
+            (h + 540, true),
+            (h + 540, false)
+        ]
+    );
+
+    // A garbled time is dropped rather than guessed.
+    assert_eq!(repository.load_report().skipped_malformed_stop_times, 1);
+    assert_eq!(times("R1_GARBLED"), vec![(h, true), (h + 360, true)]);
+    // Without any time there is nothing to interpolate from.
+    assert_eq!(repository.load_report().skipped_untimed_trips, 1);
+    assert_eq!(repository.load_report().skipped_zero_duration_trips, 0);
+    let trip = repository.trip_by_id("R1_UNTIMED").unwrap();
+    assert_eq!(trip.raptor_route_idx, u32::MAX);
+}
@@ -1 +1,149 @@
 pub mod gtfs;
+
+use std::{fmt, time::Duration};
+
+/// Summary of a source loaded into a [`Repository`](crate::repository::Repository):
+/// what was loaded, what was skipped or repaired, and how long each stage took.
+#[derive(Debug, Default, Clone)]
+pub struct LoadReport {
+    /// Number of rows loaded from each table.
+    pub loaded: LoadCounts,
+    /// Number of stop areas that referenced an area or stop that does not exist.
+    pub skipped_stop_areas: usize,
+    /// Number of trips that referenced a route that does not exist.
+    pub skipped_trips: usize,
+    /// Number of transfers that referenced a stop or trip that does not exist.
+    pub skipped_transfers: usize,
+    /// Number of stop times that referenced a trip or stop that does not exist.
+    pub skipped_stop_times: usize,
+    /// Number of stop times with an arrival or departure time that is filled in but can't be
+    /// parsed. Empty times are interpolated instead, see [`Timepoint`](crate::repository::Timepoint).
+    pub skipped_malformed_stop_times: usize,
+    /// Number of transfers dropped because they start and end at the same stop.
+    pub skipped_self_transfers: usize,
+    /// Number of transfers whose `min_transfer_time` was negative or above
+    /// [`MAX_TRANSFER_TIME`](crate::repository::source::gtfs::MAX_TRANSFER_TIME) and got clamped.
+    pub clamped_transfer_times: usize,
+    /// Number of trips left out of the raptor routes because they call at fewer than two stops.
+    pub skipped_short_trips: usize,
+    /// Number of trips left out of the raptor routes because every stop shares one time.
+    pub skipped_zero_duration_trips: usize,
+    /// Number of trips left out of the raptor routes because none of their stops has a time.
+    pub skipped_untimed_trips: usize,
+    /// How long each stage took, in the order they ran.
+    pub stage_durations: Vec<(LoadStage, Duration)>,
+}
+
+impl LoadReport {
+    /// Number of rows dropped from the source, e.g. because of dangling references.
+    ///
+    /// Trips left out of the raptor routes are not counted, they are still loaded.
+    pub fn dropped_rows(&self) -> usize {
+        self.skipped_stop_areas
+            + self.skipped_trips
+            + self.skipped_transfers
+            + self.skipped_self_transfers
+            + self.skipped_stop_times
+            + self.skipped_malformed_stop_times
+    }
+
+    /// Total time spent loading.
+    pub fn duration(&self) -> Duration {
+        self.stage_durations
+            .iter()
+            .map(|(_, duration)| *duration)
+            .sum()
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let loaded = &self.loaded;
+        write!(
+            f,
+            "Loaded {} stops, {} areas, {} routes, {} services, {} shape points, {} trips, \
+             {} transfers and {} stop times in {:?}",
+            loaded.stops,
+            loaded.areas,
+            loaded.routes,
+            loaded.services,
+            loaded.shape_points,
+            loaded.trips,
+            loaded.transfers,
+            loaded.stop_times,
+            self.duration()
+        )?;
+        write!(
+            f,
+            ", dropped {} rows ({} stop areas, {} trips, {} transfers, {} self transfers, \
+             {} stop times, {} malformed stop times), clamped {} transfer times, left {} short, \
+             {} zero duration and {} untimed trips out of routing",
+            self.dropped_rows(),
+            self.skipped_stop_areas,
+            self.skipped_trips,
+            self.skipped_transfers,
+            self.skipped_self_transfers,
+            self.skipped_stop_times,
+            self.skipped_malformed_stop_times,
+            self.clamped_transfer_times,
+            self.skipped_short_trips,
+            self.skipped_zero_duration_trips,
+            self.skipped_untimed_trips
+        )
+    }
+}
+
+/// Number of rows loaded from each table, see [`LoadReport::loaded`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LoadCounts {
+    pub stops: usize,
+    pub areas: usize,
+    pub routes: usize,
+    pub services: usize,
+    pub shape_points: usize,
+    pub trips: usize,
+    pub transfers: usize,
+    pub stop_times: usize,
+}
+
+/// The stages a source goes through while being loaded, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStage {
+    Stops,
+    /// Areas and the stops they contain.
+    Areas,
+    Routes,
+    /// Trips and their shapes.
+    Trips,
+    Transfers,
+    StopTimes,
+    /// The spatial index used to find nearby stops.
+    GeoHash,
+    /// Splitting routes into raptor routes.
+    Raptor,
+    /// Walkable connections between nearby stops.
+    Walks,
+}
+
+impl LoadStage {
+    pub const ALL: [LoadStage; 9] = [
+        LoadStage::Stops,
+        LoadStage::Areas,
+        LoadStage::Routes,
+        LoadStage::Trips,
+        LoadStage::Transfers,
+        LoadStage::StopTimes,
+        LoadStage::GeoHash,
+        LoadStage::Raptor,
+        LoadStage::Walks,
+    ];
+
+    /// How far into the load this stage is when done, from 0 to 100.
+    pub fn percent(&self) -> f32 {
+        let position = LoadStage::ALL
+            .iter()
+            .position(|stage| stage == self)
+            .unwrap_or_default();
+        (position + 1) as f32 / LoadStage::ALL.len() as f32 * 100.0
+    }
+}
@@ -0,0 +1,392 @@
+use crate::{
+    repository::{Area, Repository, Route, Shape, Slice, Stop, StopTime, Transfer, Trip},
+    shared::geo::{BoundingBox, Coordinate},
+};
+use std::{collections::HashMap, sync::Arc};
+use tracing::debug;
+
+impl Repository {
+    /// Builds a standalone repository of everything touching the box spanned by `sw` and `ne`.
+    ///
+    /// Keeps every stop inside the box, every trip calling at one of them together with all
+    /// the stops that trip calls at, and the parent stations of those stops. Indices are
+    /// remapped and the raptor routes and walks are rebuilt, so the result routes on its own.
+    /// Area aliases are kept for the areas that are.
+    pub fn subgraph_for_bbox(&self, sw: Coordinate, ne: Coordinate) -> Repository {
+        let bbox = BoundingBox { min: sw, max: ne };
+
+        // --- Trips ---
+        let trips: Vec<&Trip> = self
+            .trips
+            .iter()
+            .filter(|trip| {
+                self.stop_times_by_trip_idx(trip.index)
+                    .iter()
+                    .any(|stop_time| {
+                        bbox.contains(&self.stops[stop_time.stop_idx as usize].coordinate)
+                    })
+            })
+            .collect();
+
+        // --- Stops ---
+        let mut keep_stop = vec![false; self.stops.len()];
+        self.stops
+            .iter()
+            .filter(|stop| bbox.contains(&stop.coordinate))
+            .for_each(|stop| keep_stop[stop.index as usize] = true);
+        trips.iter().for_each(|trip| {
+            self.stop_times_by_trip_idx(trip.index)
+                .iter()
+                .for_each(|stop_time| keep_stop[stop_time.stop_idx as usize] = true);
+        });
+        // Boarding areas hang off platforms which hang off stations, so walk up twice.
+        for _ in 0..2 {
+            self.stops
+                .iter()
+                .filter(|stop| keep_stop[stop.index as usize])
+                .filter_map(|stop| stop.parent_index)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .for_each(|parent_idx| keep_stop[parent_idx as usize] = true);
+        }
+
+        let mut stop_map: Vec<Option<u32>> = vec![None; self.stops.len()];
+        let mut stops: Vec<Stop> = Vec::new();
+        self.stops
+            .iter()
+            .filter(|stop| keep_stop[stop.index as usize])
+            .for_each(|stop| {
+                stop_map[stop.index as usize] = Some(stops.len() as u32);
+                stops.push(stop.clone());
+            });
+        let mut station_to_stops: Vec<Vec<u32>> = vec![Vec::new(); stops.len()];
+        stops.iter_mut().enumerate().for_each(|(i, stop)| {
+            stop.index = i as u32;
+            stop.parent_index = stop
+                .parent_index
+                .and_then(|parent_idx| stop_map[parent_idx as usize]);
+            if let Some(parent_idx) = stop.parent_index {
+                station_to_stops[parent_idx as usize].push(stop.index);
+            }
+        });
+        let source_rows = match self.source_to_stop.len() {
+            0 => self.stops.len(),
+            len => len,
+        };
+        let source_to_stop: Box<[u32]> = (0..source_rows as u32)
+            .map(|source_idx| {
+                self.stop_idx_by_source_idx(source_idx)
+                    .and_then(|stop_idx| stop_map[stop_idx as usize])
+                    .unwrap_or(u32::MAX)
+            })
+            .collect();
+        let stop_lookup: HashMap<Arc<str>, u32> = self
+            .stop_lookup
+            .iter()
+            .filter_map(|(id, stop_idx)| Some((id.clone(), stop_map[*stop_idx as usize]?)))
+            .collect();
+        let stop_code_lookup: HashMap<Arc<str>, Box<[u32]>> = self
+            .stop_code_lookup
+            .iter()
+            .map(|(code, stop_indices)| {
+                let stop_indices: Box<[u32]> = stop_indices
+                    .iter()
+                    .filter_map(|stop_idx| stop_map[*stop_idx as usize])
+                    .collect();
+                (code.clone(), stop_indices)
+            })
+            .filter(|(_, stop_indices)| !stop_indices.is_empty())
+            .collect();
+        // Only stops the source indexed spatially are indexed again.
+        let stop_distance_lookup: HashMap<super::Cell, Box<[u32]>> = self
+            .stop_distance_lookup
+            .iter()
+            .map(|(cell, stop_indices)| {
+                let stop_indices: Box<[u32]> = stop_indices
+                    .iter()
+                    .filter_map(|stop_idx| stop_map[*stop_idx as usize])
+                    .collect();
+                (*cell, stop_indices)
+            })
+            .filter(|(_, stop_indices)| !stop_indices.is_empty())
+            .collect();
+
+        // --- Areas ---
+        let mut area_map: Vec<Option<u32>> = vec![None; self.areas.len()];
+        let mut areas: Vec<Area> = Vec::new();
+        let mut area_to_stops: Vec<Box<[u32]>> = Vec::new();
+        self.areas.iter().for_each(|area| {
+            let area_stops: Box<[u32]> = self.area_to_stops[area.index as usize]
+                .iter()
+                .filter_map(|stop_idx| stop_map[*stop_idx as usize])
+                .collect();
+            if !area_stops.is_empty() {
+                area_map[area.index as usize] = Some(areas.len() as u32);
+                let mut area = area.clone();
+                area.index = areas.len() as u32;
+                areas.push(area);
+                area_to_stops.push(area_stops);
+            }
+        });
+        let stop_to_area: Box<[Option<u32>]> = self
+            .stops
+            .iter()
+            .filter(|stop| keep_stop[stop.index as usize])
+            .map(|stop| {
+                self.stop_to_area[stop.index as usize]
+                    .and_then(|area_idx| area_map[area_idx as usize])
+            })
+            .collect();
+        let area_lookup: HashMap<Arc<str>, u32> = areas
+            .iter()
+            .map(|area| (area.id.clone(), area.index))
+            .collect();
+        // Aliases of aliases resolve to the same area, so whole chains are kept.
+        let area_aliases: HashMap<Arc<str>, Arc<str>> = self
+            .area_aliases
+            .iter()
+            .filter(|(alias, _)| {
+                self.resolve_area(alias)
+                    .is_some_and(|area| area_map[area.index as usize].is_some())
+            })
+            .map(|(alias, canonical)| (alias.clone(), canonical.clone()))
+            .collect();
+
+        // --- Routes ---
+        let mut keep_route = vec![false; self.routes.len()];
+        trips
+            .iter()
+            .for_each(|trip| keep_route[trip.route_idx as usize] = true);
+        let mut route_map: Vec<Option<u32>> = vec![None; self.routes.len()];
+        let mut routes: Vec<Route> = Vec::new();
+        self.routes
+            .iter()
+            .filter(|route| keep_route[route.index as usize])
+            .for_each(|route| {
+                route_map[route.index as usize] = Some(routes.len() as u32);
+                let mut route = route.clone();
+                route.index = routes.len() as u32;
+                routes.push(route);
+            });
+        let route_lookup: HashMap<Arc<str>, u32> = routes
+            .iter()
+            .map(|route| (route.id.clone(), route.index))
+            .collect();
+
+        // --- Trips, stop times and shapes ---
+        let mut trip_map: Vec<Option<u32>> = vec![None; self.trips.len()];
+        let mut new_trips: Vec<Trip> = Vec::with_capacity(trips.len());
+        let mut route_to_trips: Vec<Vec<u32>> = vec![Vec::new(); routes.len()];
+        let mut trip_to_route: Vec<u32> = Vec::with_capacity(trips.len());
+        let mut trip_to_stop_times_slice: Vec<Slice> = Vec::with_capacity(trips.len());
+        let mut trip_to_shapes_slice: Vec<Option<Slice>> = Vec::with_capacity(trips.len());
+        let mut stop_to_trips: Vec<Vec<u32>> = vec![Vec::new(); stops.len()];
+        let mut stop_times: Vec<StopTime> = Vec::new();
+        let mut shapes: Vec<Shape> = Vec::new();
+        let mut shape_map: HashMap<u32, Slice> = HashMap::new();
+        trips.iter().for_each(|trip| {
+            let trip_idx = new_trips.len() as u32;
+            trip_map[trip.index as usize] = Some(trip_idx);
+            let route_idx = route_map[trip.route_idx as usize].unwrap();
+            route_to_trips[route_idx as usize].push(trip_idx);
+            trip_to_route.push(route_idx);
+
+            let slice = Slice {
+                start_idx: stop_times.len() as u32,
+                count: self.stop_times_by_trip_idx(trip.index).len() as u32,
+            };
+            trip_to_stop_times_slice.push(slice);
+            self.stop_times_by_trip_idx(trip.index)
+                .iter()
+                .for_each(|stop_time| {
+                    let mut stop_time = stop_time.clone();
+                    stop_time.trip_idx = trip_idx;
+                    stop_time.stop_idx = stop_map[stop_time.stop_idx as usize].unwrap();
+                    stop_time.slice = slice;
+                    stop_time.index = slice.start_idx + stop_time.inner_idx;
+                    stop_to_trips[stop_time.stop_idx as usize].push(trip_idx);
+                    stop_times.push(stop_time);
+                });
+
+            let shape_slice = self
+                .raptor_to_shapes_slice
+                .get(trip.raptor_route_idx as usize)
+                .copied()
+                .flatten()
+                .map(|source| {
+                    *shape_map.entry(source.start_idx).or_insert_with(|| {
+                        let slice = Slice {
+                            start_idx: shapes.len() as u32,
+                            count: source.count,
+                        };
+                        let start = source.start_idx as usize;
+                        let end = start + source.count as usize;
+                        shapes.extend(self.shapes[start..end].iter().map(|shape| {
+                            let mut shape = shape.clone();
+                            shape.slice = slice;
+                            shape.index = slice.start_idx + shape.inner_idx;
+                            shape
+                        }));
+                        slice
+                    })
+                });
+            trip_to_shapes_slice.push(shape_slice);
+
+            let mut trip = (*trip).clone();
+            trip.index = trip_idx;
+            trip.route_idx = route_idx;
+            new_trips.push(trip);
+        });
+        let trip_lookup: HashMap<Arc<str>, u32> = new_trips
+            .iter()
+            .map(|trip| (trip.id.clone(), trip.index))
+            .collect();
+
+        // --- Transfers ---
+        let mut transfers: Vec<Transfer> = Vec::new();
+        let mut transfer_durations = Vec::new();
+        let mut stop_to_transfers: Vec<Vec<u32>> = vec![Vec::new(); stops.len()];
+        self.transfers
+            .iter()
+            .zip(self.transfer_durations.iter())
+            .for_each(|(transfer, duration)| {
+                let remap_trip = |trip_idx: Option<u32>| match trip_idx {
+                    Some(trip_idx) => trip_map[trip_idx as usize].map(Some),
+                    None => Some(None),
+                };
+                let (
+                    Some(from_stop_idx),
+                    Some(to_stop_idx),
+                    Some(from_trip_idx),
+                    Some(to_trip_idx),
+                ) = (
+                    stop_map[transfer.from_stop_idx as usize],
+                    stop_map[transfer.to_stop_idx as usize],
+                    remap_trip(transfer.from_trip_idx),
+                    remap_trip(transfer.to_trip_idx),
+                )
+                else {
+                    return;
+                };
+                stop_to_transfers[from_stop_idx as usize].push(transfers.len() as u32);
+                transfers.push(Transfer {
+                    from_stop_idx,
+                    to_stop_idx,
+                    from_trip_idx,
+                    to_trip_idx,
+                    min_transfer_time: transfer.min_transfer_time,
+                });
+                transfer_durations.push(*duration);
+            });
+
+        let mut repository = Repository {
+            stops: stops.into(),
+            areas: areas.into(),
+            routes: routes.into(),
+            trips: new_trips.into(),
+            stop_times: stop_times.into(),
+            transfers: transfers.into(),
+            shapes: shapes.into(),
+            services: self.services.clone(),
+            stop_lookup: stop_lookup.into(),
+            stop_code_lookup: stop_code_lookup.into(),
+            trip_lookup: trip_lookup.into(),
+            area_lookup: area_lookup.into(),
+            area_aliases: area_aliases.into(),
+            route_lookup: route_lookup.into(),
+            service_lookup: self.service_lookup.clone(),
+            stop_distance_lookup: stop_distance_lookup.into(),
+            route_to_trips: route_to_trips.into_iter().map(|val| val.into()).collect(),
+            trip_to_route: trip_to_route.into(),
+            area_to_stops: area_to_stops.into(),
+            stop_to_area: stop_to_area.into(),
+            station_to_stops: station_to_stops.into_iter().map(|val| val.into()).collect(),
+            stop_to_transfers: stop_to_transfers
+                .into_iter()
+                .map(|val| val.into())
+                .collect(),
+            stop_to_trips: stop_to_trips.into_iter().map(|val| val.into()).collect(),
+            trip_to_stop_times_slice: trip_to_stop_times_slice.into(),
+            transfer_durations: transfer_durations.into(),
+            source_to_stop: source_to_stop.into(),
+            ..Default::default()
+        };
+        repository.generate_raptor_routes(trip_to_shapes_slice, true);
+        repository.generate_walks();
+        debug!(
+            "Subgraph kept {} of {} stops and {} of {} trips",
+            repository.stops.len(),
+            self.stops.len(),
+            repository.trips.len(),
+            self.trips.len()
+        );
+        repository
+    }
+}
+
+#[test]
+fn subgraph_for_bbox_test() {
+    use crate::{
+        raptor::{Itinerary, LegType, Location},
+        shared::time::Time,
+    };
+
+    let repository = crate::repository::sample_repository()
+        .with_area_alias("AR_OLD_ALPHA", "AR_ALPHA")
+        .with_area_alias("AR_OLDER_ALPHA", "AR_OLD_ALPHA");
+    // Around the western half of the Blue Line, S_A1 to S_C2.
+    let subgraph =
+        repository.subgraph_for_bbox(Coordinate::new(59.32, 17.99), Coordinate::new(59.34, 18.05));
+    assert!(subgraph.stop_by_id("S_A1").is_some());
+    // Reached by trips entering the box.
+    assert!(subgraph.stop_by_id("S_E1").is_some());
+    assert!(subgraph.stop_by_id("S_G").is_some());
+    assert!(subgraph.stop_by_id("S_L1").is_none());
+    assert!(subgraph.trip_by_id("R4_0700").is_none());
+    assert!(subgraph.stops.len() < repository.stops.len());
+    let area = subgraph.area_by_id("AR_OLDER_ALPHA").unwrap();
+    assert_eq!(&*area.id, "AR_ALPHA");
+    // Positions in the source feed still resolve, to nothing for the dropped stops.
+    let lake =
+        repository.subgraph_for_bbox(Coordinate::new(59.48, 17.99), Coordinate::new(59.52, 18.04));
+    let source_idx = |id: &str| repository.stop_by_id(id).unwrap().index;
+    assert_eq!(
+        lake.stop_idx_by_source_idx(source_idx("S_L1")),
+        Some(lake.stop_by_id("S_L1").unwrap().index)
+    );
+    assert_eq!(lake.stop_idx_by_source_idx(source_idx("S_A1")), None);
+    assert!(lake.stop_by_code("1001", None).is_none());
+    assert!(lake.stops_by_code("2002").is_empty());
+    assert_eq!(
+        subgraph.stop_by_code("1001", None).unwrap().id,
+        subgraph.stop_by_id("S_A1").unwrap().id
+    );
+    assert!(lake.area_by_id("AR_OLD_ALPHA").is_none());
+    assert!(lake.area_aliases.is_empty());
+
+    let summarize = |repository: &Repository, itinerary: Itinerary| -> Vec<String> {
+        itinerary
+            .legs
+            .iter()
+            .map(|leg| {
+                let trip = match leg.leg_type {
+                    LegType::Transit(trip_idx) => repository.trips[trip_idx as usize].id.clone(),
+                    _ => "-".into(),
+                };
+                format!(
+                    "{:?} {:?} {:?} {:?} {trip}",
+                    leg.from, leg.to, leg.departue_time, leg.arrival_time
+                )
+            })
+            .collect()
+    };
+    let solve = |repository: &Repository| {
+        let itinerary = repository
+            .router(Location::Stop("S_A1".into()), Location::Stop("S_C2".into()))
+            .departure_at(Time::from_hms("07:00:00").unwrap())
+            .solve()
+            .unwrap();
+        summarize(repository, itinerary)
+    };
+    assert_eq!(solve(&subgraph), solve(&repository));
+}
@@ -1,3 +1,4 @@
+use super::ScoreAlgorithm;
 use std::{cmp, mem::swap};
 
 pub(crate) fn distance(s1_in: &str, s2_in: &str) -> usize {
@@ -43,14 +44,97 @@ pub(crate) fn distance(s1_in: &str, s2_in: &str) -> usize {
     matrix[s1_len][s2_len]
 }
 
+/// Optimal string alignment distance, Levenshtein where swapping two adjacent characters
+/// counts as a single edit.
+pub(crate) fn damerau_distance(s1: &str, s2: &str) -> usize {
+    if s1 == s2 {
+        return 0;
+    }
+    let a: Vec<char> = s1.chars().collect();
+    let b: Vec<char> = s2.chars().collect();
+
+    let mut matrix = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    (0..=a.len()).for_each(|i| matrix[i][0] = i);
+    (0..=b.len()).for_each(|j| matrix[0][j] = j);
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let sub_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = cmp::min(
+                cmp::min(matrix[i - 1][j] + 1, matrix[i][j - 1] + 1),
+                matrix[i - 1][j - 1] + sub_cost,
+            );
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = cmp::min(best, matrix[i - 2][j - 2] + 1);
+            }
+            matrix[i][j] = best;
+        }
+    }
+    matrix[a.len()][b.len()]
+}
+
+/// Jaro-Winkler similarity between `0.0` and `1.0`, favouring strings sharing a prefix.
+pub(crate) fn jaro_winkler(s1: &str, s2: &str) -> f64 {
+    if s1 == s2 {
+        return 1.0;
+    }
+    let a: Vec<char> = s1.chars().collect();
+    let b: Vec<char> = s2.chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let window = (cmp::max(a.len(), b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0;
+    for (i, ac) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = cmp::min(i + window + 1, b.len());
+        for j in start..end {
+            if !b_matched[j] && b[j] == *ac {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let a_order = a
+        .iter()
+        .zip(&a_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let b_order = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, m)| **m)
+        .map(|(c, _)| c);
+    let transpositions = a_order.zip(b_order).filter(|(x, y)| x != y).count() / 2;
+
+    let m = matches as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
 pub(crate) fn score(needle: &str, hay: &str) -> f64 {
+    score_with(needle, hay, ScoreAlgorithm::default())
+}
+
+/// Like [`score`] but comparing tokens with `algorithm`.
+pub(crate) fn score_with(needle: &str, hay: &str, algorithm: ScoreAlgorithm) -> f64 {
     let needle_tokens: Vec<_> = needle.split_whitespace().collect();
     let hay_tokens: Vec<_> = hay.split_whitespace().collect();
     let tokens = needle_tokens.len();
     let runs = cmp::min(needle_tokens.len(), hay_tokens.len());
     let mut score: f64 = 0.0;
     for i in 0..runs {
-        score += score_inner(needle_tokens[i], hay_tokens[i]);
+        score += score_inner(needle_tokens[i], hay_tokens[i], algorithm);
     }
 
     if score == 0.0 {
@@ -60,12 +144,30 @@ pub(crate) fn score(needle: &str, hay: &str) -> f64 {
     }
 }
 
-fn score_inner(s1: &str, s2: &str) -> f64 {
-    let dist = distance(s1, s2);
+/// Like [`score`] but a hay also matches on any single token of the needle, so a needle
+/// mixing several things (e.g. `"4 central"`) matches each of them.
+pub(crate) fn score_any(needle: &str, hay: &str) -> f64 {
+    let tokens: Vec<_> = needle.split_whitespace().collect();
+    let full = score(needle, hay);
+    if tokens.len() < 2 {
+        return full;
+    }
+    tokens
+        .into_iter()
+        .map(|token| score(token, hay))
+        .fold(full, f64::max)
+}
+
+fn score_inner(s1: &str, s2: &str, algorithm: ScoreAlgorithm) -> f64 {
+    let dist = match algorithm {
+        ScoreAlgorithm::Levenshtein => distance(s1, s2),
+        ScoreAlgorithm::DamerauLevenshtein => damerau_distance(s1, s2),
+        ScoreAlgorithm::JaroWinkler => return jaro_winkler(s1, s2),
+    };
     if dist == 0 {
         1.0
     } else {
-        1.0 - (distance(s1, s2) as f64 / cmp::max(s1.chars().count(), s2.chars().count()) as f64)
+        1.0 - (dist as f64 / cmp::max(s1.chars().count(), s2.chars().count()) as f64)
     }
 }
 
@@ -146,3 +248,27 @@ fn fuzzy_longer_sequence() {
     let dist = distance("intention", "execution");
     assert_eq!(dist, 5);
 }
+
+#[test]
+fn fuzzy_damerau_transposition() {
+    assert_eq!(distance("statino", "station"), 2);
+    assert_eq!(damerau_distance("statino", "station"), 1);
+    assert_eq!(damerau_distance("intention", "execution"), 5);
+    assert_eq!(damerau_distance("", "abc"), 3);
+}
+
+#[test]
+fn fuzzy_jaro_winkler() {
+    assert_eq!(jaro_winkler("station", "station"), 1.0);
+    assert_eq!(jaro_winkler("abc", ""), 0.0);
+    assert!((jaro_winkler("martha", "marhta") - 0.961).abs() < 0.001);
+    assert!(jaro_winkler("statino", "station") > jaro_winkler("statino", "central"));
+}
+
+#[test]
+fn fuzzy_score_with_algorithm() {
+    let levenshtein = score_with("statino", "station", ScoreAlgorithm::Levenshtein);
+    let damerau = score_with("statino", "station", ScoreAlgorithm::DamerauLevenshtein);
+    assert!(damerau > levenshtein);
+    assert_eq!(score("statino", "station"), levenshtein);
+}
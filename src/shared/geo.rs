@@ -11,9 +11,16 @@ use thiserror::Error;
 
 pub const AVERAGE_STOP_DISTANCE: Distance = Distance::from_meters(500.0);
 pub(crate) const LONGITUDE_DISTANCE: Distance = Distance::from_meters(111_320.0);
+/// Tolerance used when comparing distances against a radius, so floating point
+/// rounding does not decide whether a stop right at the edge is included.
+pub const DISTANCE_EPSILON: Distance = Distance::from_meters(0.01);
 pub(crate) const LATITUDE_DISTANCE: Distance = Distance::from_meters(110_540.0);
+const METERS_PER_MILE: f32 = 1609.344;
+const METERS_PER_FOOT: f32 = 0.3048;
 
-#[derive(Debug, Clone, Copy, Default)]
+/// A distance stored in meters, serialized as a plain number of meters.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Distance(f32);
 
 impl PartialEq for Distance {
@@ -79,6 +86,70 @@ impl Distance {
     pub const fn as_kilometers(&self) -> f32 {
         self.0 / 1000.0
     }
+
+    pub const fn from_miles(distance: f32) -> Self {
+        Self(distance * METERS_PER_MILE)
+    }
+
+    pub const fn from_feet(distance: f32) -> Self {
+        Self(distance * METERS_PER_FOOT)
+    }
+
+    pub const fn as_miles(&self) -> f32 {
+        self.0 / METERS_PER_MILE
+    }
+
+    pub const fn as_feet(&self) -> f32 {
+        self.0 / METERS_PER_FOOT
+    }
+
+    /// Returns true if the two distances differ by no more than `epsilon`.
+    pub fn approx_eq(&self, other: &Self, epsilon: Distance) -> bool {
+        (self.0 - other.0).abs() <= epsilon.0
+    }
+}
+
+/// Converts a compass bearing in degrees to one of the eight cardinal directions (e.g. `"NE"`).
+pub fn cardinal(bearing: f64) -> &'static str {
+    const DIRECTIONS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    let index = (bearing.rem_euclid(360.0) / 45.0).round() as usize % DIRECTIONS.len();
+    DIRECTIONS[index]
+}
+
+/// The smallest latitude/longitude aligned box containing a set of coordinates.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    /// South-west corner.
+    pub min: Coordinate,
+    /// North-east corner.
+    pub max: Coordinate,
+}
+
+impl BoundingBox {
+    /// Returns `None` if there are no coordinates.
+    pub fn from_coordinates(coordinates: impl IntoIterator<Item = Coordinate>) -> Option<Self> {
+        coordinates.into_iter().fold(None, |bbox, coordinate| {
+            let bbox = bbox.unwrap_or(BoundingBox {
+                min: coordinate,
+                max: coordinate,
+            });
+            Some(BoundingBox {
+                min: Coordinate::new(
+                    bbox.min.latitude.min(coordinate.latitude),
+                    bbox.min.longitude.min(coordinate.longitude),
+                ),
+                max: Coordinate::new(
+                    bbox.max.latitude.max(coordinate.latitude),
+                    bbox.max.longitude.max(coordinate.longitude),
+                ),
+            })
+        })
+    }
+
+    pub fn contains(&self, coordinate: &Coordinate) -> bool {
+        (self.min.latitude..=self.max.latitude).contains(&coordinate.latitude)
+            && (self.min.longitude..=self.max.longitude).contains(&coordinate.longitude)
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -188,6 +259,51 @@ impl Coordinate {
         Distance::from_meters(self.euclidean_distance(coord).as_meters() * CIRCUITY_FACTOR)
     }
 
+    /// Initial compass bearing in degrees (0 is north, 90 is east) when heading towards `coord`.
+    pub fn bearing(&self, coord: &Self) -> f64 {
+        let lat_a = (self.latitude as f64).to_radians();
+        let lat_b = (coord.latitude as f64).to_radians();
+        let dist_lon = (coord.longitude as f64 - self.longitude as f64).to_radians();
+        let y = dist_lon.sin() * lat_b.cos();
+        let x = lat_a.cos() * lat_b.sin() - lat_a.sin() * lat_b.cos() * dist_lon.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// The point reached after traveling `distance` along a great circle, starting out
+    /// at the compass `bearing` in degrees, the inverse of [`Coordinate::bearing`].
+    pub fn destination(&self, bearing: f64, distance: Distance) -> Self {
+        const R: f64 = 6_371_000.0;
+        let angular = distance.as_meters() as f64 / R;
+        let bearing = bearing.to_radians();
+        let lat = (self.latitude as f64).to_radians();
+        let lon = (self.longitude as f64).to_radians();
+        let dest_lat =
+            (lat.sin() * angular.cos() + lat.cos() * angular.sin() * bearing.cos()).asin();
+        let dest_lon = lon
+            + (bearing.sin() * angular.sin() * lat.cos())
+                .atan2(angular.cos() - lat.sin() * dest_lat.sin());
+        Self {
+            latitude: dest_lat.to_degrees() as f32,
+            longitude: ((dest_lon.to_degrees() + 540.0) % 360.0 - 180.0) as f32,
+        }
+    }
+
+    /// The point halfway to `coord`, see [`Coordinate::interpolate`].
+    pub fn midpoint(&self, coord: &Self) -> Self {
+        self.interpolate(coord, 0.5)
+    }
+
+    /// The point a fraction `t` of the way to `coord`, `0.0` is `self` and `1.0` is `coord`.
+    ///
+    /// Interpolates linearly in latitude and longitude, which is accurate enough at city scale.
+    pub fn interpolate(&self, coord: &Self, t: f64) -> Self {
+        let lerp = |a: f32, b: f32| (a as f64 + (b as f64 - a as f64) * t) as f32;
+        Self {
+            latitude: lerp(self.latitude, coord.latitude),
+            longitude: lerp(self.longitude, coord.longitude),
+        }
+    }
+
     pub fn to_cell(&self) -> Cell {
         let x = (self.longitude * LONGITUDE_DISTANCE.as_meters()
             / AVERAGE_STOP_DISTANCE.as_meters()) as i32;
@@ -212,6 +328,17 @@ fn distance_test() {
     assert!((d.as_kilometers() - 343_000.0).abs() > 500.0);
 }
 
+#[test]
+fn interpolate_test() {
+    let a = Coordinate::new(59.33, 18.0);
+    let b = Coordinate::new(59.35, 18.04);
+    let midpoint = a.midpoint(&b);
+    assert!((midpoint.latitude - 59.34).abs() < 1e-5);
+    assert!((midpoint.longitude - 18.02).abs() < 1e-5);
+    assert_eq!(a.interpolate(&b, 0.0), a);
+    assert_eq!(a.interpolate(&b, 1.0), b);
+}
+
 #[test]
 fn distance_eq_test() {
     let dist_a = Distance::from_meters(1000.0);
@@ -219,9 +346,73 @@ fn distance_eq_test() {
     assert_eq!(dist_a, dist_b)
 }
 
+#[test]
+fn imperial_distance_test() {
+    let mile = Distance::from_miles(1.0);
+    assert!((mile.as_meters() - 1609.344).abs() < 1e-3);
+    assert!((mile.as_miles() - 1.0).abs() < 1e-6);
+    assert!((mile.as_feet() - 5280.0).abs() < 1e-2);
+    assert!((Distance::from_feet(5280.0).as_miles() - 1.0).abs() < 1e-6);
+}
+
 #[test]
 fn distance_cmp_test() {
     let dist_a = Distance::from_meters(1000.0);
     let dist_b = Distance::from_kilometers(0.5);
     assert!(dist_a > dist_b)
 }
+
+#[test]
+fn distance_approx_eq_test() {
+    let dist_a = Distance::from_meters(1000.0);
+    let dist_b = Distance::from_meters(1000.005);
+    assert!(dist_a.approx_eq(&dist_b, DISTANCE_EPSILON));
+    assert!(!dist_a.approx_eq(&Distance::from_meters(1000.1), DISTANCE_EPSILON));
+}
+
+#[test]
+fn bearing_test() {
+    let origin = Coordinate::new(59.33, 18.0);
+    let east = origin.bearing(&Coordinate::new(59.33, 18.01));
+    assert!((east - 90.0).abs() < 0.1);
+    assert_eq!(cardinal(east), "E");
+    let north = origin.bearing(&Coordinate::new(59.34, 18.0));
+    assert!(north.abs() < 0.1);
+    assert_eq!(cardinal(north), "N");
+    assert_eq!(
+        cardinal(origin.bearing(&Coordinate::new(59.32, 17.98))),
+        "SW"
+    );
+    assert_eq!(cardinal(350.0), "N");
+}
+
+#[test]
+fn destination_test() {
+    let origin = Coordinate::new(59.33, 18.0);
+    let distance = Distance::from_meters(1000.0);
+    let north = origin.destination(0.0, distance);
+    assert!(north.latitude > origin.latitude);
+    assert!((north.longitude - origin.longitude).abs() < 1e-5);
+    let south = origin.destination(180.0, distance);
+    assert!(south.latitude < origin.latitude);
+
+    let north_east = origin.destination(45.0, distance);
+    assert!(
+        origin
+            .euclidean_distance(&north_east)
+            .approx_eq(&distance, Distance::from_meters(1.0))
+    );
+    assert!((origin.bearing(&north_east) - 45.0).abs() < 0.1);
+    // Crossing the antimeridian wraps around to negative longitudes.
+    let wrapped = Coordinate::new(0.0, 179.999).destination(90.0, distance);
+    assert!(wrapped.longitude < 0.0);
+}
+
+#[test]
+fn distance_serde_test() {
+    let distance = Distance::from_kilometers(1.5);
+    let json = serde_json::to_string(&distance).unwrap();
+    assert_eq!(json, "1500.0");
+    let parsed: Distance = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, distance);
+}
@@ -14,8 +14,48 @@ pub trait Identifiable {
     fn normalized_name(&self) -> &str;
 }
 
+impl<T: Identifiable + ?Sized> Identifiable for &T {
+    fn id(&self) -> &str {
+        (**self).id()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn normalized_name(&self) -> &str {
+        (**self).normalized_name()
+    }
+}
+
+/// How names are compared against a needle when fuzzy searching.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreAlgorithm {
+    /// Plain edit distance.
+    #[default]
+    Levenshtein,
+    /// Edit distance where swapping two adjacent characters is a single edit,
+    /// forgiving typos like `"statino"`.
+    DamerauLevenshtein,
+    /// Similarity weighted towards a shared prefix, suited to short queries.
+    JaroWinkler,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SearchConfig {
+    pub algorithm: ScoreAlgorithm,
+}
+
 /// Generic fuzzy search function built for multithreaded searching.
 pub fn search<'a, T>(needle: &'a str, haystack: &'a [T]) -> Vec<&'a T>
+where
+    T: Send + Sync + Identifiable,
+{
+    search_with(needle, haystack, &SearchConfig::default())
+}
+
+/// Like [`search`] but scoring with the algorithm picked in `config`.
+pub fn search_with<'a, T>(needle: &'a str, haystack: &'a [T], config: &SearchConfig) -> Vec<&'a T>
 where
     T: Send + Sync + Identifiable,
 {
@@ -23,7 +63,8 @@ where
     let mut results: Vec<(&T, f64)> = haystack
         .par_iter()
         .filter_map(|hay| {
-            let score = fuzzy::score(&normalized_needle, hay.normalized_name());
+            let score =
+                fuzzy::score_with(&normalized_needle, hay.normalized_name(), config.algorithm);
             if score > 0.1 {
                 Some((hay, score))
             } else {
@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 use std::{
     fmt::Display,
     ops::{Add, AddAssign, Sub, SubAssign},
+    str::FromStr,
 };
+use thiserror::Error;
 
 pub const MIN: Time = Time(0);
 pub const MAX: Time = Time(u32::MAX);
@@ -82,18 +84,69 @@ impl Time {
         format!("{:02}:{:02}:{:02}", h, m, s)
     }
 
+    /// Parses `H:MM:SS`, where each field may be surrounded by whitespace (including the
+    /// `\r` left over from CRLF files) and minutes and seconds may be a single digit.
     pub fn from_hms(time: &str) -> Option<Self> {
         const HOUR_TO_SEC: u32 = 60 * 60;
         const MINUTE_TO_SEC: u32 = 60;
         let mut split = time.split(':');
-        let hours: u32 = split.next()?.parse().ok()?;
+        let hours = parse_hms_field(split.next()?, usize::MAX)?;
         let hours = hours * HOUR_TO_SEC;
-        let minutes: u32 = split.next()?.parse().ok()?;
+        let minutes = parse_hms_field(split.next()?, 2)?;
         let minutes = minutes * MINUTE_TO_SEC;
-        let seconds: u32 = split.next()?.parse().ok()?;
+        let seconds = parse_hms_field(split.next()?, 2)?;
         let seconds = hours + minutes + seconds;
         Some(Self(seconds))
     }
+
+    /// Adds a [`Duration`], returning `None` instead of wrapping around on overflow.
+    pub const fn checked_add(self, duration: Duration) -> Option<Self> {
+        match self.0.checked_add(duration.0) {
+            Some(secs) => Some(Self(secs)),
+            None => None,
+        }
+    }
+
+    /// Subtracts a [`Duration`], returning `None` instead of wrapping around on underflow.
+    pub const fn checked_sub(self, duration: Duration) -> Option<Self> {
+        match self.0.checked_sub(duration.0) {
+            Some(secs) => Some(Self(secs)),
+            None => None,
+        }
+    }
+}
+
+#[test]
+fn round_up_to_test() {
+    let minute = Duration::from_minutes(1);
+    assert_eq!(Duration::from_seconds(47).round_up_to(minute), minute);
+    assert_eq!(Duration::from_seconds(60).round_up_to(minute), minute);
+    assert_eq!(
+        Duration::from_seconds(61).round_up_to(minute),
+        Duration::from_minutes(2)
+    );
+    assert_eq!(
+        Duration::from_seconds(47).round_up_to(Duration::from_seconds(0)),
+        Duration::from_seconds(47)
+    );
+}
+
+#[test]
+fn checked_arithmetic_test() {
+    let time = Time::from_seconds(u32::MAX - 10);
+    assert_eq!(time.checked_add(Duration::from_days(1)), None);
+    assert_eq!(
+        time.checked_add(Duration::from_seconds(10)),
+        Some(Time::from_seconds(u32::MAX))
+    );
+    assert_eq!(
+        Time::from_seconds(10).checked_sub(Duration::from_minutes(1)),
+        None
+    );
+    assert_eq!(
+        Time::from_seconds(70).checked_sub(Duration::from_minutes(1)),
+        Some(Time::from_seconds(10))
+    );
 }
 
 #[test]
@@ -130,6 +183,15 @@ fn parse_unparse_5() {
     let stime = Time::from_hms(time).unwrap();
     assert_eq!(time, stime.to_hms_string())
 }
+/// Parses a trimmed field of at most `max_digits` ASCII digits.
+fn parse_hms_field(field: &str, max_digits: usize) -> Option<u32> {
+    let field = field.trim();
+    if field.is_empty() || field.len() > max_digits || !field.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    field.parse().ok()
+}
+
 #[test]
 fn valid_time_test_1() {
     let time = "00:00:00";
@@ -165,6 +227,28 @@ fn invalid_time_test_2() {
     assert!(Time::from_hms(time).is_none())
 }
 
+#[test]
+fn invalid_time_test_3() {
+    assert!(Time::from_hms("08:005:03").is_none());
+    assert!(Time::from_hms("08:+5:03").is_none());
+    assert!(Time::from_hms("08: :03").is_none());
+}
+
+#[test]
+fn single_digit_time_test() {
+    assert_eq!(
+        Time::from_hms("8:5:3").unwrap().as_seconds(),
+        8 * 3600 + 5 * 60 + 3
+    );
+}
+
+#[test]
+fn trimmed_time_test() {
+    let expected = 8 * 3600 + 5 * 60 + 3;
+    assert_eq!(Time::from_hms(" 08:05:03 ").unwrap().as_seconds(), expected);
+    assert_eq!(Time::from_hms("08:05:03\r").unwrap().as_seconds(), expected);
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Duration(u32);
 
@@ -188,6 +272,83 @@ impl Duration {
     pub const fn as_seconds(&self) -> u32 {
         self.0
     }
+
+    /// Rounds up to the nearest multiple of `unit`, a zero `unit` leaves the duration as is.
+    pub const fn round_up_to(&self, unit: Duration) -> Duration {
+        if unit.0 == 0 {
+            return *self;
+        }
+        Self(self.0.div_ceil(unit.0) * unit.0)
+    }
+
+    /// Formats the duration compactly, leaving out any zero components
+    /// (e.g. `23m` rather than `0h 23m 00s`). The output can be parsed back with [`FromStr`].
+    pub fn humanize(&self) -> String {
+        let h = self.0 / 3600;
+        let m = (self.0 % 3600) / 60;
+        let s = self.0 % 60;
+        if self.0 == 0 {
+            return "0s".into();
+        }
+        let mut value = String::new();
+        if h > 0 {
+            value.push_str(&format!("{h}h"));
+        }
+        if m > 0 {
+            value.push_str(&format!("{m}m"));
+        }
+        if s > 0 {
+            value.push_str(&format!("{s}s"));
+        }
+        value
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ParseDurationError {
+    #[error("Invalid format")]
+    InvalidFormat,
+    #[error("Invalid unit: {0}")]
+    InvalidUnit(char),
+    #[error("Duration is too large")]
+    Overflow,
+}
+
+impl FromStr for Duration {
+    type Err = ParseDurationError;
+
+    /// Parses durations written as a sequence of `<number><unit>` components,
+    /// where the unit is one of `h`, `m` or `s` (e.g. `90s`, `15m` or `1h30m`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut seconds: u32 = 0;
+        let mut number: Option<u32> = None;
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            if let Some(digit) = c.to_digit(10) {
+                let value = number
+                    .unwrap_or_default()
+                    .checked_mul(10)
+                    .and_then(|value| value.checked_add(digit))
+                    .ok_or(ParseDurationError::Overflow)?;
+                number = Some(value);
+            } else {
+                let value = number.take().ok_or(ParseDurationError::InvalidFormat)?;
+                let unit = match c {
+                    'h' => 60 * 60,
+                    'm' => 60,
+                    's' => 1,
+                    _ => return Err(ParseDurationError::InvalidUnit(c)),
+                };
+                seconds = value
+                    .checked_mul(unit)
+                    .and_then(|value| seconds.checked_add(value))
+                    .ok_or(ParseDurationError::Overflow)?;
+            }
+        }
+        if number.is_some() || s.trim().is_empty() {
+            return Err(ParseDurationError::InvalidFormat);
+        }
+        Ok(Self(seconds))
+    }
 }
 
 impl From<u32> for Duration {
@@ -229,3 +390,47 @@ impl AddAssign for Duration {
         self.0 += rhs.0
     }
 }
+
+#[test]
+fn parse_duration_seconds_test() {
+    let duration: Duration = "90s".parse().unwrap();
+    assert_eq!(duration.as_seconds(), 90);
+}
+
+#[test]
+fn parse_duration_minutes_test() {
+    let duration: Duration = "15m".parse().unwrap();
+    assert_eq!(duration, Duration::from_minutes(15));
+}
+
+#[test]
+fn parse_duration_hours_minutes_test() {
+    let duration: Duration = "1h30m".parse().unwrap();
+    assert_eq!(duration, Duration::from_minutes(90));
+}
+
+#[test]
+fn parse_duration_invalid_test() {
+    assert!("".parse::<Duration>().is_err());
+    assert!("15".parse::<Duration>().is_err());
+    assert!("15x".parse::<Duration>().is_err());
+    assert!("h".parse::<Duration>().is_err());
+}
+
+#[test]
+fn humanize_duration_test() {
+    assert_eq!(Duration::from_minutes(23).humanize(), "23m");
+    assert_eq!(Duration::from_seconds(0).humanize(), "0s");
+    assert_eq!(Duration::from_seconds(3605).humanize(), "1h5s");
+}
+
+#[test]
+fn humanize_round_trip_test() {
+    [0, 59, 90, 900, 3600, 5400, 86_399]
+        .iter()
+        .for_each(|secs| {
+            let duration = Duration::from_seconds(*secs);
+            let parsed: Duration = duration.humanize().parse().unwrap();
+            assert_eq!(parsed, duration);
+        });
+}
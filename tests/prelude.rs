@@ -0,0 +1,26 @@
+use blaise::prelude::*;
+
+#[test]
+fn prelude_route_test() {
+    let gtfs = Gtfs::new().from_directory(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/sample"
+    ));
+    let repository: Repository = Repository::new().load_gtfs(gtfs).unwrap();
+    let mut allocator = Allocator::new(&repository);
+    let from: Location = repository.stop_by_id("S_A1").unwrap().into();
+    let to: Location = Coordinate::new(59.37, 18.04).into();
+    let result: Result<Itinerary, RaptorError> = repository
+        .router(from, to)
+        .departure_at(Time::from_hms("07:00:00").unwrap())
+        .walk_distance(Distance::from_meters(200.0))
+        .solve_with_allocator(&mut allocator);
+    let itinerary = result.unwrap();
+    assert!(!itinerary.legs.is_empty());
+    let travel_time = Duration::from_seconds(
+        (itinerary.legs.last().unwrap().arrival_time - itinerary.legs[0].departue_time)
+            .as_seconds(),
+    );
+    assert!(travel_time > Duration::from_minutes(0));
+    let _: Option<GtfsError> = None;
+}